@@ -13,8 +13,75 @@
 use std::time::Duration;
 use std::vec::IntoIter as VecIntoIter;
 
+use cpal::Sample as CpalSample;
+
 use crate::{Sample, Source};
 
+/// Order of the bytes of a raw sample.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    /// Least significant byte first.
+    Little,
+    /// Most significant byte first.
+    Big,
+}
+
+/// Describes how raw PCM samples are stored, for `SamplesBuffer::from_raw_bytes`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RawFormat {
+    /// Number of bits of each sample. Must be 8, 16, 24 or 32.
+    pub bits_per_sample: u16,
+    /// True if the samples are signed integers, false if they are unsigned and centered on the
+    /// middle of their range.
+    pub signed: bool,
+    /// Order of the bytes of each sample.
+    pub endianness: Endianness,
+}
+
+impl RawFormat {
+    /// Signed 16 bits samples in little endian, the most common raw format.
+    pub const S16_LE: RawFormat = RawFormat {
+        bits_per_sample: 16,
+        signed: true,
+        endianness: Endianness::Little,
+    };
+
+    /// Decodes one sample. `bytes` has exactly the size of a sample.
+    pub(crate) fn decode<S>(&self, bytes: &[u8]) -> S
+    where
+        S: Sample,
+    {
+        let mut value: u32 = 0;
+        match self.endianness {
+            Endianness::Little => {
+                for &byte in bytes.iter().rev() {
+                    value = (value << 8) | byte as u32;
+                }
+            }
+            Endianness::Big => {
+                for &byte in bytes {
+                    value = (value << 8) | byte as u32;
+                }
+            }
+        }
+
+        // Move the sample to the top bits so that it becomes a 32 bits signed value.
+        let shift = 32 - self.bits_per_sample as u32;
+        let mut value = value << shift;
+        if !self.signed {
+            value ^= 0x8000_0000;
+        }
+        let value = value as i32;
+
+        if self.bits_per_sample <= 16 {
+            // Going through `i16` keeps 8 and 16 bits samples exact.
+            CpalSample::from(&((value >> 16) as i16))
+        } else {
+            CpalSample::from(&(value as f32 / 2_147_483_648.0))
+        }
+    }
+}
+
 /// A buffer of samples treated as a source.
 pub struct SamplesBuffer<S> {
     data: VecIntoIter<S>,
@@ -59,6 +126,36 @@ where
             duration,
         }
     }
+
+    /// Builds a new `SamplesBuffer` from raw PCM bytes, for example read from a file or a socket.
+    ///
+    /// The bytes are decoded according to `format`. Bytes at the end that don't make up a whole
+    /// sample are ignored.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the number of channels is zero.
+    /// - Panics if the samples rate is zero.
+    /// - Panics if `format.bits_per_sample` isn't 8, 16, 24 or 32.
+    ///
+    pub fn from_raw_bytes(
+        channels: u16,
+        sample_rate: u32,
+        bytes: &[u8],
+        format: RawFormat,
+    ) -> SamplesBuffer<S> {
+        assert!(
+            matches!(format.bits_per_sample, 8 | 16 | 24 | 32),
+            "unsupported number of bits per sample"
+        );
+
+        let sample_size = format.bits_per_sample as usize / 8;
+        let data: Vec<S> = bytes
+            .chunks_exact(sample_size)
+            .map(|sample| format.decode(sample))
+            .collect();
+        SamplesBuffer::new(channels, sample_rate, data)
+    }
 }
 
 impl<S> Source for SamplesBuffer<S>
@@ -84,6 +181,17 @@ where
     fn total_duration(&self) -> Option<Duration> {
         Some(self.duration)
     }
+
+    #[inline]
+    fn next_block(&mut self, out: &mut [S]) -> usize {
+        let remaining = self.data.as_slice();
+        let written = remaining.len().min(out.len());
+        out[..written].copy_from_slice(&remaining[..written]);
+        if written > 0 {
+            self.data.nth(written - 1);
+        }
+        written
+    }
 }
 
 impl<S> Iterator for SamplesBuffer<S>
@@ -105,7 +213,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use crate::buffer::SamplesBuffer;
+    use crate::buffer::{Endianness, RawFormat, SamplesBuffer};
     use crate::source::Source;
 
     #[test]
@@ -144,4 +252,46 @@ mod tests {
         assert_eq!(buf.next(), Some(6));
         assert_eq!(buf.next(), None);
     }
+
+    #[test]
+    fn raw_bytes_endianness() {
+        let samples = [0i16, 1, -1, 0x1234, i16::MIN, i16::MAX];
+        let little: Vec<u8> = samples.iter().flat_map(|s| s.to_le_bytes()).collect();
+        let big: Vec<u8> = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+
+        let from_little: Vec<i16> =
+            SamplesBuffer::from_raw_bytes(2, 44100, &little, RawFormat::S16_LE).collect();
+        let big_format = RawFormat {
+            endianness: Endianness::Big,
+            ..RawFormat::S16_LE
+        };
+        let from_big: Vec<i16> =
+            SamplesBuffer::from_raw_bytes(2, 44100, &big, big_format).collect();
+
+        assert_eq!(from_little, samples);
+        assert_eq!(from_big, samples);
+    }
+
+    #[test]
+    fn raw_bytes_other_formats() {
+        let unsigned_8 = RawFormat {
+            bits_per_sample: 8,
+            signed: false,
+            endianness: Endianness::Little,
+        };
+        let buf: Vec<i16> =
+            SamplesBuffer::from_raw_bytes(1, 8000, &[128, 0, 255], unsigned_8).collect();
+        assert_eq!(buf, vec![0, i16::MIN, 0x7f00]);
+
+        let signed_24 = RawFormat {
+            bits_per_sample: 24,
+            signed: true,
+            endianness: Endianness::Big,
+        };
+        // The last byte isn't a whole sample and is ignored.
+        let buf: Vec<f32> =
+            SamplesBuffer::from_raw_bytes(1, 8000, &[0xc0, 0, 0, 0x40, 0, 0, 1], signed_24)
+                .collect();
+        assert_eq!(buf, vec![-0.5, 0.5]);
+    }
 }
@@ -1,7 +1,14 @@
 use crate::conversions::Sample;
 
+use cpal::Sample as CpalSample;
+use std::collections::VecDeque;
+use std::f32::consts::PI;
 use std::mem;
 
+/// Number of taps of the anti-aliasing filter on each side of its center, per unit of the
+/// decimation factor.
+const DECIMATION_TAPS_PER_FACTOR: usize = 8;
+
 /// Iterator that converts from a certain sample rate to another.
 #[derive(Clone, Debug)]
 pub struct SampleRateConverter<I>
@@ -27,6 +34,218 @@ where
     next_output_frame_pos_in_chunk: u32,
     /// The buffer containing the samples waiting to be output.
     output_buffer: Vec<I::Item>,
+    /// Used instead of the linear interpolation when `from` is a multiple of `to`.
+    decimator: Option<Box<Decimator>>,
+}
+
+/// Converts to a sample rate that divides the input one, by filtering the input with a lowpass
+/// filter and keeping one frame out of `factor`.
+///
+/// The filter is centered on the frames that are kept, so that the sound isn't delayed. The
+/// first and last frames are repeated to fill the filter at the edges of the stream.
+///
+/// A stream can be split across several inputs, such as the frames of a decoder. In that case
+/// the decimator waits at the end of each input until it is resumed with the next one, so that
+/// the filter and the frames to keep carry on across the boundary.
+#[derive(Clone, Debug)]
+struct Decimator {
+    factor: usize,
+    channels: usize,
+    taps: Vec<f32>,
+    /// True if the stream may continue after the end of the input.
+    continued: bool,
+    /// The frames covered by the filter, interleaved. The frame in the middle is the next one
+    /// that is kept.
+    window: VecDeque<f32>,
+    /// Number of frames at the end of `window` that repeat the last frame of the input.
+    padding: usize,
+    /// The last complete frame read from the input.
+    last_frame: Vec<f32>,
+    /// The frame being output, and the position of the next sample to return in it.
+    output: Vec<f32>,
+    output_pos: usize,
+}
+
+impl Decimator {
+    /// Builds a decimator, reading the frames needed before the first output frame.
+    fn new<I>(input: &mut I, factor: usize, channels: usize) -> Decimator
+    where
+        I: Iterator,
+        I::Item: Sample,
+    {
+        let half = DECIMATION_TAPS_PER_FACTOR * factor;
+        // Hann-windowed sinc, with its cutoff slightly below the output Nyquist frequency.
+        let cutoff = 0.9 / factor as f32;
+        let mut taps: Vec<f32> = (0..2 * half + 1)
+            .map(|n| {
+                let x = n as f32 - half as f32;
+                let sinc = if x == 0.0 {
+                    1.0
+                } else {
+                    (PI * cutoff * x).sin() / (PI * cutoff * x)
+                };
+                let window = 0.5 + 0.5 * (PI * x / (half + 1) as f32).cos();
+                sinc * window
+            })
+            .collect();
+        let sum: f32 = taps.iter().sum();
+        taps.iter_mut().for_each(|tap| *tap /= sum);
+
+        let mut decimator = Decimator {
+            factor,
+            channels,
+            taps,
+            continued: false,
+            window: VecDeque::with_capacity((2 * half + 1) * channels),
+            padding: 0,
+            last_frame: Vec::with_capacity(channels),
+            output: Vec::with_capacity(channels),
+            output_pos: 0,
+        };
+
+        decimator.start(input);
+        decimator
+    }
+
+    /// Fills the filter with the first frame of the input, and the frames after it.
+    fn start<I>(&mut self, input: &mut I)
+    where
+        I: Iterator,
+        I::Item: Sample,
+    {
+        if !self.read_frame(input) {
+            // Empty input: the window stays empty and nothing is output.
+            return;
+        }
+        let half = self.taps.len() / 2;
+        for _ in 0..half {
+            self.window.extend(self.last_frame.clone());
+        }
+        for _ in 0..half {
+            self.push_frame(input);
+        }
+    }
+
+    /// Returns true if the decimator is waiting for the frames that follow the input.
+    fn is_waiting(&self) -> bool {
+        self.continued && self.padding > 0
+    }
+
+    /// Carries on with `input`, which holds the frames that follow the ones read so far. The
+    /// copies of the last frame at the end of `window` are replaced with the new frames. If
+    /// `input` is empty, the stream ends there.
+    fn resume<I>(&mut self, input: &mut I)
+    where
+        I: Iterator,
+        I::Item: Sample,
+    {
+        if self.window.is_empty() {
+            self.start(input);
+            return;
+        }
+
+        let padding = mem::replace(&mut self.padding, 0);
+        self.window
+            .truncate(self.window.len() - padding * self.channels);
+        for _ in 0..padding {
+            self.push_frame(input);
+        }
+        if padding > 0 && self.padding == padding {
+            self.continued = false;
+        }
+    }
+
+    /// Reads a complete frame from the input and appends it to `window`. Returns false at the
+    /// end of the input.
+    fn read_frame<I>(&mut self, input: &mut I) -> bool
+    where
+        I: Iterator,
+        I::Item: Sample,
+    {
+        let mut frame = Vec::with_capacity(self.channels);
+        frame.extend(input.by_ref().take(self.channels).map(|s| s.to_f32()));
+        if frame.len() < self.channels {
+            return false;
+        }
+        self.window.extend(frame.iter().copied());
+        self.last_frame = frame;
+        true
+    }
+
+    /// Appends the next frame of the input to `window`, or a copy of the last frame.
+    fn push_frame<I>(&mut self, input: &mut I)
+    where
+        I: Iterator,
+        I::Item: Sample,
+    {
+        if self.padding > 0 || !self.read_frame(input) {
+            self.window.extend(self.last_frame.iter().copied());
+            self.padding += 1;
+        }
+    }
+
+    /// Returns true if the frame in the middle of `window` comes from the input, and the frames
+    /// after it are known.
+    fn has_frame(&self) -> bool {
+        if self.continued {
+            !self.window.is_empty() && self.padding == 0
+        } else {
+            !self.window.is_empty() && self.padding <= self.taps.len() / 2
+        }
+    }
+
+    fn next<I>(&mut self, input: &mut I) -> Option<f32>
+    where
+        I: Iterator,
+        I::Item: Sample,
+    {
+        if self.output_pos < self.output.len() {
+            self.output_pos += 1;
+            return Some(self.output[self.output_pos - 1]);
+        }
+        if !self.has_frame() {
+            return None;
+        }
+
+        self.output.clear();
+        for channel in 0..self.channels {
+            let value = self
+                .taps
+                .iter()
+                .enumerate()
+                .map(|(n, tap)| tap * self.window[n * self.channels + channel])
+                .sum();
+            self.output.push(value);
+        }
+        self.output_pos = 1;
+
+        for _ in 0..self.factor {
+            self.window.drain(..self.channels);
+            self.push_frame(input);
+        }
+        Some(self.output[0])
+    }
+
+    fn size_hint(&self, (min, max): (usize, Option<usize>)) -> (usize, Option<usize>) {
+        let pending = self.output.len() - self.output_pos;
+        if self.window.is_empty() {
+            return (pending, Some(pending));
+        }
+        let half = self.taps.len() / 2;
+        let ahead = (half + 1).saturating_sub(self.padding);
+        let apply = |samples: usize| {
+            let frames = ahead + samples / self.channels;
+            pending + frames.div_ceil(self.factor) * self.channels
+        };
+        if self.continued {
+            // The frames too close to the end of the input wait for the next one.
+            let frames = (ahead + min / self.channels).saturating_sub(half);
+            let min = pending + frames.div_ceil(self.factor) * self.channels;
+            (min, max.map(apply))
+        } else {
+            (apply(min), max.map(apply))
+        }
+    }
 }
 
 impl<I> SampleRateConverter<I>
@@ -42,10 +261,37 @@ where
     ///
     #[inline]
     pub fn new(
+        input: I,
+        from: cpal::SampleRate,
+        to: cpal::SampleRate,
+        num_channels: cpal::ChannelCount,
+    ) -> SampleRateConverter<I> {
+        SampleRateConverter::build(input, from, to, num_channels, true)
+    }
+
+    /// Same as `new`, but always uses the linear interpolation, even when `from` is a multiple
+    /// of `to`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `from` or `to` are equal to 0.
+    ///
+    #[inline]
+    pub fn new_linear(
+        input: I,
+        from: cpal::SampleRate,
+        to: cpal::SampleRate,
+        num_channels: cpal::ChannelCount,
+    ) -> SampleRateConverter<I> {
+        SampleRateConverter::build(input, from, to, num_channels, false)
+    }
+
+    fn build(
         mut input: I,
         from: cpal::SampleRate,
         to: cpal::SampleRate,
         num_channels: cpal::ChannelCount,
+        decimate: bool,
     ) -> SampleRateConverter<I> {
         let from = from.0;
         let to = to.0;
@@ -67,9 +313,19 @@ where
             gcd(from, to)
         };
 
-        let (first_samples, next_samples) = if from == to {
+        // Keeping one frame out of `from / gcd` only needs an anti-aliasing filter.
+        let decimator = if decimate && from != to && to == gcd {
+            Some(Box::new(Decimator::new(
+                &mut input,
+                (from / gcd) as usize,
+                num_channels as usize,
+            )))
+        } else {
+            None
+        };
+
+        let (first_samples, next_samples) = if from == to || decimator.is_some() {
             // if `from` == `to` == 1, then we just pass through
-            debug_assert_eq!(from, gcd);
             (Vec::new(), Vec::new())
         } else {
             let first = input
@@ -93,6 +349,7 @@ where
             current_frame: first_samples,
             next_frame: next_samples,
             output_buffer: Vec::with_capacity(num_channels as usize - 1),
+            decimator,
         }
     }
 
@@ -102,6 +359,47 @@ where
         self.input
     }
 
+    /// Returns a mutable reference to the underlying iterator.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Tells the anti-aliasing filter that the stream may continue after the end of the input,
+    /// so that it waits for `resume` instead of repeating the last frame.
+    #[inline]
+    pub fn set_continued(&mut self) {
+        if let Some(decimator) = self.decimator.as_mut() {
+            decimator.continued = true;
+        }
+    }
+
+    /// Returns true if the anti-aliasing filter waits for the rest of the stream, which must be
+    /// given with `resume` or `finish`.
+    #[inline]
+    pub fn is_waiting(&self) -> bool {
+        self.decimator
+            .as_ref()
+            .is_some_and(|decimator| decimator.is_waiting())
+    }
+
+    /// Carries on with the underlying iterator, which must now hold the frames that follow the
+    /// ones read so far, with the same sample rate and channels.
+    #[inline]
+    pub fn resume(&mut self) {
+        if let Some(decimator) = self.decimator.as_mut() {
+            decimator.resume(&mut self.input);
+        }
+    }
+
+    /// Ends the stream at the end of the input, whatever comes after it.
+    #[inline]
+    pub fn finish(&mut self) {
+        if let Some(decimator) = self.decimator.as_mut() {
+            decimator.continued = false;
+        }
+    }
+
     fn next_input_frame(&mut self) {
         self.current_frame_pos_in_chunk += 1;
 
@@ -131,6 +429,12 @@ where
             return self.input.next();
         }
 
+        if let Some(decimator) = self.decimator.as_mut() {
+            return decimator
+                .next(&mut self.input)
+                .map(|sample| CpalSample::from(&sample));
+        }
+
         // Short circuit if there are some samples waiting.
         if !self.output_buffer.is_empty() {
             return Some(self.output_buffer.remove(0));
@@ -232,6 +536,8 @@ where
 
         if self.from == self.to {
             self.input.size_hint()
+        } else if let Some(decimator) = self.decimator.as_ref() {
+            decimator.size_hint(self.input.size_hint())
         } else {
             let (min, max) = self.input.size_hint();
             (apply(min), max.map(apply))
@@ -286,9 +592,28 @@ mod test {
             assert_eq!(input, output);
         }
 
-        /// Check that dividing the sample rate by k (integer) is the same as
-        ///   dropping a sample from each channel.
-        fn divide_sample_rate(to: u32, k: u32, input: Vec<u16>, n: u16) -> () {
+        /// Check that dividing the sample rate by k (integer) keeps one frame out of k, and
+        ///   that the anti-aliasing filter doesn't change a constant signal.
+        fn divide_sample_rate(to: u32, k: u32, frames: u8, value: u16, n: u8) -> () {
+            let to = if to == 0 { return; } else { SampleRate(to) };
+            let k = k % 16;
+            let from = multiply_rate(to, k);
+            if k == 0 || n == 0 { return; }
+
+            let input = vec![value; frames as usize * n as usize];
+            let output =
+                SampleRateConverter::new(input.into_iter(), from, to, n.into());
+            let len = output.len();
+            let output = output.collect::<Vec<_>>();
+
+            assert_eq!(output.len(), (frames as usize).div_ceil(k as usize) * n as usize);
+            assert_eq!(output.len(), len);
+            assert!(output.iter().all(|&s| s.abs_diff(value) <= 1));
+        }
+
+        /// Check that dividing the sample rate by k (integer) gives the same number of samples
+        ///   as dropping a sample from each channel.
+        fn divide_sample_rate_len(to: u32, k: u32, input: Vec<u16>, n: u16) -> () {
             let to = if to == 0 { return; } else { SampleRate(to) };
             let from = multiply_rate(to, k);
             if k == 0 || n == 0 { return; }
@@ -306,8 +631,8 @@ mod test {
                   .collect::<Vec<_>>();
 
             assert_eq!(input.chunks_exact(n.into())
-                         .step_by(k as usize).collect::<Vec<_>>().concat(),
-                       output)
+                         .step_by(k as usize).collect::<Vec<_>>().concat().len(),
+                       output.len())
         }
 
         /// Check that, after multiplying the sample rate by k, every k-th
@@ -368,4 +693,43 @@ mod test {
         let output = output.collect::<Vec<_>>();
         assert_eq!(output, [2, 16, 3, 17, 4, 18, 6, 20, 7, 21, 8, 22]);
     }
+
+    #[test]
+    fn decimate_anti_aliases() {
+        use crate::source::{SineWave, Source};
+
+        let rms = |freq: f32| {
+            let input = SineWave::new(freq).take_duration(Duration::from_millis(100));
+            let output = SampleRateConverter::new(input, SampleRate(48000), SampleRate(24000), 1);
+            assert!(output.decimator.is_some());
+            let output: Vec<f32> = output.collect();
+            assert_eq!(output.len(), 2400);
+            let sum: f32 = output[100..2300].iter().map(|s| s * s).sum();
+            (sum / 2200.0).sqrt()
+        };
+
+        // A sine below the output Nyquist frequency is kept, one above it is removed.
+        assert!((rms(1000.0) - 0.5f32.sqrt()).abs() < 0.01);
+        assert!(rms(18000.0) < 0.01);
+
+        // Other ratios keep using the linear interpolation.
+        let input = vec![0u16; 100];
+        let output =
+            SampleRateConverter::new(input.into_iter(), SampleRate(48000), SampleRate(32000), 1);
+        assert!(output.decimator.is_none());
+    }
+
+    #[test]
+    fn decimate_linear() {
+        // Every other sample is dropped instead of being filtered.
+        let input = vec![1.0f32, 0.0, 1.0, 0.0, 1.0, 0.0, 1.0, 0.0];
+        let output = SampleRateConverter::new_linear(
+            input.into_iter(),
+            SampleRate(48000),
+            SampleRate(24000),
+            1,
+        );
+        assert!(output.decimator.is_none());
+        assert_eq!(output.collect::<Vec<_>>(), [1.0, 1.0, 1.0, 1.0]);
+    }
 }
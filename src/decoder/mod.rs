@@ -16,10 +16,13 @@ use self::read_seek_source::ReadSeekSource;
 #[cfg(feature = "symphonia")]
 use ::symphonia::core::io::{MediaSource, MediaSourceStream};
 
+pub use self::raw::RawPcmSource;
+
 #[cfg(all(feature = "flac", not(feature = "symphonia-flac")))]
 mod flac;
 #[cfg(all(feature = "minimp3", not(feature = "symphonia-mp3")))]
 mod mp3;
+mod raw;
 #[cfg(feature = "symphonia")]
 mod read_seek_source;
 #[cfg(feature = "symphonia")]
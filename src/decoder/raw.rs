@@ -0,0 +1,196 @@
+use std::io::{ErrorKind, Read};
+use std::time::Duration;
+
+use crate::buffer::RawFormat;
+use crate::Source;
+
+/// Number of frames read from the reader at once.
+const CHUNK_FRAMES: usize = 1024;
+
+/// Source that plays headerless PCM samples read from a `Read` stream.
+///
+/// The bytes are read in chunks as the samples are needed, and decoded according to a
+/// `RawFormat`. The source ends when the reader reaches its end or returns an error. Bytes at
+/// the end that don't make up a whole sample are ignored.
+pub struct RawPcmSource<R> {
+    reader: R,
+    channels: u16,
+    sample_rate: u32,
+    format: RawFormat,
+    // The bytes read from the reader, and the samples decoded from them.
+    bytes: Vec<u8>,
+    samples: Vec<i16>,
+    position: usize,
+    ended: bool,
+}
+
+impl<R> RawPcmSource<R>
+where
+    R: Read,
+{
+    /// Builds a new `RawPcmSource`.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the number of channels is zero.
+    /// - Panics if the samples rate is zero.
+    /// - Panics if `format.bits_per_sample` isn't 8, 16, 24 or 32.
+    ///
+    pub fn new(reader: R, channels: u16, sample_rate: u32, format: RawFormat) -> RawPcmSource<R> {
+        assert!(channels != 0);
+        assert!(sample_rate != 0);
+        assert!(
+            matches!(format.bits_per_sample, 8 | 16 | 24 | 32),
+            "unsupported number of bits per sample"
+        );
+
+        let chunk_len = CHUNK_FRAMES * channels as usize * format.bits_per_sample as usize / 8;
+        RawPcmSource {
+            reader,
+            channels,
+            sample_rate,
+            format,
+            bytes: vec![0; chunk_len],
+            samples: Vec::with_capacity(CHUNK_FRAMES * channels as usize),
+            position: 0,
+            ended: false,
+        }
+    }
+
+    /// Returns a reference to the reader.
+    #[inline]
+    pub fn get_ref(&self) -> &R {
+        &self.reader
+    }
+
+    /// Returns the reader.
+    #[inline]
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Reads and decodes the next chunk.
+    fn read_chunk(&mut self) {
+        let mut len = 0;
+        while len < self.bytes.len() {
+            match self.reader.read(&mut self.bytes[len..]) {
+                Ok(0) => {
+                    self.ended = true;
+                    break;
+                }
+                Ok(read) => len += read,
+                Err(err) if err.kind() == ErrorKind::Interrupted => {}
+                Err(_) => {
+                    self.ended = true;
+                    break;
+                }
+            }
+        }
+
+        let sample_size = self.format.bits_per_sample as usize / 8;
+        let format = self.format;
+        self.samples.clear();
+        self.samples.extend(
+            self.bytes[..len]
+                .chunks_exact(sample_size)
+                .map(|sample| format.decode::<i16>(sample)),
+        );
+        self.position = 0;
+    }
+}
+
+impl<R> Iterator for RawPcmSource<R>
+where
+    R: Read,
+{
+    type Item = i16;
+
+    #[inline]
+    fn next(&mut self) -> Option<i16> {
+        if self.position >= self.samples.len() {
+            if self.ended {
+                return None;
+            }
+            self.read_chunk();
+        }
+
+        let sample = *self.samples.get(self.position)?;
+        self.position += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (
+            self.samples.len() - self.position.min(self.samples.len()),
+            None,
+        )
+    }
+}
+
+impl<R> Source for RawPcmSource<R>
+where
+    R: Read,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::{Cursor, Read};
+
+    use super::RawPcmSource;
+    use crate::buffer::{Endianness, RawFormat};
+    use crate::Source;
+
+    #[test]
+    fn reads_samples() {
+        let bytes = [0x00, 0x80, 0xff, 0x7f, 0x01, 0x00, 0xfe];
+        let source = RawPcmSource::new(Cursor::new(bytes), 2, 8000, RawFormat::S16_LE);
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.sample_rate(), 8000);
+        // The last byte isn't a whole sample.
+        assert_eq!(source.collect::<Vec<_>>(), [i16::MIN, i16::MAX, 1]);
+    }
+
+    /// A reader that returns at most three bytes at a time.
+    struct Trickle(Cursor<Vec<u8>>);
+
+    impl Read for Trickle {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            let len = buf.len().min(3);
+            self.0.read(&mut buf[..len])
+        }
+    }
+
+    #[test]
+    fn reads_across_chunks() {
+        let samples: Vec<i16> = (0..5000).map(|n| (n * 7) as i16).collect();
+        let bytes: Vec<u8> = samples.iter().flat_map(|s| s.to_be_bytes()).collect();
+        let format = RawFormat {
+            bits_per_sample: 16,
+            signed: true,
+            endianness: Endianness::Big,
+        };
+        let source = RawPcmSource::new(Trickle(Cursor::new(bytes)), 1, 8000, format);
+        assert_eq!(source.collect::<Vec<_>>(), samples);
+    }
+}
@@ -60,7 +60,12 @@ impl SymphoniaDecoder {
         if let Some(ext) = extension {
             hint.with_extension(ext);
         }
-        let format_opts: FormatOptions = Default::default();
+        // Gapless playback trims the encoder delay and padding, for example as described by the
+        // LAME tag of MP3 files.
+        let format_opts = FormatOptions {
+            enable_gapless: true,
+            ..Default::default()
+        };
         let metadata_opts: MetadataOptions = Default::default();
         let mut probed = get_probe().format(&hint, mss, &format_opts, &metadata_opts)?;
 
@@ -81,6 +86,8 @@ impl SymphoniaDecoder {
         let decoded = loop {
             let current_frame = probed.format.next_packet()?;
             match decoder.decode(&current_frame) {
+                // Packets can be entirely trimmed for gapless playback.
+                Ok(decoded) if decoded.frames() == 0 => continue,
                 Ok(decoded) => break decoded,
                 Err(e) => match e {
                     Error::DecodeError(_) => {
@@ -148,6 +155,7 @@ impl Iterator for SymphoniaDecoder {
             let decoded = loop {
                 match self.format.next_packet() {
                     Ok(packet) => match self.decoder.decode(&packet) {
+                        Ok(decoded) if decoded.frames() == 0 => continue,
                         Ok(decoded) => break decoded,
                         Err(e) => match e {
                             Error::DecodeError(_) => {
@@ -0,0 +1,191 @@
+use cpal::Sample as CpalSample;
+
+/// How the samples are dithered when they are converted to 16 bits for the output device.
+///
+/// Dithering adds a tiny amount of noise before rounding, which turns the distortion caused by
+/// the rounding into a constant, gentle hiss. It only applies to devices that take integer
+/// samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum DitherType {
+    /// No dithering. The samples are converted exactly like the other conversions of this crate.
+    #[default]
+    None,
+    /// Adds noise uniformly distributed over one step of the output.
+    Rectangular,
+    /// Adds noise with a triangular distribution over two steps of the output, which makes the
+    /// level of the remaining noise independent of the signal. The usual choice.
+    Triangular,
+    /// Triangular dithering whose rounding error is fed back into the next sample, which moves
+    /// most of the noise to the high frequencies, where it is harder to hear.
+    NoiseShaped,
+}
+
+impl DitherType {
+    #[inline]
+    pub(crate) fn to_u8(self) -> u8 {
+        self as u8
+    }
+
+    #[inline]
+    pub(crate) fn from_u8(value: u8) -> DitherType {
+        match value {
+            1 => DitherType::Rectangular,
+            2 => DitherType::Triangular,
+            3 => DitherType::NoiseShaped,
+            _ => DitherType::None,
+        }
+    }
+}
+
+/// Converts interleaved `f32` samples to `i16` with dithering.
+#[derive(Clone, Debug)]
+pub(crate) struct Ditherer {
+    // State of the random number generator.
+    seed: u32,
+    // Last rounding error of each channel, for noise shaping.
+    errors: Vec<f32>,
+    // Channel of the next sample.
+    channel: usize,
+}
+
+impl Ditherer {
+    pub(crate) fn new(channels: u16) -> Ditherer {
+        Ditherer {
+            seed: 0x9e37_79b9,
+            errors: vec![0.0; channels.max(1) as usize],
+            channel: 0,
+        }
+    }
+
+    /// Returns a random value uniformly distributed between `-0.5` and `0.5`.
+    #[inline]
+    fn random(&mut self) -> f32 {
+        // Xorshift.
+        self.seed ^= self.seed << 13;
+        self.seed ^= self.seed >> 17;
+        self.seed ^= self.seed << 5;
+        (self.seed >> 8) as f32 / (1 << 24) as f32 - 0.5
+    }
+
+    /// Converts the next sample.
+    #[inline]
+    pub(crate) fn convert(&mut self, dither: DitherType, value: f32) -> i16 {
+        let channel = self.channel;
+        self.channel = (self.channel + 1) % self.errors.len();
+
+        // The same scale as the conversion of `cpal`.
+        let scale = if value >= 0.0 {
+            i16::MAX as f32
+        } else {
+            -(i16::MIN as f32)
+        };
+        let scaled = value * scale;
+        let (target, noise) = match dither {
+            DitherType::None => return value.to_i16(),
+            DitherType::Rectangular => (scaled, self.random()),
+            DitherType::Triangular => (scaled, self.random() + self.random()),
+            DitherType::NoiseShaped => {
+                (scaled - self.errors[channel], self.random() + self.random())
+            }
+        };
+
+        let output = (target + noise)
+            .round()
+            .clamp(i16::MIN as f32, i16::MAX as f32);
+        self.errors[channel] = output - target;
+        output as i16
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DitherType, Ditherer};
+
+    /// Converts a slowly changing positive signal and returns the rounding errors, in steps of
+    /// the output.
+    fn errors(dither: DitherType) -> Vec<f32> {
+        let mut ditherer = Ditherer::new(1);
+        (0..100_000)
+            .map(|n| {
+                let value = 0.3 + 0.2 * (n as f32 * 0.001).sin();
+                ditherer.convert(dither, value) as f32 - value * i16::MAX as f32
+            })
+            .collect()
+    }
+
+    fn variance(errors: &[f32]) -> f32 {
+        errors.iter().map(|e| e * e).sum::<f32>() / errors.len() as f32
+    }
+
+    /// Correlation between each error and the next one.
+    fn correlation(errors: &[f32]) -> f32 {
+        let sum: f32 = errors.windows(2).map(|w| w[0] * w[1]).sum();
+        sum / errors.len() as f32 / variance(errors)
+    }
+
+    #[test]
+    fn no_dither() {
+        let mut ditherer = Ditherer::new(2);
+        for value in [0.0, 0.25, -0.25, 1.0, -1.0] {
+            assert_eq!(
+                ditherer.convert(DitherType::None, value),
+                cpal::Sample::to_i16(&value)
+            );
+        }
+        assert!(errors(DitherType::None).iter().all(|e| e.abs() < 1.0));
+    }
+
+    #[test]
+    fn rectangular() {
+        let errors = errors(DitherType::Rectangular);
+        assert!(errors.iter().all(|e| e.abs() <= 1.0));
+        // The dither and the rounding are each uniform over one step: 1/12 + 1/12.
+        assert!((variance(&errors) - 1.0 / 6.0).abs() < 0.01);
+        assert!(correlation(&errors).abs() < 0.05);
+    }
+
+    #[test]
+    fn triangular() {
+        let errors = errors(DitherType::Triangular);
+        assert!(errors.iter().all(|e| e.abs() <= 1.5));
+        // Triangular dither over two steps plus uniform rounding: 1/6 + 1/12.
+        assert!((variance(&errors) - 0.25).abs() < 0.01);
+        assert!(correlation(&errors).abs() < 0.05);
+
+        // The noise alone is triangular: values near zero are the most likely.
+        let mut ditherer = Ditherer::new(1);
+        let noise: Vec<f32> = (0..100_000)
+            .map(|_| ditherer.random() + ditherer.random())
+            .collect();
+        let center = noise.iter().filter(|n| n.abs() < 0.25).count();
+        let edges = noise.iter().filter(|n| n.abs() > 0.75).count();
+        assert!(noise.iter().all(|n| n.abs() <= 1.0));
+        // The triangle puts 7/16 of the values in the center and 1/16 on the edges.
+        assert!((center as f32 / noise.len() as f32 - 7.0 / 16.0).abs() < 0.01);
+        assert!((edges as f32 / noise.len() as f32 - 1.0 / 16.0).abs() < 0.01);
+    }
+
+    #[test]
+    fn noise_shaped() {
+        let errors = errors(DitherType::NoiseShaped);
+        // Noise shaped like `1 - z^-1` has a correlation of -1/2 between consecutive samples,
+        // which means it is mostly made of high frequencies.
+        assert!((correlation(&errors) + 0.5).abs() < 0.05);
+
+        // The noise at low frequencies is much lower than with plain triangular dithering.
+        let low = |errors: &[f32]| {
+            let sums: Vec<f32> = errors.chunks(64).map(|c| c.iter().sum::<f32>()).collect();
+            variance(&sums)
+        };
+        assert!(low(&errors) < low(&self::errors(DitherType::Triangular)) / 10.0);
+    }
+
+    #[test]
+    fn full_scale_does_not_wrap() {
+        let mut ditherer = Ditherer::new(1);
+        for _ in 0..1000 {
+            assert!(ditherer.convert(DitherType::NoiseShaped, 1.0) > 32000);
+            assert!(ditherer.convert(DitherType::Triangular, -1.0) < -32000);
+        }
+    }
+}
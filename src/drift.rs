@@ -0,0 +1,326 @@
+//! Buffer that absorbs the drift between the clock of a producer and the clock of the output.
+//!
+//! Sounds that come from another clock, such as a capture device or a network stream, are never
+//! produced exactly as fast as the output device plays them. A plain buffer slowly runs empty or
+//! overflows, which causes periodic glitches. The buffer of this module resamples its output very
+//! slightly, faster when the buffer fills and slower when it empties, so that it stays close to
+//! the requested latency.
+//!
+//! The correction is limited to `MAX_CORRECTION`, which is far more than the drift of real
+//! clocks and too small to be heard.
+
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Maximum relative change of the playback rate.
+const MAX_CORRECTION: f64 = 0.01;
+
+/// How long it takes for the measured fill level to follow the actual one.
+const FILL_SMOOTHING: Duration = Duration::from_secs(1);
+
+/// Builds a new drift-compensating buffer. It consists of an input and an output.
+///
+/// Samples pushed to the input at the rate of the producer are played by the output, which
+/// implements `Source` and can be appended to a `Sink` like any other sound. `latency` is the
+/// amount of sound that the buffer tries to hold. The output plays silence until it is first
+/// reached, and again after the buffer has run empty.
+///
+/// # Panic
+///
+/// Panics if `channels` or `sample_rate` is zero.
+pub fn drift_buffer<S>(
+    channels: u16,
+    sample_rate: u32,
+    latency: Duration,
+) -> (Arc<DriftBufferInput<S>>, DriftBufferOutput<S>)
+where
+    S: Sample,
+{
+    assert!(channels >= 1);
+    assert!(sample_rate >= 1);
+
+    let target_frames = ((latency.as_secs_f64() * sample_rate as f64) as usize).max(2);
+    let input = Arc::new(DriftBufferInput {
+        channels,
+        samples: Mutex::new(VecDeque::with_capacity(
+            2 * target_frames * channels as usize,
+        )),
+        capacity: 2 * target_frames * channels as usize,
+        overruns: AtomicU64::new(0),
+    });
+
+    let samples = FILL_SMOOTHING.as_secs_f64() * sample_rate as f64;
+    let output = DriftBufferOutput {
+        input: input.clone(),
+        channels,
+        sample_rate,
+        target_frames,
+        fill_coeff: (-1.0 / samples).exp(),
+        fill: 0.0,
+        playing: false,
+        previous: vec![0.0; channels as usize],
+        next: vec![0.0; channels as usize],
+        position: 0.0,
+        current_channel: 0,
+        underruns: 0,
+    };
+
+    (input, output)
+}
+
+/// The input of a drift-compensating buffer.
+pub struct DriftBufferInput<S> {
+    channels: u16,
+    samples: Mutex<VecDeque<S>>,
+    // Maximum number of samples held, twice the requested latency.
+    capacity: usize,
+    overruns: AtomicU64,
+}
+
+impl<S> DriftBufferInput<S>
+where
+    S: Sample,
+{
+    /// Adds interleaved samples to the buffer.
+    ///
+    /// If the buffer is full, the oldest samples are dropped to make room.
+    pub fn push(&self, samples: &[S]) {
+        let mut buffer = self.samples.lock().unwrap();
+        buffer.extend(samples.iter().copied());
+        if buffer.len() > self.capacity {
+            // Drop whole frames so that the channels stay in order.
+            let excess = buffer.len() - self.capacity;
+            let channels = self.channels as usize;
+            let excess = (excess.div_ceil(channels) * channels).min(buffer.len());
+            buffer.drain(..excess);
+            self.overruns.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    /// Returns the number of samples currently held by the buffer.
+    #[inline]
+    pub fn len(&self) -> usize {
+        self.samples.lock().unwrap().len()
+    }
+
+    /// Returns true if the buffer holds no sample.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the number of times samples had to be dropped because the buffer was full.
+    #[inline]
+    pub fn overruns(&self) -> u64 {
+        self.overruns.load(Ordering::Relaxed)
+    }
+}
+
+/// The output of a drift-compensating buffer.
+///
+/// This source never ends.
+pub struct DriftBufferOutput<S> {
+    input: Arc<DriftBufferInput<S>>,
+    channels: u16,
+    sample_rate: u32,
+    // Number of frames that the buffer tries to hold.
+    target_frames: usize,
+    fill_coeff: f64,
+    // Smoothed number of frames held by the buffer.
+    fill: f64,
+    // False while waiting for the buffer to reach the target latency.
+    playing: bool,
+    // The frames between which the current output frame is interpolated.
+    previous: Vec<f32>,
+    next: Vec<f32>,
+    // Position of the current output frame between `previous` and `next`.
+    position: f64,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+    underruns: u64,
+}
+
+impl<S> DriftBufferOutput<S>
+where
+    S: Sample,
+{
+    /// Returns the ratio at which the input is currently read. `1.0` means no correction.
+    #[inline]
+    pub fn ratio(&self) -> f64 {
+        let error = (self.fill - self.target_frames as f64) / self.target_frames as f64;
+        1.0 + (error * MAX_CORRECTION).clamp(-MAX_CORRECTION, MAX_CORRECTION)
+    }
+
+    /// Returns the number of times the buffer ran empty while playing.
+    #[inline]
+    pub fn underruns(&self) -> u64 {
+        self.underruns
+    }
+
+    /// Moves to the next output frame, reading from the buffer as needed.
+    fn advance(&mut self) {
+        let mut samples = self.input.samples.lock().unwrap();
+        let frames = samples.len() / self.channels as usize;
+        self.fill = frames as f64 + (self.fill - frames as f64) * self.fill_coeff;
+
+        if !self.playing {
+            if frames < self.target_frames {
+                return;
+            }
+            self.playing = true;
+            self.fill = frames as f64;
+            // Read the first two frames.
+            self.position = 2.0;
+        } else {
+            self.position += self.ratio();
+        }
+
+        while self.position >= 1.0 {
+            if samples.len() < self.channels as usize {
+                self.playing = false;
+                self.underruns += 1;
+                self.previous.iter_mut().for_each(|s| *s = 0.0);
+                self.next.iter_mut().for_each(|s| *s = 0.0);
+                self.position = 0.0;
+                return;
+            }
+            std::mem::swap(&mut self.previous, &mut self.next);
+            for value in self.next.iter_mut() {
+                *value = samples.pop_front().unwrap().to_f32();
+            }
+            self.position -= 1.0;
+        }
+    }
+}
+
+impl<S> Iterator for DriftBufferOutput<S>
+where
+    S: Sample,
+{
+    type Item = S;
+
+    #[inline]
+    fn next(&mut self) -> Option<S> {
+        if self.current_channel == 0 {
+            self.advance();
+        }
+
+        let channel = self.current_channel as usize;
+        let previous = self.previous[channel];
+        let value = previous + (self.next[channel] - previous) * self.position as f32;
+
+        self.current_channel += 1;
+        if self.current_channel >= self.channels {
+            self.current_channel = 0;
+        }
+
+        Some(CpalSample::from(&value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl<S> Source for DriftBufferOutput<S>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::drift_buffer;
+    use crate::Source;
+
+    /// Plays `seconds` of sound while the producer runs `clock_ratio` times as fast as the
+    /// output, and returns the lowest and highest number of samples held while playing.
+    fn simulate(clock_ratio: f64, seconds: usize) -> (usize, usize) {
+        let (input, mut output) = drift_buffer::<f32>(2, 1000, Duration::from_millis(100));
+        assert_eq!(output.channels(), 2);
+
+        let mut produced = 0.0;
+        let mut pushed = 0;
+        let (mut min, mut max) = (usize::MAX, 0);
+        for block in 0..seconds * 100 {
+            // The producer pushes blocks of about 10 frames.
+            produced += 10.0 * clock_ratio;
+            let frames = produced as usize - pushed;
+            input.push(&vec![0.5f32; frames * 2]);
+            pushed += frames;
+
+            for _ in 0..10 * 2 {
+                output.next();
+            }
+            if block > 100 {
+                min = min.min(input.len());
+                max = max.max(input.len());
+            }
+        }
+
+        assert_eq!(output.underruns(), 0);
+        assert_eq!(input.overruns(), 0);
+        (min, max)
+    }
+
+    #[test]
+    fn fast_producer() {
+        let (_, max) = simulate(1.002, 600);
+        assert!(max < 2 * 2 * 100);
+    }
+
+    #[test]
+    fn slow_producer() {
+        let (min, _) = simulate(0.998, 600);
+        assert!(min > 0);
+    }
+
+    #[test]
+    fn interpolates_samples() {
+        let (input, output) = drift_buffer::<f32>(1, 1000, Duration::from_millis(4));
+        // Silence until the buffer holds the target latency.
+        input.push(&[1.0, 2.0, 3.0]);
+        let mut output = output.take(20);
+        assert_eq!(output.next(), Some(0.0));
+
+        input.push(&[4.0, 5.0, 6.0, 7.0, 8.0]);
+        let samples: Vec<f32> = output.by_ref().take(4).collect();
+        assert_eq!(samples[0], 1.0);
+        // The buffer is twice as full as required, so it is read slightly faster.
+        assert!(samples[1] > 2.0 && samples[1] < 2.02);
+        assert!(samples[3] - samples[2] > 1.0);
+    }
+}
@@ -22,6 +22,7 @@ where
 {
     let input = Arc::new(DynamicMixerController {
         has_pending: AtomicBool::new(false),
+        bit_perfect: Arc::new(AtomicBool::new(true)),
         pending_sources: Mutex::new(Vec::new()),
         channels,
         sample_rate,
@@ -41,6 +42,8 @@ where
 /// The input of the mixer.
 pub struct DynamicMixerController<S> {
     has_pending: AtomicBool,
+    // False once a source that had to be converted has been played.
+    bit_perfect: Arc<AtomicBool>,
     pending_sources: Mutex<Vec<Box<dyn Source<Item = S> + Send>>>,
     channels: u16,
     sample_rate: u32,
@@ -56,13 +59,102 @@ where
     where
         T: Source<Item = S> + Send + 'static,
     {
-        let uniform_source = UniformSourceIterator::new(source, self.channels, self.sample_rate);
-        self.pending_sources
-            .lock()
-            .unwrap()
-            .push(Box::new(uniform_source) as Box<_>);
+        // The frames that already have the format of the mixer go through the conversion
+        // untouched.
+        let source = CheckFormat {
+            input: source,
+            channels: self.channels,
+            sample_rate: self.sample_rate,
+            bit_perfect: self.bit_perfect.clone(),
+            remaining: 0,
+        };
+        source.check();
+        let source = UniformSourceIterator::new(source, self.channels, self.sample_rate);
+        self.pending_sources.lock().unwrap().push(Box::new(source));
         self.has_pending.store(true, Ordering::SeqCst); // TODO: can we relax this ordering?
     }
+
+    /// Returns true if all the sources added so far had the channels and sample rate of the
+    /// mixer, and have been played without any conversion.
+    ///
+    /// The format of a source is checked when it is added and then at each of its frames, so
+    /// this becomes false when a source that was added switches to another format.
+    #[inline]
+    pub fn is_bit_perfect(&self) -> bool {
+        self.bit_perfect.load(Ordering::Relaxed)
+    }
+}
+
+/// Clears the `bit_perfect` flag of the mixer when a frame of the source has another format.
+struct CheckFormat<I> {
+    input: I,
+    channels: u16,
+    sample_rate: u32,
+    bit_perfect: Arc<AtomicBool>,
+    // Number of samples left in the current frame.
+    remaining: usize,
+}
+
+impl<I> CheckFormat<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    fn check(&self) {
+        if self.input.channels() != self.channels || self.input.sample_rate() != self.sample_rate {
+            self.bit_perfect.store(false, Ordering::Relaxed);
+        }
+    }
+}
+
+impl<I> Iterator for CheckFormat<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.remaining == 0 {
+            self.check();
+            self.remaining = self.input.current_frame_len().unwrap_or(usize::MAX);
+        }
+        let sample = self.input.next()?;
+        self.remaining = self.remaining.saturating_sub(1);
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for CheckFormat<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
 }
 
 /// The output of the mixer. Implements `Source`.
@@ -220,6 +312,75 @@ mod tests {
         assert_eq!(rx.next(), None);
     }
 
+    #[test]
+    fn surround_channels_kept() {
+        let (tx, mut rx) = dynamic_mixer::mixer(6, 48000);
+
+        // One frame of 5.1 sound with only the center channel.
+        tx.add(SamplesBuffer::new(6, 48000, vec![0i16, 0, 10, 0, 0, 0]));
+
+        assert_eq!(rx.channels(), 6);
+        assert_eq!(rx.next(), Some(0));
+        assert_eq!(rx.next(), Some(0));
+        assert_eq!(rx.next(), Some(10));
+        assert_eq!(rx.next(), Some(0));
+        assert_eq!(rx.next(), Some(0));
+        assert_eq!(rx.next(), Some(0));
+        assert_eq!(rx.next(), None);
+    }
+
+    #[test]
+    fn bit_perfect() {
+        let (tx, mut rx) = dynamic_mixer::mixer(2, 48000);
+
+        let data = vec![1i16, -1, i16::MAX, i16::MIN, 1234, -4321];
+        tx.add(SamplesBuffer::new(2, 48000, data.clone()));
+        assert!(tx.is_bit_perfect());
+        assert_eq!(rx.by_ref().collect::<Vec<_>>(), data);
+
+        tx.add(SamplesBuffer::new(2, 44100, data));
+        assert!(!tx.is_bit_perfect());
+    }
+
+    #[test]
+    fn bit_perfect_i16_through_f32() {
+        use cpal::Sample as CpalSample;
+
+        // This is what happens when playing an `i16` source on an `i16` device.
+        let (tx, rx) = dynamic_mixer::mixer::<f32>(2, 48000);
+        let data: Vec<i16> = (i16::MIN..=i16::MAX).collect();
+        tx.add(SamplesBuffer::new(2, 48000, data.clone()).convert_samples());
+
+        assert!(tx.is_bit_perfect());
+        let output: Vec<i16> = rx.map(|s| s.to_i16()).collect();
+        assert_eq!(output, data);
+    }
+
+    #[test]
+    fn bit_perfect_through_sink() {
+        use crate::Sink;
+
+        let (sink, queue_rx) = Sink::new_idle();
+        let data: Vec<f32> = (0..2000).map(|i| (i as f32 / 1000.0).sin()).collect();
+        sink.append(SamplesBuffer::new(2, 48000, data.clone()));
+
+        let (tx, mut rx) = dynamic_mixer::mixer::<f32>(2, 48000);
+        tx.add(queue_rx);
+        assert!(tx.is_bit_perfect());
+        let output: Vec<f32> = rx.by_ref().take(data.len()).collect();
+        assert_eq!(output, data);
+
+        // The silence between the sounds keeps their format.
+        rx.by_ref().take(5000).for_each(drop);
+        assert!(tx.is_bit_perfect());
+
+        // A sound at another sample rate is converted once it plays.
+        sink.append(SamplesBuffer::new(2, 44100, data));
+        assert!(tx.is_bit_perfect());
+        rx.by_ref().take(5000).for_each(drop);
+        assert!(!tx.is_bit_perfect());
+    }
+
     #[test]
     fn rate_conv() {
         let (tx, mut rx) = dynamic_mixer::mixer(1, 96000);
@@ -115,12 +115,15 @@ pub use cpal::{
 };
 
 mod conversions;
+mod dither;
+mod music_director;
 mod sink;
 mod spatial_sink;
 mod stream;
 
 pub mod buffer;
 pub mod decoder;
+pub mod drift;
 pub mod dynamic_mixer;
 pub mod queue;
 pub mod source;
@@ -128,7 +131,9 @@ pub mod static_buffer;
 
 pub use crate::conversions::Sample;
 pub use crate::decoder::Decoder;
-pub use crate::sink::Sink;
+pub use crate::dither::DitherType;
+pub use crate::music_director::MusicDirector;
+pub use crate::sink::{LoopHandle, PositionWatch, Sink, SourceHandle, VolumeCurve};
 pub use crate::source::Source;
 pub use crate::spatial_sink::SpatialSink;
 pub use crate::stream::{OutputStream, OutputStreamHandle, PlayError, StreamError};
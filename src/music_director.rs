@@ -0,0 +1,330 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::source::UniformSourceIterator;
+use crate::stream::{OutputStreamHandle, PlayError};
+use crate::{Sample, Sink, Source};
+
+/// Plays a music bed that can be swapped for another one with a crossfade, as adaptive game
+/// music does.
+///
+/// If a bar length is set, transitions wait for the start of the next bar, counted from the
+/// moment the first bed started, so that the music stays on the beat.
+pub struct MusicDirector {
+    sink: Sink,
+    bed: Mutex<Option<Arc<Shared>>>,
+    bar_length: Mutex<Option<Duration>>,
+}
+
+struct Shared {
+    // True when `next` holds a transition.
+    pending: AtomicBool,
+    state: Mutex<State>,
+}
+
+struct State {
+    // The transition to start next.
+    next: Option<Transition>,
+    // True once the `Bed` source has ended.
+    finished: bool,
+}
+
+struct Transition {
+    source: Box<dyn Source<Item = f32> + Send>,
+    crossfade: Duration,
+    bar_length: Option<Duration>,
+}
+
+impl MusicDirector {
+    /// Builds a new `MusicDirector`, beginning playback on a stream.
+    #[inline]
+    pub fn try_new(stream: &OutputStreamHandle) -> Result<MusicDirector, PlayError> {
+        Ok(MusicDirector::new(Sink::try_new(stream)?))
+    }
+
+    /// Builds a new `MusicDirector` that plays through a sink.
+    #[inline]
+    pub fn new(sink: Sink) -> MusicDirector {
+        MusicDirector {
+            sink,
+            bed: Mutex::new(None),
+            bar_length: Mutex::new(None),
+        }
+    }
+
+    /// Returns the sink that plays the music, for example to change its volume.
+    #[inline]
+    pub fn sink(&self) -> &Sink {
+        &self.sink
+    }
+
+    /// Returns the sink that plays the music.
+    #[inline]
+    pub fn into_sink(self) -> Sink {
+        self.sink
+    }
+
+    /// Returns the length of a bar, if transitions are aligned on bars.
+    #[inline]
+    pub fn bar_length(&self) -> Option<Duration> {
+        *self.bar_length.lock().unwrap()
+    }
+
+    /// Sets the length of a bar, or `None` to start transitions immediately.
+    ///
+    /// Only applies to the transitions requested afterwards.
+    #[inline]
+    pub fn set_bar_length(&self, bar_length: Option<Duration>) {
+        *self.bar_length.lock().unwrap() = bar_length;
+    }
+
+    /// Replaces the current bed with `source`, crossfading the two over `crossfade`.
+    ///
+    /// If nothing is playing, the new bed fades in over `crossfade`. A transition requested
+    /// while another one is waiting for the next bar replaces it. The beds are converted to the
+    /// channels and sample rate of the bed that was played when nothing was playing.
+    pub fn transition_to<S>(&self, source: S, crossfade: Duration)
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample + Send,
+    {
+        let (channels, sample_rate) = (source.channels(), source.sample_rate());
+        let transition = Transition {
+            source: Box::new(source.convert_samples()),
+            crossfade,
+            bar_length: self.bar_length(),
+        };
+
+        let mut bed = self.bed.lock().unwrap();
+        if let Some(shared) = bed.as_ref() {
+            let mut state = shared.state.lock().unwrap();
+            if !state.finished {
+                state.next = Some(transition);
+                shared.pending.store(true, Ordering::SeqCst);
+                return;
+            }
+        }
+
+        let shared = Arc::new(Shared {
+            pending: AtomicBool::new(true),
+            state: Mutex::new(State {
+                next: Some(transition),
+                finished: false,
+            }),
+        });
+        *bed = Some(shared.clone());
+        drop(bed);
+
+        self.sink.append(Bed {
+            current: None,
+            fading: None,
+            scheduled: None,
+            shared,
+            channels,
+            sample_rate,
+            current_channel: 0,
+            frame: 0,
+            fade: (0, 0),
+        });
+    }
+}
+
+/// Plays the beds sent by `MusicDirector::transition_to`.
+struct Bed {
+    current: Option<Box<dyn Source<Item = f32> + Send>>,
+    // The bed being replaced, while it fades out.
+    fading: Option<Box<dyn Source<Item = f32> + Send>>,
+    // The next bed, its crossfade in frames, and the frame at which it starts.
+    scheduled: Option<(Box<dyn Source<Item = f32> + Send>, u64, u64)>,
+    shared: Arc<Shared>,
+    channels: u16,
+    sample_rate: u32,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+    // Number of frames played so far.
+    frame: u64,
+    // Remaining and total number of frames of the crossfade.
+    fade: (u64, u64),
+}
+
+impl Bed {
+    /// Schedules the transition that was sent, if any, and starts the scheduled one if its time
+    /// has come.
+    fn update(&mut self) {
+        if self.shared.pending.swap(false, Ordering::SeqCst) {
+            if let Some(transition) = self.shared.state.lock().unwrap().next.take() {
+                let rate = self.sample_rate as f64;
+                let crossfade = (transition.crossfade.as_secs_f64() * rate).round() as u64;
+                let bar = transition
+                    .bar_length
+                    .map_or(0, |bar| (bar.as_secs_f64() * rate).round() as u64);
+                let start = if bar == 0 {
+                    self.frame
+                } else {
+                    self.frame.div_ceil(bar) * bar
+                };
+                let source = UniformSourceIterator::<_, f32>::new(
+                    transition.source,
+                    self.channels,
+                    self.sample_rate,
+                );
+                self.scheduled = Some((Box::new(source), crossfade, start));
+            }
+        }
+
+        if !matches!(self.scheduled, Some((_, _, start)) if start <= self.frame) {
+            return;
+        }
+        let (next, crossfade, _) = self.scheduled.take().unwrap();
+        self.fading = self.current.replace(next);
+        self.fade = (crossfade, crossfade);
+        if crossfade == 0 {
+            self.fading = None;
+        }
+    }
+
+    /// Marks the source as finished, unless a transition is pending.
+    fn finish(&mut self) -> bool {
+        let mut state = self.shared.state.lock().unwrap();
+        if self.scheduled.is_some() || (state.next.is_some() && self.current_channel == 0) {
+            return false;
+        }
+        state.finished = true;
+        true
+    }
+}
+
+impl Iterator for Bed {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.current_channel == 0 {
+            self.update();
+        }
+
+        let (remaining, total) = self.fade;
+        let (fade_out, fade_in) = if remaining > 0 {
+            let fade_out = remaining as f32 / total as f32;
+            (fade_out, 1.0 - fade_out)
+        } else {
+            (0.0, 1.0)
+        };
+        let fading = self.fading.as_mut().and_then(|s| s.next());
+        let current = self.current.as_mut().and_then(|s| s.next());
+
+        // Silence is played while a bed ends before the bar at which the next one starts.
+        if fading.is_none() && current.is_none() && self.finish() {
+            return None;
+        }
+
+        self.current_channel += 1;
+        if self.current_channel >= self.channels {
+            self.current_channel = 0;
+            self.frame += 1;
+            if remaining > 0 {
+                self.fade.0 -= 1;
+                if self.fade.0 == 0 {
+                    self.fading = None;
+                }
+            }
+        }
+
+        Some(current.unwrap_or(0.0) * fade_in + fading.unwrap_or(0.0) * fade_out)
+    }
+}
+
+impl Source for Bed {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Drop for Bed {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.finished = true;
+            state.next = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::MusicDirector;
+    use crate::buffer::SamplesBuffer;
+    use crate::Sink;
+
+    fn assert_close(output: &[f32], expected: &[f32]) {
+        assert_eq!(output.len(), expected.len());
+        for (output, expected) in output.iter().zip(expected) {
+            assert!((output - expected).abs() < 1e-6, "{:?}", output);
+        }
+    }
+
+    #[test]
+    fn transition_crossfades() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let director = MusicDirector::new(sink);
+
+        // The first bed fades in.
+        director.transition_to(
+            SamplesBuffer::new(1, 1000, vec![1.0f32; 1000]),
+            Duration::from_millis(5),
+        );
+        let output: Vec<f32> = queue_rx.by_ref().take(100).collect();
+        assert_close(&output[..6], &[0.0, 0.2, 0.4, 0.6, 0.8, 1.0]);
+        assert!(output[5..].iter().all(|&s| s == 1.0));
+
+        // The second bed replaces the first one after a crossfade of 4 ms.
+        director.transition_to(
+            SamplesBuffer::new(1, 1000, vec![0.5f32; 1000]),
+            Duration::from_millis(4),
+        );
+        let output: Vec<f32> = queue_rx.by_ref().take(10).collect();
+        assert_close(&output[..5], &[1.0, 0.875, 0.75, 0.625, 0.5]);
+        assert!(output[4..].iter().all(|&s| s == 0.5));
+        assert!(!director.sink().empty());
+    }
+
+    #[test]
+    fn transition_waits_for_bar() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let director = MusicDirector::new(sink);
+        director.set_bar_length(Some(Duration::from_millis(100)));
+
+        director.transition_to(
+            SamplesBuffer::new(1, 1000, vec![1.0f32; 1000]),
+            Duration::ZERO,
+        );
+        assert!(queue_rx.by_ref().take(130).all(|s| s == 1.0));
+
+        director.transition_to(
+            SamplesBuffer::new(1, 1000, vec![0.5f32; 1000]),
+            Duration::ZERO,
+        );
+        let output: Vec<f32> = queue_rx.by_ref().take(100).collect();
+        // The next bar starts 200 ms after the first bed.
+        assert!(output[..70].iter().all(|&s| s == 1.0));
+        assert!(output[70..].iter().all(|&s| s == 0.5));
+    }
+}
@@ -5,7 +5,7 @@ use std::sync::mpsc::{Receiver, Sender};
 use std::sync::{mpsc, Arc, Mutex};
 use std::time::Duration;
 
-use crate::source::{Empty, Source, Zero};
+use crate::source::{Empty, Silence, Source};
 use crate::Sample;
 
 /// Builds a new queue. It consists of an input and an output.
@@ -30,6 +30,7 @@ where
 
     let output = SourcesQueueOutput {
         current: Box::new(Empty::<S>::new()) as Box<_>,
+        placeholder: true,
         signal_after_end: None,
         input: input.clone(),
     };
@@ -93,6 +94,9 @@ pub struct SourcesQueueOutput<S> {
     // The current iterator that produces samples.
     current: Box<dyn Source<Item = S> + Send>,
 
+    // True while `current` is the empty source the queue starts with.
+    placeholder: bool,
+
     // Signal this sender before picking from `next`.
     signal_after_end: Option<Sender<()>>,
 
@@ -118,33 +122,35 @@ where
         // constant.
         const THRESHOLD: usize = 512;
 
-        // Try the current `current_frame_len`.
-        if let Some(val) = self.current.current_frame_len() {
-            if val != 0 {
-                return Some(val);
+        self.with_next_sound(|sound| {
+            // Try the current `current_frame_len`.
+            if let Some(val) = sound.current_frame_len() {
+                if val != 0 {
+                    return Some(val);
+                }
             }
-        }
 
-        // Try the size hint.
-        let (lower_bound, _) = self.current.size_hint();
-        // The iterator default implementation just returns 0.
-        // That's a problematic value, so skip it.
-        if lower_bound > 0 {
-            return Some(lower_bound);
-        }
+            // Try the size hint.
+            let (lower_bound, _) = sound.size_hint();
+            // The iterator default implementation just returns 0.
+            // That's a problematic value, so skip it.
+            if lower_bound > 0 {
+                return Some(lower_bound);
+            }
 
-        // Otherwise we use the constant value.
-        Some(THRESHOLD)
+            // Otherwise we use the constant value.
+            Some(THRESHOLD)
+        })
     }
 
     #[inline]
     fn channels(&self) -> u16 {
-        self.current.channels()
+        self.with_next_sound(|sound| sound.channels())
     }
 
     #[inline]
     fn sample_rate(&self) -> u32 {
-        self.current.sample_rate()
+        self.with_next_sound(|sound| sound.sample_rate())
     }
 
     #[inline]
@@ -185,6 +191,18 @@ impl<S> SourcesQueueOutput<S>
 where
     S: Sample + Send + 'static,
 {
+    // Calls `f` with the sound that plays the next sample, as far as can be told without reading
+    // it. Once `current` is known to be over, this is the next sound of the queue, so that the
+    // format of the next frame is the one of the sound that plays it.
+    fn with_next_sound<R>(&self, f: impl Fn(&dyn Source<Item = S>) -> R) -> R {
+        if self.placeholder || self.current.size_hint().1 == Some(0) {
+            if let Some((next, _)) = self.input.next_sounds.lock().unwrap().first() {
+                return f(&**next);
+            }
+        }
+        f(&*self.current)
+    }
+
     // Called when `current` is empty and we must jump to the next element.
     // Returns `Ok` if the sound should continue playing, or an error if it should stop.
     //
@@ -199,12 +217,15 @@ where
 
             if next.len() == 0 {
                 if self.input.keep_alive_if_empty.load(Ordering::Acquire) {
-                    // Play a short silence in order to avoid spinlocking.
-                    let silence = Zero::<S>::new(1, 44100); // TODO: meh
-                    (
-                        Box::new(silence.take_duration(Duration::from_millis(10))) as Box<_>,
-                        None,
-                    )
+                    // Play a short silence in order to avoid spinlocking. It keeps the format of the
+                    // previous sound, so that the sounds around it don't need to be converted.
+                    let channels = self.current.channels().max(1);
+                    let sample_rate = self.current.sample_rate().max(1);
+                    // At least one frame, even at very low sample rates.
+                    let frame = Duration::from_secs_f64(1.0 / sample_rate as f64);
+                    let duration = Duration::from_millis(10).max(frame);
+                    let silence = Silence::<S>::new(channels, sample_rate, duration);
+                    (Box::new(silence) as Box<_>, None)
                 } else {
                     return Err(());
                 }
@@ -214,6 +235,7 @@ where
         };
 
         self.current = next;
+        self.placeholder = false;
         self.signal_after_end = signal_after_end;
         Ok(())
     }
@@ -226,7 +248,6 @@ mod tests {
     use crate::source::Source;
 
     #[test]
-    #[ignore] // FIXME: samples rate and channel not updated immediately after transition
     fn basic() {
         let (tx, mut rx) = queue::queue(false);
 
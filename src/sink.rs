@@ -1,10 +1,22 @@
-use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::f32::consts::FRAC_PI_2;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
 use std::sync::mpsc::Receiver;
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Condvar, Mutex};
 use std::time::Duration;
 
+use cpal::Sample as CpalSample;
+
+use crate::source::{
+    repeat_crossfade_infinite, DecodeThread, DecodeWorker, Done, Limiter, UniformSourceIterator,
+};
 use crate::stream::{OutputStreamHandle, PlayError};
-use crate::{queue, source::Done, Sample, Source};
+use crate::{queue, Sample, Source};
+
+/// Delay used by the limiter to lower the volume before peaks.
+const LIMITER_LOOKAHEAD: Duration = Duration::from_millis(5);
+
+/// Default duration of the fade out when the sink is stopped.
+const DEFAULT_STOP_FADE: Duration = Duration::from_millis(5);
 
 /// Handle to an device that outputs sounds.
 ///
@@ -16,6 +28,10 @@ pub struct Sink {
 
     controls: Arc<Controls>,
     sound_count: Arc<AtomicUsize>,
+    // The thread that decodes the sounds in advance, if the sink has one.
+    decode_worker: Option<DecodeWorker>,
+    // Where `play_interruptible` sends the sounds to the one that is currently playing.
+    interruptible: Mutex<Option<Arc<InterruptSlot>>>,
 
     detached: bool,
 }
@@ -23,8 +39,49 @@ pub struct Sink {
 struct Controls {
     pause: AtomicBool,
     volume: Mutex<f32>,
+    volume_curve: Mutex<VolumeCurve>,
     stopped: AtomicBool,
     speed: Mutex<f32>,
+    limiter: Mutex<Option<f32>>,
+    stop_fade: Mutex<Duration>,
+    // Seconds of sound played by the sink so far, stored as the bits of a `f64`.
+    position: AtomicU64,
+    // Position of the sink in the stereo field, stored as the bits of a `f32`.
+    pan: AtomicU32,
+}
+
+/// How the value passed to `Sink::set_volume` is turned into the factor applied to the samples.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum VolumeCurve {
+    /// Samples are multiplied by the volume. This is the default.
+    Linear,
+    /// The volume is a position on a 60 dB range, which matches how loudness is perceived.
+    ///
+    /// `1.0` is the normal volume, and each step of `0.1` below it lowers the sound by 6 dB, so
+    /// `0.5` is 30 dB below normal (a factor of about `0.0316`). `0.0` is silent.
+    ///
+    /// The midpoint is much quieter than with `Linear`, where `0.5` is already 6 dB below normal:
+    /// a curve that keeps `0.5` at -6 dB while reaching silence at `0.0` would be the linear curve
+    /// itself. With the linear curve, most of the audible range is squeezed into the bottom of a
+    /// slider. Spreading 60 dB evenly gives every step of the slider the same change in loudness.
+    Logarithmic,
+}
+
+impl VolumeCurve {
+    /// Range of the logarithmic curve, in decibels.
+    const LOGARITHMIC_RANGE_DB: f32 = 60.0;
+
+    /// Returns the factor applied to the samples for the given volume.
+    #[inline]
+    pub fn gain(self, volume: f32) -> f32 {
+        match self {
+            VolumeCurve::Linear => volume,
+            VolumeCurve::Logarithmic if volume <= 0.0 => 0.0,
+            VolumeCurve::Logarithmic => {
+                10f32.powf((volume - 1.0) * VolumeCurve::LOGARITHMIC_RANGE_DB / 20.0)
+            }
+        }
+    }
 }
 
 impl Sink {
@@ -47,18 +104,153 @@ impl Sink {
             controls: Arc::new(Controls {
                 pause: AtomicBool::new(false),
                 volume: Mutex::new(1.0),
+                volume_curve: Mutex::new(VolumeCurve::Linear),
                 stopped: AtomicBool::new(false),
                 speed: Mutex::new(1.0),
+                limiter: Mutex::new(None),
+                stop_fade: Mutex::new(DEFAULT_STOP_FADE),
+                position: AtomicU64::new(0f64.to_bits()),
+                pan: AtomicU32::new(0f32.to_bits()),
             }),
             sound_count: Arc::new(AtomicUsize::new(0)),
+            decode_worker: None,
+            interruptible: Mutex::new(None),
             detached: false,
         };
         (sink, queue_rx)
     }
 
+    /// Decodes the sounds appended to this sink on a separate thread, `buffer` in advance.
+    ///
+    /// The audio callback then only copies samples that have already been decoded, so heavy
+    /// codecs or filters can't make it miss its deadline. If the decoding thread can't keep up,
+    /// silence is played until it catches up. A single thread decodes all the sounds of the
+    /// sink, in the order they are appended.
+    #[inline]
+    pub fn with_decode_thread(mut self, buffer: Duration) -> Sink {
+        self.decode_worker = Some(DecodeWorker::new(buffer));
+        self
+    }
+
     /// Appends a sound to the queue of sounds to play.
     #[inline]
     pub fn append<S>(&self, source: S)
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+        S::Item: Send,
+    {
+        self.append_source(self.decoded(source));
+    }
+
+    /// Appends a boxed sound to the queue of sounds to play.
+    ///
+    /// Unlike `append`, this doesn't instantiate new code for each type of source, which keeps
+    /// the binary small when many different types of sources are played.
+    #[inline]
+    pub fn append_boxed(&self, source: Box<dyn Source<Item = i16> + Send>) {
+        self.append(source);
+    }
+
+    /// Plays a sound in place of the one played by the previous call, if it is still playing.
+    ///
+    /// The previous sound is crossfaded with the new one over the duration set by
+    /// `set_stop_fade`, so that the latest sound always wins. This suits sounds that give
+    /// feedback to the actions of a user. If no sound played by this function is playing, the
+    /// sound is appended to the queue like with `append`. Replacing sounds are converted to the
+    /// channels and sample rate of the first one.
+    pub fn play_interruptible<S>(&self, source: S)
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+        S::Item: Send,
+    {
+        let mut interruptible = self.interruptible.lock().unwrap();
+        if let Some(slot) = interruptible.as_ref() {
+            let mut state = slot.state.lock().unwrap();
+            if !state.finished {
+                state.next = Some(Box::new(self.decoded(source).convert_samples()));
+                slot.pending.store(true, Ordering::SeqCst);
+                return;
+            }
+        }
+
+        let slot = Arc::new(InterruptSlot {
+            pending: AtomicBool::new(false),
+            state: Mutex::new(InterruptState {
+                next: None,
+                finished: false,
+            }),
+        });
+        *interruptible = Some(slot.clone());
+        drop(interruptible);
+
+        let (channels, sample_rate) = (source.channels(), source.sample_rate());
+        // Each sound is decoded on its own, so that replacing one takes effect immediately.
+        let source =
+            UniformSourceIterator::<_, f32>::new(self.decoded(source), channels, sample_rate);
+        self.append_source(Interruptible {
+            current: Some(Box::new(source)),
+            fading: None,
+            slot,
+            controls: self.controls.clone(),
+            channels,
+            sample_rate,
+            current_channel: 0,
+            fade: (0, 0),
+        });
+    }
+
+    /// Plays a sound in a loop until the returned handle stops it, as is common for background
+    /// music.
+    ///
+    /// The sound is stored in a buffer the first time it plays, so the amount of memory used is
+    /// proportional to its size. If `crossfade` is given, the end of each iteration is crossfaded
+    /// with the beginning of the next one, which hides the seam of the loop. Stopping the loop
+    /// fades it out over the duration set by `set_stop_fade`.
+    pub fn append_looping<S>(&self, source: S, crossfade: Option<Duration>) -> LoopHandle
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+        S::Item: Send,
+    {
+        // The buffer of the loop shares its samples between threads, so they must be `Sync`.
+        let source = source.convert_samples::<f32>();
+        let looping: Box<dyn Source<Item = f32> + Send> = match crossfade {
+            Some(fade) => Box::new(repeat_crossfade_infinite(source, fade)),
+            None => Box::new(source.repeat_infinite()),
+        };
+
+        let handle = LoopHandle {
+            stopped: Arc::new(AtomicBool::new(false)),
+        };
+        self.append_source(StopLoop {
+            input: self.decoded(looping),
+            stopped: handle.stopped.clone(),
+            controls: self.controls.clone(),
+            fade: None,
+        });
+        handle
+    }
+
+    /// Moves the decoding of a sound to the decoding thread of the sink, if it has one.
+    ///
+    /// The sources that depend on the playback, like `StartAt`, must wrap the returned source so
+    /// that they run when the sound is played rather than when it is decoded.
+    #[inline]
+    fn decoded<S>(&self, source: S) -> Decoded<S>
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+        S::Item: Send,
+    {
+        match self.decode_worker.as_ref() {
+            Some(worker) => Decoded::Thread(worker.decode(source)),
+            None => Decoded::Direct(source),
+        }
+    }
+
+    fn append_source<S>(&self, source: S)
     where
         S: Source + Send + 'static,
         S::Item: Sample,
@@ -70,31 +262,139 @@ impl Sink {
             .speed(1.0)
             .pausable(false)
             .amplify(1.0)
-            .stoppable()
             .periodic_access(Duration::from_millis(5), move |src| {
-                if controls.stopped.load(Ordering::SeqCst) {
-                    src.stop();
-                } else {
-                    src.inner_mut().set_factor(*controls.volume.lock().unwrap());
-                    src.inner_mut()
-                        .inner_mut()
-                        .set_paused(controls.pause.load(Ordering::SeqCst));
-                    src.inner_mut()
-                        .inner_mut()
-                        .inner_mut()
-                        .set_factor(*controls.speed.lock().unwrap());
-                }
+                let curve = *controls.volume_curve.lock().unwrap();
+                let volume = *controls.volume.lock().unwrap();
+                src.set_factor(curve.gain(volume));
+                src.inner_mut()
+                    .set_paused(controls.pause.load(Ordering::SeqCst));
+                src.inner_mut()
+                    .inner_mut()
+                    .set_factor(*controls.speed.lock().unwrap());
             })
             .convert_samples();
+        let source = SinkPan {
+            input: source,
+            controls: self.controls.clone(),
+            current_channel: 0,
+            right: None,
+        };
+        // The limiter comes after every stage that can raise the level of the samples.
+        let controls = self.controls.clone();
+        let limiter = *self.controls.limiter.lock().unwrap();
+        let source = Limiter::new(source, limiter, LIMITER_LOOKAHEAD)
+            .periodic_access(Duration::from_millis(5), move |src| {
+                src.set_ceiling(*controls.limiter.lock().unwrap())
+            });
+        let source = FadeOnStop {
+            input: source,
+            controls: self.controls.clone(),
+            started: false,
+            fade: None,
+        };
         self.sound_count.fetch_add(1, Ordering::Relaxed);
         let source = Done::new(source, self.sound_count.clone());
+        let source = TrackPosition {
+            input: source,
+            controls: self.controls.clone(),
+        };
         *self.sleep_until_end.lock().unwrap() = Some(self.queue_tx.append_with_signal(source));
     }
 
+    /// Returns a handle that reads the playback time of the sink.
+    ///
+    /// The playback time is the one used by `append_at`. It is updated by the audio thread for
+    /// each sample and read without locking, so it can be polled as often as a user interface
+    /// needs.
+    #[inline]
+    pub fn position_watch(&self) -> PositionWatch {
+        PositionWatch {
+            controls: self.controls.clone(),
+        }
+    }
+
+    /// Appends a sound to the queue of sounds to play, starting it at the given playback time.
+    ///
+    /// The playback time is measured from the start of the sink and only advances while the sink
+    /// is playing sounds. If the sound only starts after `when`, because the sounds queued before
+    /// it last longer, it is played immediately when its turn comes.
+    ///
+    /// Silence is inserted before the sound, so changing the speed of the sink also changes the
+    /// time at which the sound starts.
+    #[inline]
+    pub fn append_at<S>(&self, source: S, when: Duration)
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+        S::Item: Send,
+    {
+        self.append_source(StartAt {
+            input: self.decoded(source),
+            when: Some(when),
+            controls: self.controls.clone(),
+            remaining_samples: 0,
+        });
+    }
+
+    /// Appends a sound to the queue of sounds to play, starting it at `start` into the sound.
+    ///
+    /// This is meant to continue a sound where it was left off. Sources can't seek, so the
+    /// samples before `start` are decoded and skipped when the sound begins.
+    #[inline]
+    pub fn play_from<S>(&self, source: S, start: Duration)
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+        S::Item: Send,
+    {
+        self.append(source.skip_duration(start));
+    }
+
+    /// Appends a sound to the queue of sounds to play, and returns a handle that controls only
+    /// this sound.
+    ///
+    /// The volume of the handle is applied on top of the volume of the sink.
+    #[inline]
+    pub fn append_controllable<S>(&self, source: S) -> SourceHandle
+    where
+        S: Source + Send + 'static,
+        S::Item: Sample,
+        S::Item: Send,
+    {
+        let handle = SourceHandle {
+            controls: Arc::new(SourceControls {
+                volume: Mutex::new(1.0),
+                muted: AtomicBool::new(false),
+                started: AtomicBool::new(false),
+                finished: AtomicBool::new(false),
+                wake: (Mutex::new(()), Condvar::new()),
+            }),
+        };
+
+        let controls = handle.controls.clone();
+        let source = self.decoded(source).amplify(1.0).periodic_access(
+            Duration::from_millis(5),
+            move |src| {
+                let volume = if controls.muted.load(Ordering::SeqCst) {
+                    0.0
+                } else {
+                    *controls.volume.lock().unwrap()
+                };
+                src.set_factor(volume);
+            },
+        );
+        // The controls apply when the sound is played, not when it is decoded.
+        self.append_source(SignalFinished {
+            input: source,
+            controls: handle.controls.clone(),
+        });
+        handle
+    }
+
     /// Gets the volume of the sound.
     ///
     /// The value `1.0` is the "normal" volume (unfiltered input). Any value other than 1.0 will
-    /// multiply each sample by this value.
+    /// multiply each sample by this value, or by the gain given by the volume curve.
     #[inline]
     pub fn volume(&self) -> f32 {
         *self.controls.volume.lock().unwrap()
@@ -103,12 +403,27 @@ impl Sink {
     /// Changes the volume of the sound.
     ///
     /// The value `1.0` is the "normal" volume (unfiltered input). Any value other than `1.0` will
-    /// multiply each sample by this value.
+    /// multiply each sample by this value, or by the gain given by the volume curve.
     #[inline]
     pub fn set_volume(&self, value: f32) {
         *self.controls.volume.lock().unwrap() = value;
     }
 
+    /// Gets the curve that maps the volume to the factor applied to the samples.
+    #[inline]
+    pub fn volume_curve(&self) -> VolumeCurve {
+        *self.controls.volume_curve.lock().unwrap()
+    }
+
+    /// Changes the curve that maps the volume to the factor applied to the samples.
+    ///
+    /// With `VolumeCurve::Logarithmic`, a volume slider going from `0.0` to `1.0` sounds evenly
+    /// spread instead of being too loud for most of its range.
+    #[inline]
+    pub fn set_volume_curve(&self, curve: VolumeCurve) {
+        *self.controls.volume_curve.lock().unwrap() = curve;
+    }
+
     /// Gets the speed of the sound.
     ///
     /// The value `1.0` is the "normal" speed (unfiltered input). Any value other than `1.0` will
@@ -127,6 +442,62 @@ impl Sink {
         *self.controls.speed.lock().unwrap() = value;
     }
 
+    /// Gets the ceiling of the limiter, or `None` if the limiter is disabled.
+    #[inline]
+    pub fn limiter(&self) -> Option<f32> {
+        *self.controls.limiter.lock().unwrap()
+    }
+
+    /// Enables or disables the limiter that protects the output of the sink from clipping.
+    ///
+    /// When a ceiling is set, the volume of the sound is lowered whenever needed so that the
+    /// amplitude of the samples never exceeds it. For example `Some(1.0)` prevents any clipping.
+    /// The limiter looks a few milliseconds ahead, which delays the sound by that amount.
+    ///
+    /// Pass `None` to disable the limiter.
+    #[inline]
+    pub fn set_limiter(&self, ceiling: Option<f32>) {
+        *self.controls.limiter.lock().unwrap() = ceiling;
+    }
+
+    /// Gets the position of the sink in the stereo field.
+    #[inline]
+    pub fn pan(&self) -> f32 {
+        f32::from_bits(self.controls.pan.load(Ordering::Relaxed))
+    }
+
+    /// Moves the sound of the sink in the stereo field, for example to place a voice on one side.
+    ///
+    /// `-1.0` plays everything on the left channel, `1.0` on the right channel and `0.0`, the
+    /// default, leaves the sound unchanged. The channel on the opposite side is moved towards the
+    /// other one with an equal-power law, so its loudness stays the same. Values outside of this
+    /// range are clamped. The change is applied immediately, even to the sound being played.
+    ///
+    /// Only the first two channels of a sound are panned, and sounds with a single channel are
+    /// not affected.
+    #[inline]
+    pub fn set_pan(&self, position: f32) {
+        let position = position.clamp(-1.0, 1.0);
+        self.controls
+            .pan
+            .store(position.to_bits(), Ordering::Relaxed);
+    }
+
+    /// Gets the duration of the fade out applied when the sink is stopped.
+    #[inline]
+    pub fn stop_fade(&self) -> Duration {
+        *self.controls.stop_fade.lock().unwrap()
+    }
+
+    /// Changes the duration of the fade out applied when the sink is stopped or dropped.
+    ///
+    /// Cutting a sound abruptly can be heard as a click, so by default the sound fades out over
+    /// a few milliseconds. A duration of zero stops the sound immediately.
+    #[inline]
+    pub fn set_stop_fade(&self, duration: Duration) {
+        *self.controls.stop_fade.lock().unwrap() = duration;
+    }
+
     /// Resumes playback of a paused sink.
     ///
     /// No effect if not paused.
@@ -153,12 +524,17 @@ impl Sink {
     }
 
     /// Stops the sink by emptying the queue.
+    ///
+    /// The sound that is playing fades out first, see `set_stop_fade`.
     #[inline]
     pub fn stop(&self) {
         self.controls.stopped.store(true, Ordering::SeqCst);
     }
 
     /// Destroys the sink without stopping the sounds that are still playing.
+    ///
+    /// The sounds already appended keep playing until they end, which is handy for sound effects
+    /// that are played and forgotten. Without this, dropping the sink stops them.
     #[inline]
     pub fn detach(mut self) {
         self.detached = true;
@@ -172,13 +548,40 @@ impl Sink {
         }
     }
 
+    /// Sleeps the current thread until the sound of `handle` starts playing, which is when it
+    /// reaches the front of the queue and produces its first sample.
+    ///
+    /// Returns `false` if the sound was removed from the sink without being played, for example
+    /// because the sink was stopped. Returns immediately if the sound has already started.
+    pub fn wait_until_plays(&self, handle: &SourceHandle) -> bool {
+        let controls = &handle.controls;
+        let mut guard = controls.wake.0.lock().unwrap();
+        loop {
+            if controls.started.load(Ordering::SeqCst) {
+                return true;
+            }
+            if controls.finished.load(Ordering::SeqCst) {
+                return false;
+            }
+            guard = controls.wake.1.wait(guard).unwrap();
+        }
+    }
+
     /// Returns true if this sink has no more sounds to play.
     #[inline]
     pub fn empty(&self) -> bool {
+        self.is_empty()
+    }
+
+    /// Returns true if this sink has no more sounds to play.
+    ///
+    /// Same as `empty`.
+    #[inline]
+    pub fn is_empty(&self) -> bool {
         self.len() == 0
     }
 
-    /// Returns the number of sounds currently in the queue.
+    /// Returns the number of sounds currently in the queue, including the one that is playing.
     #[inline]
     pub fn len(&self) -> usize {
         self.sound_count.load(Ordering::Relaxed)
@@ -196,57 +599,1304 @@ impl Drop for Sink {
     }
 }
 
-#[cfg(test)]
-mod tests {
-    use crate::buffer::SamplesBuffer;
-    use crate::{Sink, Source};
+/// Reads the playback time of a `Sink`, returned by `Sink::position_watch`.
+///
+/// This can be cloned and sent to another thread.
+#[derive(Clone)]
+pub struct PositionWatch {
+    controls: Arc<Controls>,
+}
 
-    #[test]
-    fn test_pause_and_stop() {
-        let (sink, mut queue_rx) = Sink::new_idle();
+impl PositionWatch {
+    /// Returns the playback time of the sink.
+    #[inline]
+    pub fn position(&self) -> Duration {
+        let position = f64::from_bits(self.controls.position.load(Ordering::Relaxed));
+        Duration::from_secs_f64(position.max(0.0))
+    }
+}
 
-        // assert_eq!(queue_rx.next(), Some(0.0));
+/// Stops a sound looped by `Sink::append_looping`.
+///
+/// This can be cloned and sent to another thread.
+#[derive(Clone, Debug)]
+pub struct LoopHandle {
+    stopped: Arc<AtomicBool>,
+}
 
-        let v = vec![10i16, -10, 20, -20, 30, -30];
+impl LoopHandle {
+    /// Fades the loop out and ends it.
+    #[inline]
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
 
-        // Low rate to ensure immediate control.
-        sink.append(SamplesBuffer::new(1, 1, v.clone()));
-        let mut src = SamplesBuffer::new(1, 1, v).convert_samples();
+    /// Returns true once `stop` has been called.
+    #[inline]
+    pub fn is_stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+}
 
-        assert_eq!(queue_rx.next(), src.next());
-        assert_eq!(queue_rx.next(), src.next());
+/// Controls a single sound appended to a `Sink` with `append_controllable`.
+///
+/// This can be cloned and sent to another thread.
+#[derive(Clone)]
+pub struct SourceHandle {
+    controls: Arc<SourceControls>,
+}
 
-        sink.pause();
+struct SourceControls {
+    volume: Mutex<f32>,
+    muted: AtomicBool,
+    started: AtomicBool,
+    finished: AtomicBool,
+    // Notified when the sound starts or finishes.
+    wake: (Mutex<()>, Condvar),
+}
 
-        assert_eq!(queue_rx.next(), Some(0.0));
+impl SourceControls {
+    /// Sets `flag` and wakes the threads waiting for the sound.
+    fn signal(&self, flag: &AtomicBool) {
+        flag.store(true, Ordering::SeqCst);
+        let _guard = self.wake.0.lock().unwrap();
+        self.wake.1.notify_all();
+    }
+}
 
-        sink.play();
+impl SourceHandle {
+    /// Gets the volume of the sound.
+    ///
+    /// The value `1.0` is the "normal" volume (unfiltered input).
+    #[inline]
+    pub fn volume(&self) -> f32 {
+        *self.controls.volume.lock().unwrap()
+    }
 
-        assert_eq!(queue_rx.next(), src.next());
-        assert_eq!(queue_rx.next(), src.next());
+    /// Changes the volume of the sound.
+    ///
+    /// The value `1.0` is the "normal" volume (unfiltered input). Any value other than `1.0` will
+    /// multiply each sample by this value.
+    #[inline]
+    pub fn set_volume(&self, value: f32) {
+        *self.controls.volume.lock().unwrap() = value;
+    }
 
-        sink.stop();
+    /// Returns true if the sound is muted.
+    #[inline]
+    pub fn is_muted(&self) -> bool {
+        self.controls.muted.load(Ordering::SeqCst)
+    }
 
-        assert_eq!(queue_rx.next(), Some(0.0));
+    /// Mutes or unmutes the sound. The volume is kept while muted.
+    #[inline]
+    pub fn set_muted(&self, muted: bool) {
+        self.controls.muted.store(muted, Ordering::SeqCst);
+    }
 
-        assert_eq!(sink.empty(), true);
+    /// Returns true once the sound has finished playing or has been removed from the sink.
+    #[inline]
+    pub fn is_finished(&self) -> bool {
+        self.controls.finished.load(Ordering::SeqCst)
     }
 
-    #[test]
-    fn test_volume() {
-        let (sink, mut queue_rx) = Sink::new_idle();
+    /// Returns true once the sound has produced its first sample.
+    #[inline]
+    pub fn has_started(&self) -> bool {
+        self.controls.started.load(Ordering::SeqCst)
+    }
+}
 
-        let v = vec![10i16, -10, 20, -20, 30, -30];
+/// Marks the sound of a `SourceHandle` as finished when it ends or is dropped by the queue.
+struct SignalFinished<I> {
+    input: I,
+    controls: Arc<SourceControls>,
+}
 
-        // High rate to avoid immediate control.
-        sink.append(SamplesBuffer::new(2, 44100, v.clone()));
-        let src = SamplesBuffer::new(2, 44100, v.clone()).convert_samples();
+impl<I> Iterator for SignalFinished<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
 
-        let mut src = src.amplify(0.5);
-        sink.set_volume(0.5);
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next();
+        if sample.is_none() {
+            self.controls.signal(&self.controls.finished);
+        } else if !self.controls.started.load(Ordering::Relaxed) {
+            self.controls.signal(&self.controls.started);
+        }
+        sample
+    }
 
-        for _ in 0..v.len() {
-            assert_eq!(queue_rx.next(), src.next());
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for SignalFinished<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+impl<I> Drop for SignalFinished<I> {
+    #[inline]
+    fn drop(&mut self) {
+        self.controls.signal(&self.controls.finished);
+    }
+}
+
+/// Plays the loop of `Sink::append_looping` until its handle stops it.
+struct StopLoop<I> {
+    input: I,
+    stopped: Arc<AtomicBool>,
+    controls: Arc<Controls>,
+    // Remaining and total number of samples of the fade, once it has started.
+    fade: Option<(u64, u64)>,
+}
+
+impl<I> Iterator for StopLoop<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.fade.is_none() && self.stopped.load(Ordering::SeqCst) {
+            let duration = *self.controls.stop_fade.lock().unwrap();
+            let frames = (duration.as_secs_f64() * self.input.sample_rate() as f64).round() as u64;
+            let samples = frames * self.input.channels() as u64;
+            self.fade = Some((samples, samples));
+        }
+
+        match &mut self.fade {
+            None => self.input.next(),
+            Some((0, _)) => None,
+            Some((remaining, total)) => {
+                *remaining -= 1;
+                let factor = *remaining as f32 / *total as f32;
+                self.input.next().map(|sample| sample.amplify(factor))
+            }
         }
     }
 }
+
+impl<I> Source for StopLoop<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+/// Shared between `Sink::play_interruptible` and the `Interruptible` source it plays.
+struct InterruptSlot {
+    // True when `next` holds a sound.
+    pending: AtomicBool,
+    state: Mutex<InterruptState>,
+}
+
+struct InterruptState {
+    // The sound that replaces the current one.
+    next: Option<Box<dyn Source<Item = f32> + Send>>,
+    // True once the `Interruptible` source has ended.
+    finished: bool,
+}
+
+/// Plays the sounds sent by `Sink::play_interruptible`, each one replacing the previous one.
+struct Interruptible {
+    current: Option<Box<dyn Source<Item = f32> + Send>>,
+    // The sound being replaced, while it fades out.
+    fading: Option<Box<dyn Source<Item = f32> + Send>>,
+    slot: Arc<InterruptSlot>,
+    controls: Arc<Controls>,
+    channels: u16,
+    sample_rate: u32,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+    // Remaining and total number of frames of the crossfade.
+    fade: (u64, u64),
+}
+
+impl Interruptible {
+    /// Starts the crossfade with the next sound, if one was sent.
+    fn take_next(&mut self) {
+        if !self.slot.pending.swap(false, Ordering::SeqCst) {
+            return;
+        }
+        let next = match self.slot.state.lock().unwrap().next.take() {
+            Some(next) => next,
+            None => return,
+        };
+
+        let next = UniformSourceIterator::<_, f32>::new(next, self.channels, self.sample_rate);
+        self.fading = self.current.replace(Box::new(next));
+        let duration = *self.controls.stop_fade.lock().unwrap();
+        let frames = (duration.as_secs_f64() * self.sample_rate as f64).round() as u64;
+        self.fade = (frames, frames);
+        if frames == 0 {
+            self.fading = None;
+        }
+    }
+
+    /// Marks the source as finished, unless a sound was sent in the meantime.
+    fn finish(&mut self) -> bool {
+        let mut state = self.slot.state.lock().unwrap();
+        if state.next.is_some() && self.current_channel == 0 {
+            return false;
+        }
+        state.finished = true;
+        true
+    }
+}
+
+impl Iterator for Interruptible {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.current_channel == 0 {
+            self.take_next();
+        }
+
+        let (remaining, total) = self.fade;
+        let (fade_out, fade_in) = if remaining > 0 {
+            let fade_out = remaining as f32 / total as f32;
+            (fade_out, 1.0 - fade_out)
+        } else {
+            (0.0, 1.0)
+        };
+        let fading = self.fading.as_mut().and_then(|s| s.next());
+        let current = self.current.as_mut().and_then(|s| s.next());
+
+        if fading.is_none() && current.is_none() {
+            if self.finish() {
+                return None;
+            }
+            return self.next();
+        }
+
+        self.current_channel += 1;
+        if self.current_channel >= self.channels {
+            self.current_channel = 0;
+            if remaining > 0 {
+                self.fade.0 -= 1;
+                if self.fade.0 == 0 {
+                    self.fading = None;
+                }
+            }
+        }
+
+        Some(current.unwrap_or(0.0) * fade_in + fading.unwrap_or(0.0) * fade_out)
+    }
+}
+
+impl Source for Interruptible {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Drop for Interruptible {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.slot.state.lock() {
+            state.finished = true;
+            state.next = None;
+        }
+    }
+}
+
+/// Fades the sound out and ends it once the sink is stopped.
+///
+/// Sounds that haven't started yet when the sink is stopped end immediately.
+struct FadeOnStop<I> {
+    input: I,
+    controls: Arc<Controls>,
+    // Whether a sample has been played.
+    started: bool,
+    // Remaining and total number of samples of the fade, once it has started.
+    fade: Option<(u64, u64)>,
+}
+
+impl<I> Iterator for FadeOnStop<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.fade.is_none() && self.controls.stopped.load(Ordering::SeqCst) {
+            if !self.started {
+                return None;
+            }
+            let duration = *self.controls.stop_fade.lock().unwrap();
+            let frames = (duration.as_secs_f64() * self.input.sample_rate() as f64).round() as u64;
+            let samples = frames * self.input.channels() as u64;
+            self.fade = Some((samples, samples));
+        }
+
+        let sample = self.input.next()?;
+        self.started = true;
+        match &mut self.fade {
+            None => Some(sample),
+            Some((0, _)) => None,
+            Some((remaining, total)) => {
+                *remaining -= 1;
+                Some(sample.amplify(*remaining as f32 / *total as f32))
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self.fade {
+            None => self.input.size_hint(),
+            Some((remaining, _)) => {
+                let (min, max) = self.input.size_hint();
+                let remaining = remaining as usize;
+                (
+                    min.min(remaining),
+                    Some(max.map_or(remaining, |max| max.min(remaining))),
+                )
+            }
+        }
+    }
+}
+
+impl<I> Source for FadeOnStop<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// Pans the first two channels of a sound by the position set with `Sink::set_pan`.
+struct SinkPan<I> {
+    input: I,
+    controls: Arc<Controls>,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+    // The panned right sample of the current frame, until it is returned.
+    right: Option<f32>,
+}
+
+impl<I> Iterator for SinkPan<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let channels = self.input.channels();
+        if let Some(right) = self.right.take() {
+            self.current_channel = if channels > 2 { 2 } else { 0 };
+            return Some(CpalSample::from(&right));
+        }
+
+        let sample = self.input.next()?;
+        let pan = f32::from_bits(self.controls.pan.load(Ordering::Relaxed));
+        if self.current_channel != 0 || channels < 2 || pan == 0.0 {
+            self.current_channel = (self.current_channel + 1) % channels.max(1);
+            return Some(sample);
+        }
+
+        let left = sample.to_f32();
+        let right = match self.input.next() {
+            Some(right) => right.to_f32(),
+            None => return Some(sample),
+        };
+        // The channel on the opposite side is split between both channels.
+        let (far, near) = ((pan.abs() * FRAC_PI_2).cos(), (pan.abs() * FRAC_PI_2).sin());
+        let (left, right) = if pan < 0.0 {
+            (left + right * near, right * far)
+        } else {
+            (left * far, right + left * near)
+        };
+        self.right = Some(right);
+        self.current_channel = 1;
+        Some(CpalSample::from(&left))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.right.is_some() as usize;
+        let (min, max) = self.input.size_hint();
+        (
+            min.saturating_add(pending),
+            max.and_then(|max| max.checked_add(pending)),
+        )
+    }
+}
+
+impl<I> Source for SinkPan<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let pending = self.right.is_some() as usize;
+        self.input.current_frame_len().map(|len| len + pending)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// A sound decoded either by the audio thread or by its own decoding thread.
+enum Decoded<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    Direct(S),
+    Thread(DecodeThread<S::Item>),
+}
+
+impl<S> Iterator for Decoded<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<S::Item> {
+        match self {
+            Decoded::Direct(source) => source.next(),
+            Decoded::Thread(source) => source.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match self {
+            Decoded::Direct(source) => source.size_hint(),
+            Decoded::Thread(source) => source.size_hint(),
+        }
+    }
+}
+
+impl<S> Source for Decoded<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        match self {
+            Decoded::Direct(source) => source.current_frame_len(),
+            Decoded::Thread(source) => source.current_frame_len(),
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        match self {
+            Decoded::Direct(source) => source.channels(),
+            Decoded::Thread(source) => source.channels(),
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        match self {
+            Decoded::Direct(source) => source.sample_rate(),
+            Decoded::Thread(source) => source.sample_rate(),
+        }
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        match self {
+            Decoded::Direct(source) => source.total_duration(),
+            Decoded::Thread(source) => source.total_duration(),
+        }
+    }
+}
+
+/// Updates the playback position of the sink as samples are read.
+///
+/// The silence played while the sink is paused doesn't count.
+struct TrackPosition<I> {
+    input: I,
+    controls: Arc<Controls>,
+}
+
+impl<I> Iterator for TrackPosition<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+        if self.controls.pause.load(Ordering::SeqCst) {
+            return Some(sample);
+        }
+        let sample_duration =
+            1.0 / (self.input.sample_rate() as f64 * self.input.channels() as f64);
+        let position = f64::from_bits(self.controls.position.load(Ordering::Relaxed));
+        self.controls
+            .position
+            .store((position + sample_duration).to_bits(), Ordering::Relaxed);
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> Source for TrackPosition<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// Plays silence until the playback position of the sink reaches `when`, then plays `input`.
+///
+/// The amount of silence is only known once the sound is reached in the queue.
+struct StartAt<I> {
+    input: I,
+    // Taken when the first sample is requested.
+    when: Option<Duration>,
+    controls: Arc<Controls>,
+    remaining_samples: u64,
+}
+
+impl<I> Iterator for StartAt<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if let Some(when) = self.when.take() {
+            let position = f64::from_bits(self.controls.position.load(Ordering::Relaxed));
+            let remaining = (when.as_secs_f64() - position).max(0.0);
+            let frames = (remaining * self.input.sample_rate() as f64).round() as u64;
+            self.remaining_samples = frames * self.input.channels() as u64;
+        }
+
+        if self.remaining_samples > 0 {
+            self.remaining_samples -= 1;
+            return Some(I::Item::zero_value());
+        }
+
+        self.input.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.input.size_hint();
+        let silence = self.remaining_samples as usize;
+        (min + silence, max.map(|max| max + silence))
+    }
+}
+
+impl<I> Source for StartAt<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.when.is_some() {
+            // The length of the silence is not known yet.
+            return Some(self.input.channels() as usize);
+        }
+        self.input
+            .current_frame_len()
+            .map(|val| val + self.remaining_samples as usize)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicBool, Ordering};
+    use std::sync::Arc;
+    use std::thread;
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::SineWave;
+    use crate::{Sink, Source, VolumeCurve};
+    use cpal::Sample;
+
+    #[test]
+    fn test_pause_and_stop() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+
+        // assert_eq!(queue_rx.next(), Some(0.0));
+
+        let v = vec![10i16, -10, 20, -20, 30, -30];
+
+        // Low rate to ensure immediate control.
+        sink.append(SamplesBuffer::new(1, 1, v.clone()));
+        let mut src = SamplesBuffer::new(1, 1, v).convert_samples();
+
+        assert_eq!(queue_rx.next(), src.next());
+        assert_eq!(queue_rx.next(), src.next());
+
+        sink.pause();
+
+        assert_eq!(queue_rx.next(), Some(0.0));
+
+        sink.play();
+
+        assert_eq!(queue_rx.next(), src.next());
+        assert_eq!(queue_rx.next(), src.next());
+
+        sink.stop();
+
+        assert_eq!(queue_rx.next(), Some(0.0));
+
+        assert_eq!(sink.empty(), true);
+    }
+
+    #[test]
+    fn test_append_at() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+
+        sink.append(SamplesBuffer::new(1, 10, vec![1i16; 5]));
+        sink.append_at(
+            SamplesBuffer::new(1, 10, vec![2i16; 5]),
+            Duration::from_secs(1),
+        );
+
+        for _ in 0..5 {
+            assert_eq!(queue_rx.next(), Some(1i16.to_f32()));
+        }
+        // The first sound lasted 0.5 seconds, so 0.5 seconds of silence are needed.
+        for _ in 0..5 {
+            assert_eq!(queue_rx.next(), Some(0.0));
+        }
+        for _ in 0..5 {
+            assert_eq!(queue_rx.next(), Some(2i16.to_f32()));
+        }
+    }
+
+    #[test]
+    fn test_append_at_decode_thread() {
+        let (sink, queue_rx) = Sink::new_idle();
+        let sink = sink.with_decode_thread(Duration::from_millis(100));
+
+        sink.append(SamplesBuffer::new(1, 44100, vec![1.0f32; 4410]));
+        sink.append_at(
+            SamplesBuffer::new(1, 44100, vec![0.5f32; 441]),
+            Duration::from_millis(200),
+        );
+        // Wait for the decoding thread to fill the buffers.
+        sink.decode_worker.as_ref().unwrap().wait_idle();
+
+        // The silence is measured when the sound is played, not when it is decoded.
+        let output: Vec<f32> = queue_rx.take(8820 + 441).collect();
+        assert!(output[..4410].iter().all(|&s| s == 1.0));
+        assert!(output[4410..8820].iter().all(|&s| s == 0.0));
+        assert!(output[8820..].iter().all(|&s| s == 0.5));
+    }
+
+    #[test]
+    fn test_limiter() {
+        let (sink, queue_rx) = Sink::new_idle();
+        sink.set_limiter(Some(0.5));
+
+        let v = vec![1.0f32, -1.0, 0.25, -0.25, 1.0, 0.9, -0.9, 0.1];
+        sink.append(SamplesBuffer::new(1, 1000, v.clone()));
+        sink.set_volume(2.0);
+
+        let output: Vec<f32> = queue_rx.take(v.len()).collect();
+        assert!(output.iter().all(|s| s.abs() <= 0.5));
+        assert!(output.iter().any(|s| s.abs() > 0.0));
+    }
+
+    #[test]
+    fn test_limiter_after_pan() {
+        let (sink, queue_rx) = Sink::new_idle();
+        sink.set_limiter(Some(0.5));
+        sink.set_pan(-1.0);
+
+        // Panning folds both channels into the left one, which would reach 1.0.
+        sink.append(SamplesBuffer::new(2, 1000, vec![0.5f32; 200]));
+        let output: Vec<f32> = queue_rx.take(200).collect();
+        assert!(output.iter().all(|s| s.abs() <= 0.5 + 1e-6));
+        assert!(output.iter().any(|s| s.abs() > 0.4));
+    }
+
+    #[test]
+    fn test_decode_thread() {
+        let (sink, queue_rx) = Sink::new_idle();
+        let sink = sink.with_decode_thread(Duration::from_millis(100));
+
+        let v = vec![10i16, -10, 20, -20, 30, -30];
+        sink.append(SamplesBuffer::new(2, 44100, v.clone()));
+        sink.decode_worker.as_ref().unwrap().wait_idle();
+
+        let output: Vec<f32> = queue_rx.take(v.len()).collect();
+        let expected: Vec<f32> = v.iter().map(|s| s.to_f32()).collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_decode_thread_slow_source() {
+        // A source that stalls for 20 ms every 50 ms of sound, while being faster on average.
+        let data: Vec<i16> = (1..=500).collect();
+        let slow_source = || {
+            SamplesBuffer::new(1, 1000, data.clone())
+                .periodic_access(Duration::from_millis(50), |_| {
+                    thread::sleep(Duration::from_millis(20))
+                })
+        };
+
+        // Read directly, some reads of 10 ms of sound take longer than 10 ms.
+        let mut direct = slow_source();
+        let mut slowest = Duration::ZERO;
+        loop {
+            let start = std::time::Instant::now();
+            if direct.by_ref().take(10).count() == 0 {
+                break;
+            }
+            slowest = slowest.max(start.elapsed());
+        }
+        assert!(slowest >= Duration::from_millis(15));
+
+        // Read in real time from the decoding thread, the buffer never runs empty.
+        let mut decoded = slow_source().decode_in_thread(Duration::from_millis(200));
+        decoded.wait_idle();
+        let mut output = Vec::new();
+        while output.len() < data.len() {
+            output.extend(decoded.by_ref().take(10));
+            thread::sleep(Duration::from_millis(10));
+        }
+        assert_eq!(output, data);
+        assert_eq!(decoded.underruns(), 0);
+    }
+
+    #[test]
+    fn test_len() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        assert!(sink.is_empty());
+
+        for _ in 0..3 {
+            sink.append(SamplesBuffer::new(1, 1, vec![1i16, 2]));
+        }
+        assert_eq!(sink.len(), 3);
+
+        // A sound ends when the sample after its last one is requested.
+        assert_eq!(queue_rx.next(), Some(1i16.to_f32()));
+        assert_eq!(queue_rx.next(), Some(2i16.to_f32()));
+        assert_eq!(sink.len(), 3);
+        assert_eq!(queue_rx.next(), Some(1i16.to_f32()));
+        assert_eq!(sink.len(), 2);
+        assert_eq!(queue_rx.next(), Some(2i16.to_f32()));
+        assert_eq!(queue_rx.next(), Some(1i16.to_f32()));
+        assert_eq!(sink.len(), 1);
+        assert_eq!(queue_rx.next(), Some(2i16.to_f32()));
+        assert_eq!(queue_rx.next(), Some(0.0));
+        assert_eq!(sink.len(), 0);
+        assert!(sink.is_empty());
+    }
+
+    #[test]
+    fn test_stop_fade() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        sink.set_stop_fade(Duration::from_millis(10));
+
+        sink.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 1000]));
+        sink.append(SamplesBuffer::new(1, 1000, vec![1.0f32; 1000]));
+        for _ in 0..5 {
+            assert_eq!(queue_rx.next(), Some(1.0));
+        }
+
+        sink.stop();
+
+        // The sound ramps down over 10 samples instead of ending abruptly.
+        let mut previous = 1.0;
+        for _ in 0..10 {
+            let sample = queue_rx.next().unwrap();
+            assert!(sample < previous);
+            previous = sample;
+        }
+        assert_eq!(previous, 0.0);
+
+        // The next sound in the queue isn't played at all.
+        assert_eq!(queue_rx.next(), Some(0.0));
+        assert_eq!(queue_rx.next(), Some(0.0));
+        assert!(sink.empty());
+    }
+
+    #[test]
+    fn test_append_controllable() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+
+        let v = vec![10i16, -10, 20, -20];
+
+        // Low rate to ensure immediate control.
+        let first = sink.append_controllable(SamplesBuffer::new(1, 1, v.clone()));
+        sink.append(SamplesBuffer::new(1, 1, v.clone()));
+        first.set_volume(0.5);
+
+        let mut src = SamplesBuffer::new(1, 1, v.clone())
+            .convert_samples()
+            .amplify(0.5);
+        for _ in 0..v.len() {
+            assert_eq!(queue_rx.next(), src.next());
+        }
+
+        // The next sound plays at full volume.
+        let mut src = SamplesBuffer::new(1, 1, v.clone()).convert_samples();
+        assert_eq!(queue_rx.next(), src.next());
+        assert!(first.is_finished());
+        for _ in 1..v.len() {
+            assert_eq!(queue_rx.next(), src.next());
+        }
+    }
+
+    #[test]
+    fn test_wait_until_plays() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+
+        sink.append(SamplesBuffer::new(1, 1000, vec![0.5f32; 10]));
+        let handle = sink.append_controllable(SamplesBuffer::new(1, 1000, vec![0.25f32; 10]));
+        assert!(queue_rx.by_ref().take(10).all(|s| s == 0.5));
+        assert!(!handle.has_started());
+
+        let pulled = Arc::new(AtomicBool::new(false));
+        let thread_pulled = pulled.clone();
+        let thread = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(50));
+            thread_pulled.store(true, Ordering::SeqCst);
+            queue_rx.next()
+        });
+
+        assert!(sink.wait_until_plays(&handle));
+        assert!(pulled.load(Ordering::SeqCst));
+        assert!(handle.has_started());
+        assert_eq!(thread.join().unwrap(), Some(0.25));
+
+        // A sound removed without being played wakes the waiting thread too.
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let handle = sink.append_controllable(SamplesBuffer::new(1, 1000, vec![0.25f32; 10]));
+        sink.stop();
+        assert_eq!(queue_rx.next(), Some(0.0));
+        assert!(!sink.wait_until_plays(&handle));
+    }
+
+    #[test]
+    fn test_wait_until_plays_decode_thread() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let sink = sink.with_decode_thread(Duration::from_millis(100));
+
+        sink.append(SamplesBuffer::new(1, 1000, vec![0.5f32; 10]));
+        let handle = sink.append_controllable(SamplesBuffer::new(1, 1000, vec![0.25f32; 10000]));
+        sink.decode_worker.as_ref().unwrap().wait_idle();
+
+        // Decoding the sound in advance doesn't start it.
+        assert!(!handle.has_started());
+        assert!(queue_rx.by_ref().take(10).all(|s| s == 0.5));
+        assert!(!handle.has_started());
+        assert_eq!(queue_rx.next(), Some(0.25));
+        assert!(sink.wait_until_plays(&handle));
+        assert!(!handle.is_finished());
+    }
+
+    #[test]
+    fn test_append_controllable_decode_thread() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let sink = sink.with_decode_thread(Duration::from_millis(100));
+
+        let handle = sink.append_controllable(SamplesBuffer::new(1, 44100, vec![1.0f32; 44100]));
+        sink.decode_worker.as_ref().unwrap().wait_idle();
+
+        // The samples decoded in advance get the new volume too.
+        handle.set_volume(0.5);
+        assert!(queue_rx.by_ref().take(100).all(|s| s == 0.5));
+        handle.set_muted(true);
+        assert!(queue_rx.by_ref().skip(220).take(100).all(|s| s == 0.0));
+    }
+
+    #[test]
+    fn test_play_interruptible_decode_thread() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let sink = sink.with_decode_thread(Duration::from_millis(100));
+
+        sink.play_interruptible(SamplesBuffer::new(1, 1000, vec![1.0f32; 1000]));
+        sink.decode_worker.as_ref().unwrap().wait_idle();
+        assert!(queue_rx.by_ref().take(10).all(|s| s == 1.0));
+
+        // The replacing sound doesn't wait for the decoded part of the first one.
+        sink.play_interruptible(SamplesBuffer::new(1, 1000, vec![0.5f32; 1000]));
+        sink.decode_worker.as_ref().unwrap().wait_idle();
+        let output: Vec<f32> = queue_rx.by_ref().take(10).collect();
+        assert!(output[5..].iter().all(|&s| s == 0.5), "{:?}", output);
+    }
+
+    #[test]
+    fn test_append_looping_decode_thread() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let sink = sink.with_decode_thread(Duration::from_millis(100));
+
+        let handle = sink.append_looping(SamplesBuffer::new(1, 1000, vec![1.0f32; 10]), None);
+        sink.decode_worker.as_ref().unwrap().wait_idle();
+        assert!(queue_rx.by_ref().take(10).all(|s| s == 1.0));
+
+        // The loop fades out over 5 ms without playing the decoded part first.
+        handle.stop();
+        let output: Vec<f32> = queue_rx.by_ref().take(6).collect();
+        assert_eq!(output[5], 0.0);
+        assert!(sink.empty());
+    }
+
+    #[test]
+    fn test_append_looping() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+
+        let ramp = vec![0.1f32, 0.2, 0.3, 0.4];
+        let handle = sink.append_looping(SamplesBuffer::new(1, 1000, ramp.clone()), None);
+        // The loop goes on past the end of the sound.
+        let output: Vec<f32> = queue_rx.by_ref().take(18).collect();
+        let expected: Vec<f32> = ramp.iter().copied().cycle().take(18).collect();
+        assert_eq!(output, expected);
+
+        // Stopping fades the loop out over 5 ms.
+        handle.stop();
+        assert!(handle.is_stopped());
+        let output: Vec<f32> = queue_rx.by_ref().take(5).collect();
+        let expected = [0.3 * 0.8, 0.4 * 0.6, 0.1 * 0.4, 0.2 * 0.2, 0.0];
+        for (output, expected) in output.iter().zip(expected) {
+            assert!((output - expected).abs() < 1e-6);
+        }
+        assert_eq!(queue_rx.next(), Some(0.0));
+        assert!(sink.empty());
+
+        // With a crossfade, the loop goes on too.
+        let handle = sink.append_looping(
+            SamplesBuffer::new(1, 1000, vec![0.5f32; 20]),
+            Some(Duration::from_millis(5)),
+        );
+        let output: Vec<f32> = queue_rx.skip_while(|&s| s == 0.0).take(100).collect();
+        assert_eq!(output.len(), 100);
+        assert!(output.iter().all(|&s| (s - 0.5).abs() < 1e-6));
+        handle.stop();
+    }
+
+    #[test]
+    fn test_volume() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+
+        let v = vec![10i16, -10, 20, -20, 30, -30];
+
+        // High rate to avoid immediate control.
+        sink.append(SamplesBuffer::new(2, 44100, v.clone()));
+        let src = SamplesBuffer::new(2, 44100, v.clone()).convert_samples();
+
+        let mut src = src.amplify(0.5);
+        sink.set_volume(0.5);
+
+        for _ in 0..v.len() {
+            assert_eq!(queue_rx.next(), src.next());
+        }
+    }
+
+    #[test]
+    fn test_append_boxed() {
+        let (sink, queue_rx) = Sink::new_idle();
+        let (boxed_sink, boxed_queue_rx) = Sink::new_idle();
+
+        let source = SineWave::new(440.0).convert_samples::<i16>();
+        sink.append(source.clone().take_duration(Duration::from_millis(10)));
+        boxed_sink.append_boxed(Box::new(source.take_duration(Duration::from_millis(10))));
+
+        let output: Vec<f32> = queue_rx.take(441).collect();
+        let boxed_output: Vec<f32> = boxed_queue_rx.take(441).collect();
+        assert_eq!(output.len(), 441);
+        assert_eq!(output, boxed_output);
+    }
+
+    #[test]
+    fn test_detach() {
+        let v: Vec<i16> = (1..=100).collect();
+
+        let (sink, queue_rx) = Sink::new_idle();
+        sink.append(SamplesBuffer::new(1, 1000, v.clone()));
+        sink.detach();
+        let expected: Vec<f32> = v.iter().map(|s| s.to_f32()).collect();
+        assert_eq!(queue_rx.collect::<Vec<_>>(), expected);
+
+        // Without detaching, dropping the sink stops the sound.
+        let (sink, queue_rx) = Sink::new_idle();
+        sink.append(SamplesBuffer::new(1, 1000, v));
+        drop(sink);
+        assert!(queue_rx.count() < 100);
+    }
+
+    #[test]
+    fn test_position_watch() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let watch = sink.position_watch();
+        assert_eq!(watch.position(), Duration::ZERO);
+
+        sink.append(SamplesBuffer::new(2, 1000, vec![0i16; 2000]));
+        let mut previous = Duration::ZERO;
+        for _ in 0..10 {
+            queue_rx.by_ref().take(200).for_each(drop);
+            let position = watch.position();
+            assert!(position > previous);
+            previous = position;
+        }
+        assert!((previous.as_secs_f64() - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_position_watch_decode_thread() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let sink = sink.with_decode_thread(Duration::from_millis(100));
+        let watch = sink.position_watch();
+
+        // Decoding in advance doesn't move the position, only playing does.
+        sink.append(SamplesBuffer::new(1, 1000, vec![1i16; 1000]));
+        sink.decode_worker.as_ref().unwrap().wait_idle();
+        assert_eq!(watch.position(), Duration::ZERO);
+        queue_rx.by_ref().take(10).for_each(drop);
+        assert!((watch.position().as_secs_f64() - 0.01).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_position_watch_paused() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        let watch = sink.position_watch();
+
+        sink.append(SamplesBuffer::new(1, 1, vec![1i16; 40]));
+        queue_rx.next();
+        assert_eq!(watch.position(), Duration::from_secs(1));
+
+        sink.pause();
+        queue_rx.by_ref().take(20).for_each(drop);
+        assert_eq!(watch.position(), Duration::from_secs(1));
+
+        sink.play();
+        queue_rx.next();
+        assert_eq!(watch.position(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn test_play_interruptible() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+
+        sink.play_interruptible(SamplesBuffer::new(1, 1000, vec![1.0f32; 1000]));
+        assert!(queue_rx.by_ref().take(100).all(|s| s == 1.0));
+
+        // The second sound replaces the first one after a crossfade of 5 ms.
+        sink.play_interruptible(SamplesBuffer::new(1, 1000, vec![0.5f32; 1000]));
+        let output: Vec<f32> = queue_rx.by_ref().take(1000).collect();
+        let expected = [1.0, 0.9, 0.8, 0.7, 0.6, 0.5];
+        for (output, expected) in output.iter().zip(expected) {
+            assert!((output - expected).abs() < 1e-6);
+        }
+        assert!(output[5..].iter().all(|&s| s == 0.5));
+        assert_eq!(queue_rx.next(), Some(0.0));
+        assert!(sink.empty());
+
+        // Once the sound has ended, the next one is played normally.
+        sink.play_interruptible(SamplesBuffer::new(1, 1000, vec![0.25f32; 10]));
+        let output: Vec<f32> = queue_rx.skip_while(|&s| s == 0.0).take(10).collect();
+        assert_eq!(output, [0.25; 10]);
+    }
+
+    #[test]
+    fn test_play_from() {
+        let (sink, queue_rx) = Sink::new_idle();
+
+        // Forty seconds of a ramp, in which each frame holds its index.
+        let data: Vec<i16> = (0..40 * 100).flat_map(|i| [i as i16, i as i16]).collect();
+        sink.play_from(SamplesBuffer::new(2, 100, data), Duration::from_secs(30));
+
+        let output: Vec<f32> = queue_rx.take(4).collect();
+        let expected: Vec<f32> = [3000i16, 3000, 3001, 3001]
+            .iter()
+            .map(|s| s.to_f32())
+            .collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn test_volume_curve() {
+        assert_eq!(VolumeCurve::Linear.gain(0.5), 0.5);
+        assert_eq!(VolumeCurve::Logarithmic.gain(1.0), 1.0);
+        assert_eq!(VolumeCurve::Logarithmic.gain(0.0), 0.0);
+        // The midpoint is 30 dB below the normal volume, and every step is the same in decibels.
+        let midpoint = VolumeCurve::Logarithmic.gain(0.5);
+        assert!((20.0 * midpoint.log10() + 30.0).abs() < 1e-3);
+        let db = |volume: f32| 20.0 * VolumeCurve::Logarithmic.gain(volume).log10();
+        assert!((db(0.9) - db(0.8) - 6.0).abs() < 1e-3);
+        assert!((db(0.2) - db(0.1) - 6.0).abs() < 1e-3);
+
+        let (sink, queue_rx) = Sink::new_idle();
+        sink.set_volume_curve(VolumeCurve::Logarithmic);
+        sink.set_volume(0.5);
+        assert_eq!(sink.volume(), 0.5);
+
+        sink.append(SamplesBuffer::new(1, 44100, vec![1.0f32; 4]));
+        for sample in queue_rx.take(4) {
+            assert!((sample - midpoint).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn test_set_pan() {
+        let (sink, mut queue_rx) = Sink::new_idle();
+        sink.set_pan(-1.0);
+        assert_eq!(sink.pan(), -1.0);
+
+        // Both channels end up on the left one.
+        sink.append(SamplesBuffer::new(2, 44100, vec![0.5f32, 0.25, 0.5, 0.25]));
+        let output: Vec<f32> = queue_rx.by_ref().take(4).collect();
+        for (output, expected) in output.iter().zip([0.75, 0.0, 0.75, 0.0]) {
+            assert!((output - expected).abs() < 1e-6);
+        }
+
+        // The change applies to the sound being played, and the center leaves it unchanged.
+        sink.append(SamplesBuffer::new(2, 44100, vec![0.5f32, 0.25, 0.5, 0.25]));
+        assert_eq!(queue_rx.next(), Some(0.75));
+        assert!(queue_rx.next().unwrap().abs() < 1e-6);
+        sink.set_pan(0.0);
+        let output: Vec<f32> = queue_rx.take(2).collect();
+        assert_eq!(output, [0.5, 0.25]);
+    }
+}
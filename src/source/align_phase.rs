@@ -0,0 +1,147 @@
+use cpal::Sample as CpalSample;
+
+use crate::buffer::SamplesBuffer;
+use crate::source::spectrum::fft;
+use crate::{Sample, Source};
+
+/// Finds the delay that best aligns `target` with `reference`, and returns `target` with this
+/// delay removed along with the delay.
+///
+/// The delay is in frames. It is positive when `target` comes after `reference`, in which case
+/// its beginning is dropped, and negative when it comes before, in which case silence is added
+/// in front of it. The delay is the one that maximizes the cross-correlation of both sources
+/// mixed down to mono, which is computed with a FFT.
+///
+/// Both sources are read entirely, so they must not be infinite. They should have the same
+/// sample rate. The returned buffer has the channels and sample rate of `target`.
+pub fn align_phase<R, T>(reference: R, target: T) -> (SamplesBuffer<T::Item>, i64)
+where
+    R: Source,
+    R::Item: Sample,
+    T: Source,
+    T::Item: Sample,
+{
+    let channels = target.channels();
+    let sample_rate = target.sample_rate();
+    let reference = mono(reference);
+    let target: Vec<T::Item> = target.collect();
+    let target_mono: Vec<f32> = target
+        .chunks(channels as usize)
+        .map(|frame| frame.iter().map(|s| s.to_f32()).sum::<f32>() / channels as f32)
+        .collect();
+
+    let offset = best_lag(&reference, &target_mono);
+    let skip = offset.max(0) as usize * channels as usize;
+    let padding = (-offset).max(0) as usize * channels as usize;
+    let mut data = vec![T::Item::zero_value(); padding];
+    data.extend(target.into_iter().skip(skip));
+
+    (SamplesBuffer::new(channels, sample_rate, data), offset)
+}
+
+/// Mixes a source down to mono.
+fn mono<I>(mut source: I) -> Vec<f32>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let mut output = Vec::new();
+    loop {
+        let channels = source.channels().max(1);
+        let frame: Vec<f32> = source
+            .by_ref()
+            .take(channels as usize)
+            .map(|s| s.to_f32())
+            .collect();
+        if frame.len() < channels as usize {
+            return output;
+        }
+        output.push(frame.iter().sum::<f32>() / channels as f32);
+    }
+}
+
+/// Returns the delay of `target` relative to `reference` that maximizes their cross-correlation.
+fn best_lag(reference: &[f32], target: &[f32]) -> i64 {
+    if reference.is_empty() || target.is_empty() {
+        return 0;
+    }
+
+    let size = (reference.len() + target.len()).next_power_of_two();
+    let transform = |samples: &[f32]| {
+        let mut re = samples.to_vec();
+        re.resize(size, 0.0);
+        let mut im = vec![0.0; size];
+        fft(&mut re, &mut im);
+        (re, im)
+    };
+    let (target_re, target_im) = transform(target);
+    let (reference_re, reference_im) = transform(reference);
+
+    // The cross-correlation is the inverse transform of `T * conj(R)`. The inverse transform is
+    // computed as the conjugate of the transform of the conjugate.
+    let mut re: Vec<f32> = (0..size)
+        .map(|k| target_re[k] * reference_re[k] + target_im[k] * reference_im[k])
+        .collect();
+    let mut im: Vec<f32> = (0..size)
+        .map(|k| -(target_im[k] * reference_re[k] - target_re[k] * reference_im[k]))
+        .collect();
+    fft(&mut re, &mut im);
+
+    // Index `k` holds the delay `k`, and negative delays wrap around to the end.
+    let lags = -(reference.len() as i64 - 1)..target.len() as i64;
+    lags.max_by(|&a, &b| {
+        let value = |lag: i64| re[lag.rem_euclid(size as i64) as usize];
+        value(a).total_cmp(&value(b))
+    })
+    .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::align_phase;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    /// Noise that is easy to correlate.
+    fn noise(len: usize) -> Vec<f32> {
+        let mut seed = 0x2545_f491u32;
+        (0..len)
+            .map(|_| {
+                seed ^= seed << 13;
+                seed ^= seed >> 17;
+                seed ^= seed << 5;
+                seed as f32 / u32::MAX as f32 - 0.5
+            })
+            .collect()
+    }
+
+    #[test]
+    fn finds_delay() {
+        let signal = noise(4000);
+        let mut delayed = vec![0.0; 37];
+        delayed.extend_from_slice(&signal);
+        let stereo: Vec<f32> = delayed.iter().flat_map(|&s| [s, s]).collect();
+
+        let reference = SamplesBuffer::new(1, 48000, signal.clone());
+        let target = SamplesBuffer::new(2, 48000, stereo);
+        let (aligned, offset) = align_phase(reference, target);
+        assert_eq!(offset, 37);
+        assert_eq!(aligned.channels(), 2);
+
+        let aligned: Vec<f32> = aligned.step_by(2).collect();
+        assert_eq!(aligned, signal);
+    }
+
+    #[test]
+    fn finds_advance() {
+        let signal = noise(4000);
+        let reference = SamplesBuffer::new(1, 48000, signal.clone());
+        let target = SamplesBuffer::new(1, 48000, signal[10..].to_vec());
+        let (aligned, offset) = align_phase(reference, target);
+        assert_eq!(offset, -10);
+
+        let aligned: Vec<f32> = aligned.collect();
+        assert_eq!(aligned[..10], [0.0; 10]);
+        assert_eq!(aligned[10..], signal[10..]);
+    }
+}
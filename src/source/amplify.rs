@@ -93,4 +93,43 @@ where
     fn total_duration(&self) -> Option<Duration> {
         self.input.total_duration()
     }
+
+    #[inline]
+    fn next_block(&mut self, out: &mut [I::Item]) -> usize {
+        let written = self.input.next_block(out);
+        for sample in &mut out[..written] {
+            *sample = sample.amplify(self.factor);
+        }
+        written
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{from_iter, Source};
+
+    #[test]
+    fn next_block_matches_next() {
+        let data: Vec<i16> = (0..1000).map(|i| (i * 37 % 2000) as i16 - 1000).collect();
+
+        // `SamplesBuffer` and `Amplify` override `next_block`, while `FromIter` uses the
+        // default implementation.
+        let mut overridden = SamplesBuffer::new(2, 44100, data.clone()).amplify(0.7);
+        let mut default = from_iter(Some(SamplesBuffer::new(2, 44100, data.clone()))).amplify(0.7);
+        let expected: Vec<i16> = SamplesBuffer::new(2, 44100, data).amplify(0.7).collect();
+
+        for source in [&mut overridden as &mut dyn Source<Item = i16>, &mut default] {
+            let mut output = Vec::new();
+            let mut block = [0i16; 64];
+            loop {
+                let written = source.next_block(&mut block);
+                output.extend_from_slice(&block[..written]);
+                if written < block.len() {
+                    break;
+                }
+            }
+            assert_eq!(output, expected);
+        }
+    }
 }
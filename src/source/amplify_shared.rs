@@ -0,0 +1,154 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds an `AmplifyShared` object and its handle.
+pub fn amplify_shared<I>(input: I, factor: f32) -> (AmplifyShared<I>, VolumeHandle)
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let handle = VolumeHandle {
+        factor: Arc::new(AtomicU32::new(factor.to_bits())),
+    };
+    let source = AmplifyShared {
+        input,
+        handle: handle.clone(),
+    };
+    (source, handle)
+}
+
+/// Changes the volume of an `AmplifyShared` while it is playing.
+///
+/// This can be cloned and sent to another thread.
+#[derive(Clone, Debug)]
+pub struct VolumeHandle {
+    // The factor, stored as the bits of an `f32`.
+    factor: Arc<AtomicU32>,
+}
+
+impl VolumeHandle {
+    /// Returns the value that the samples are multiplied by.
+    #[inline]
+    pub fn volume(&self) -> f32 {
+        f32::from_bits(self.factor.load(Ordering::Relaxed))
+    }
+
+    /// Changes the value that the samples are multiplied by. The change applies from the next
+    /// sample.
+    #[inline]
+    pub fn set_volume(&self, factor: f32) {
+        self.factor.store(factor.to_bits(), Ordering::Relaxed);
+    }
+}
+
+/// Filter that multiplies each sample by a value that can be changed through a `VolumeHandle`.
+#[derive(Clone, Debug)]
+pub struct AmplifyShared<I> {
+    input: I,
+    handle: VolumeHandle,
+}
+
+impl<I> AmplifyShared<I> {
+    /// Returns a handle that changes the volume.
+    #[inline]
+    pub fn handle(&self) -> VolumeHandle {
+        self.handle.clone()
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for AmplifyShared<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let factor = self.handle.volume();
+        self.input.next().map(|value| value.amplify(factor))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for AmplifyShared<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for AmplifyShared<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn volume_changed_from_other_thread() {
+        let input = SamplesBuffer::new(1, 44100, vec![0.5f32; 4]);
+        let (mut source, handle) = input.amplify_shared();
+        assert_eq!(handle.volume(), 1.0);
+        assert_eq!(source.next(), Some(0.5));
+
+        let other = handle.clone();
+        thread::spawn(move || other.set_volume(0.5)).join().unwrap();
+        assert_eq!(handle.volume(), 0.5);
+        assert_eq!(source.next(), Some(0.25));
+
+        handle.set_volume(0.0);
+        assert_eq!(source.collect::<Vec<_>>(), vec![0.0, 0.0]);
+    }
+}
@@ -0,0 +1,100 @@
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Statistics about a whole source, returned by [`analyze`].
+///
+/// All the levels are linear amplitudes, where `1.0` is full scale.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AudioStats {
+    /// Highest absolute value of any sample.
+    pub peak: f32,
+    /// Root mean square of all the samples.
+    pub rms: f32,
+    /// Number of samples at or above full scale.
+    pub clipped_samples: u64,
+    /// Average value of all the samples. Anything far from zero hints at a faulty recording.
+    pub dc_offset: f32,
+    /// Duration of the source.
+    pub duration: Duration,
+}
+
+/// Reads a whole source and returns statistics about its samples.
+///
+/// This consumes the source, so use `buffered` first if it must be played afterwards. The source
+/// must not be infinite.
+pub fn analyze<I>(mut source: I) -> AudioStats
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let mut peak = 0f32;
+    let mut sum = 0f64;
+    let mut sum_squares = 0f64;
+    let mut clipped_samples = 0;
+    let mut samples = 0u64;
+    let mut seconds = 0f64;
+
+    loop {
+        // The format of the source can change after each frame.
+        let sample_duration = 1.0 / (source.sample_rate() as f64 * source.channels() as f64);
+        let sample = match source.next() {
+            Some(sample) => sample.to_f32(),
+            None => break,
+        };
+
+        peak = peak.max(sample.abs());
+        if sample.abs() >= 1.0 {
+            clipped_samples += 1;
+        }
+        sum += sample as f64;
+        sum_squares += sample as f64 * sample as f64;
+        samples += 1;
+        seconds += sample_duration;
+    }
+
+    let count = samples.max(1) as f64;
+    AudioStats {
+        peak,
+        rms: (sum_squares / count).sqrt() as f32,
+        clipped_samples,
+        dc_offset: (sum / count) as f32,
+        duration: Duration::from_secs_f64(seconds),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::analyze;
+    use crate::buffer::SamplesBuffer;
+
+    #[test]
+    fn known_stats() {
+        // A square wave between 0.9 and -0.5, with a DC offset of 0.2.
+        let mut data: Vec<f32> = (0..1000)
+            .map(|i| if i % 2 == 0 { 0.9 } else { -0.5 })
+            .collect();
+        // Two clipped samples.
+        data[10] = 1.0;
+        data[11] = -1.5;
+        let stats = analyze(SamplesBuffer::new(2, 1000, data));
+
+        assert_eq!(stats.peak, 1.5);
+        assert_eq!(stats.clipped_samples, 2);
+        assert!((stats.dc_offset - (0.2 + (0.1 - 1.0) / 1000.0)).abs() < 1e-4);
+        assert!((stats.rms - 0.728).abs() < 1e-2);
+        assert!((stats.duration.as_secs_f64() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn empty_source() {
+        let stats = analyze(SamplesBuffer::<i16>::new(1, 1000, vec![]));
+        assert_eq!(stats.peak, 0.0);
+        assert_eq!(stats.rms, 0.0);
+        assert_eq!(stats.duration, Duration::ZERO);
+    }
+}
@@ -0,0 +1,211 @@
+use std::time::Duration;
+
+use crate::source::UniformSourceIterator;
+use crate::{Sample, Source};
+
+/// Internal function that builds an `AtRate` object.
+pub fn at_rate<I>(input: I, sample_rate: u32) -> AtRate<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let inner = if input.sample_rate() == sample_rate {
+        AtRateInner::Passthrough(input)
+    } else {
+        let channels = input.channels();
+        AtRateInner::Resampled(UniformSourceIterator::new(input, channels, sample_rate))
+    };
+
+    AtRate { inner }
+}
+
+/// Internal function that builds an `AtRate` object that resamples with linear interpolation.
+pub fn resample_linear<I>(input: I, sample_rate: u32) -> AtRate<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let inner = if input.sample_rate() == sample_rate {
+        AtRateInner::Passthrough(input)
+    } else {
+        let channels = input.channels();
+        AtRateInner::Resampled(UniformSourceIterator::new_linear(
+            input,
+            channels,
+            sample_rate,
+        ))
+    };
+
+    AtRate { inner }
+}
+
+/// A source whose samples are at a fixed sample rate.
+///
+/// The resampler is only used if the sample rate of the inner source is different from the
+/// requested one when the `AtRate` is built. Otherwise the samples are passed through untouched.
+#[derive(Clone)]
+pub struct AtRate<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    inner: AtRateInner<I>,
+}
+
+#[derive(Clone)]
+enum AtRateInner<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    Passthrough(I),
+    Resampled(UniformSourceIterator<I, I::Item>),
+}
+
+impl<I> AtRate<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns true if the samples of the inner source are passed through without resampling.
+    #[inline]
+    pub fn is_passthrough(&self) -> bool {
+        matches!(self.inner, AtRateInner::Passthrough(_))
+    }
+}
+
+impl<I> Iterator for AtRate<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        match &mut self.inner {
+            AtRateInner::Passthrough(input) => input.next(),
+            AtRateInner::Resampled(input) => input.next(),
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        match &self.inner {
+            AtRateInner::Passthrough(input) => input.size_hint(),
+            AtRateInner::Resampled(input) => input.size_hint(),
+        }
+    }
+}
+
+impl<I> Source for AtRate<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        match &self.inner {
+            AtRateInner::Passthrough(input) => input.current_frame_len(),
+            AtRateInner::Resampled(input) => input.current_frame_len(),
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        match &self.inner {
+            AtRateInner::Passthrough(input) => input.channels(),
+            AtRateInner::Resampled(input) => input.channels(),
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        match &self.inner {
+            AtRateInner::Passthrough(input) => input.sample_rate(),
+            AtRateInner::Resampled(input) => input.sample_rate(),
+        }
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        match &self.inner {
+            AtRateInner::Passthrough(input) => input.total_duration(),
+            AtRateInner::Resampled(input) => input.total_duration(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn resamples_when_needed() {
+        let source = SamplesBuffer::new(2, 44100, vec![0i16; 882]).at_rate(48000);
+        assert!(!source.is_passthrough());
+        assert_eq!(source.sample_rate(), 48000);
+        assert_eq!(source.channels(), 2);
+    }
+
+    #[test]
+    fn resample_linear() {
+        let source = SamplesBuffer::new(1, 1000, vec![0.0f32, 1.0, 0.0, 1.0]).resample_linear(2000);
+        assert_eq!(source.sample_rate(), 2000);
+
+        // New samples are interpolated between the original ones.
+        let output: Vec<f32> = source.collect();
+        assert_eq!(&output[..4], &[0.0, 0.5, 1.0, 0.5]);
+    }
+
+    #[test]
+    fn resample_linear_integer_ratio() {
+        let data: Vec<f32> = (0..96).map(|n| (1 - n % 2) as f32).collect();
+        let source = SamplesBuffer::new(1, 48000, data).resample_linear(24000);
+        assert_eq!(source.sample_rate(), 24000);
+
+        // Every other sample is kept, without going through the anti-aliasing filter.
+        let output: Vec<f32> = source.collect();
+        assert_eq!(output.len(), 48);
+        assert!(output.iter().all(|&s| s == 1.0));
+    }
+
+    #[test]
+    fn decimates_across_frames() {
+        use std::f32::consts::PI;
+
+        use crate::source::from_iter;
+
+        let data: Vec<f32> = (0..4800)
+            .map(|n| (2.0 * PI * 1000.0 * n as f32 / 48000.0).sin())
+            .collect();
+        let whole: Vec<f32> = SamplesBuffer::new(1, 48000, data.clone())
+            .at_rate(24000)
+            .collect();
+
+        // Decoders report short frames, which must not restart the filter.
+        let frames: Vec<_> = data
+            .chunks(75)
+            .map(|chunk| SamplesBuffer::new(1, 48000, chunk.to_vec()))
+            .collect();
+        let source = from_iter(frames);
+        assert_eq!(source.current_frame_len(), Some(75));
+        let chunked: Vec<f32> = source.at_rate(24000).collect();
+
+        assert_eq!(chunked.len(), whole.len());
+        assert!(chunked
+            .iter()
+            .zip(&whole)
+            .all(|(a, b)| (a - b).abs() < 1e-6));
+    }
+
+    #[test]
+    fn passes_through_matching_rate() {
+        let data = vec![1i16, -2, 3, -4, 5, -6];
+        let source = SamplesBuffer::new(2, 48000, data.clone()).at_rate(48000);
+        assert!(source.is_passthrough());
+        assert_eq!(source.sample_rate(), 48000);
+        assert_eq!(source.collect::<Vec<_>>(), data);
+    }
+}
@@ -0,0 +1,329 @@
+use std::f32::consts::PI;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Distance between the center of the head and each ear, in meters.
+const HEAD_RADIUS: f32 = 0.0875;
+
+/// Speed of sound, in meters per second.
+const SPEED_OF_SOUND: f32 = 343.0;
+
+/// Cutoff frequency of the head shadow for a sound that comes from the opposite side of the head.
+const SHADOW_CUTOFF: f32 = 1500.0;
+
+/// Cutoff frequency above which the head shadow is not modeled.
+const OPEN_CUTOFF: f32 = 20000.0;
+
+/// Level of the ear opposite to a sound coming from the side, relative to the near ear.
+const SHADOW_GAIN: f32 = 0.8;
+
+/// Internal function that builds a `Binaural` object.
+pub fn binaural<I>(input: I) -> Binaural<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let mut binaural = Binaural {
+        input,
+        speakers: Vec::new(),
+        history: Vec::new(),
+        history_len: 1,
+        position: 0,
+        right: None,
+    };
+    binaural.set_layout();
+    binaural
+}
+
+/// Filter that renders a surround sound to two channels meant for headphones.
+///
+/// Each channel of the input is played by a virtual speaker placed around the listener, at the
+/// usual position of that channel in a 5.1 or 7.1 setup. The sound of a speaker reaches the ear
+/// on the opposite side of the head a bit later, slightly quieter, and with fewer high
+/// frequencies, which is what lets the listener locate it.
+///
+/// Mono and stereo sources are played by a center speaker and by a pair of front speakers. The
+/// channels of other layouts are all played in front of the listener.
+#[derive(Clone, Debug)]
+pub struct Binaural<I> {
+    input: I,
+    speakers: Vec<Speaker>,
+    // Last samples of each input channel, interleaved, for the delays of the far ears.
+    history: Vec<f32>,
+    // Number of frames kept in `history`.
+    history_len: usize,
+    position: usize,
+    // The right channel of the current frame, once the left one has been returned.
+    right: Option<f32>,
+}
+
+/// How the sound of a virtual speaker reaches the two ears.
+#[derive(Clone, Debug)]
+struct Speaker {
+    // Level of the speaker in the mix.
+    weight: f32,
+    left: Ear,
+    right: Ear,
+}
+
+/// How the sound of a virtual speaker reaches one ear.
+#[derive(Clone, Debug)]
+struct Ear {
+    // Delay in frames.
+    delay: usize,
+    gain: f32,
+    // Coefficient of the one-pole lowpass filter, `1.0` when there is no filtering.
+    lowpass: f32,
+    state: f32,
+}
+
+impl Ear {
+    /// Builds the path to an ear. `shadow` goes from `0.0` when the ear faces the speaker to `1.0`
+    /// when the speaker is on the opposite side of the head.
+    fn new(shadow: f32, sample_rate: u32) -> Ear {
+        if shadow <= 0.0 {
+            return Ear {
+                delay: 0,
+                gain: 1.0,
+                lowpass: 1.0,
+                state: 0.0,
+            };
+        }
+
+        // Woodworth's formula for the difference of arrival time between both ears.
+        let itd = HEAD_RADIUS / SPEED_OF_SOUND * (shadow.asin() + shadow);
+        let cutoff = (OPEN_CUTOFF * (SHADOW_CUTOFF / OPEN_CUTOFF).powf(shadow))
+            .min(0.45 * sample_rate as f32);
+        Ear {
+            delay: (itd * sample_rate as f32).round() as usize,
+            gain: 1.0 - (1.0 - SHADOW_GAIN) * shadow,
+            lowpass: 1.0 - (-2.0 * PI * cutoff / sample_rate as f32).exp(),
+            state: 0.0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, value: f32) -> f32 {
+        self.state += (value - self.state) * self.lowpass;
+        self.state * self.gain
+    }
+}
+
+impl Speaker {
+    /// Builds a virtual speaker at the given azimuth, in degrees. Negative angles are on the left.
+    fn new(azimuth: f32, weight: f32, sample_rate: u32) -> Speaker {
+        let lateral = azimuth.to_radians().sin();
+        Speaker {
+            weight,
+            left: Ear::new(lateral.max(0.0), sample_rate),
+            right: Ear::new((-lateral).max(0.0), sample_rate),
+        }
+    }
+}
+
+/// Returns the azimuth and weight of each channel, following the usual order of the layouts.
+///
+/// The weights are the ones of the usual surround to stereo downmix.
+fn layout(channels: u16) -> Vec<(f32, f32)> {
+    const SIDE: f32 = std::f32::consts::FRAC_1_SQRT_2;
+    match channels {
+        1 => vec![(0.0, 1.0)],
+        2 => vec![(-30.0, 1.0), (30.0, 1.0)],
+        // Front left, front right, center, LFE, surround left and surround right.
+        6 => vec![
+            (-30.0, 1.0),
+            (30.0, 1.0),
+            (0.0, SIDE),
+            (0.0, SIDE),
+            (-110.0, SIDE),
+            (110.0, SIDE),
+        ],
+        // Front left, front right, center, LFE, back left, back right, side left and side right.
+        8 => vec![
+            (-30.0, 1.0),
+            (30.0, 1.0),
+            (0.0, SIDE),
+            (0.0, SIDE),
+            (-150.0, SIDE),
+            (150.0, SIDE),
+            (-90.0, SIDE),
+            (90.0, SIDE),
+        ],
+        _ => vec![(0.0, 1.0 / channels.max(1) as f32); channels as usize],
+    }
+}
+
+impl<I> Binaural<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    /// Places the virtual speakers for the current number of channels of the inner source.
+    fn set_layout(&mut self) {
+        let sample_rate = self.input.sample_rate();
+        self.speakers = layout(self.input.channels())
+            .into_iter()
+            .map(|(azimuth, weight)| Speaker::new(azimuth, weight, sample_rate))
+            .collect();
+        let max_delay = self
+            .speakers
+            .iter()
+            .map(|speaker| speaker.left.delay.max(speaker.right.delay))
+            .max()
+            .unwrap_or(0);
+        self.history_len = max_delay + 1;
+        self.history = vec![0.0; self.history_len * self.speakers.len()];
+        self.position = 0;
+    }
+}
+
+impl<I> Iterator for Binaural<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if let Some(right) = self.right.take() {
+            return Some(CpalSample::from(&right));
+        }
+
+        if self.input.channels() as usize != self.speakers.len() {
+            self.set_layout();
+        }
+
+        let channels = self.speakers.len();
+        let frame = self.position * channels;
+        for channel in 0..channels {
+            self.history[frame + channel] = self.input.next()?.to_f32();
+        }
+
+        let (history, history_len, position) = (&self.history, self.history_len, self.position);
+        let (mut left, mut right) = (0.0, 0.0);
+        for (channel, speaker) in self.speakers.iter_mut().enumerate() {
+            let weight = speaker.weight;
+            let delayed = |delay: usize| {
+                let position = (position + history_len - delay) % history_len;
+                history[position * channels + channel] * weight
+            };
+            let (left_input, right_input) =
+                (delayed(speaker.left.delay), delayed(speaker.right.delay));
+            left += speaker.left.process(left_input);
+            right += speaker.right.process(right_input);
+        }
+        self.position = (self.position + 1) % self.history_len;
+
+        self.right = Some(right);
+        Some(CpalSample::from(&left))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let channels = self.input.channels().max(1) as usize;
+        let pending = self.right.is_some() as usize;
+        let (min, max) = self.input.size_hint();
+        (
+            min / channels * 2 + pending,
+            max.map(|max| max / channels * 2 + pending),
+        )
+    }
+}
+
+impl<I> Source for Binaural<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let channels = self.input.channels().max(1) as usize;
+        let pending = self.right.is_some() as usize;
+        self.input
+            .current_frame_len()
+            .map(|len| len / channels * 2 + pending)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{SineWave, Source};
+
+    /// Builds a 5.1 source that only plays a sine wave on the given channel.
+    fn surround(channel: usize) -> SamplesBuffer<f32> {
+        let data = SineWave::new(440.0)
+            .take(4800)
+            .flat_map(|value| {
+                let mut frame = [0.0; 6];
+                frame[channel] = value;
+                frame
+            })
+            .collect::<Vec<_>>();
+        SamplesBuffer::new(6, 48000, data)
+    }
+
+    fn energies(source: impl Source<Item = f32>) -> (f32, f32) {
+        assert_eq!(source.channels(), 2);
+        let samples: Vec<f32> = source.collect();
+        assert_eq!(samples.len(), 4800 * 2);
+        samples.chunks(2).fold((0.0, 0.0), |(left, right), frame| {
+            (left + frame[0] * frame[0], right + frame[1] * frame[1])
+        })
+    }
+
+    #[test]
+    fn center_is_centered() {
+        let source = surround(2).surround_to_binaural();
+        let samples: Vec<f32> = source.collect();
+        assert!(samples.iter().any(|&value| value.abs() > 0.5));
+        for frame in samples.chunks(2) {
+            assert_eq!(frame[0], frame[1]);
+        }
+    }
+
+    #[test]
+    fn sides_are_placed() {
+        let (left, right) = energies(surround(0).surround_to_binaural());
+        assert!(left > right * 1.2);
+        let (left, right) = energies(surround(5).surround_to_binaural());
+        assert!(right > left * 1.2);
+    }
+}
@@ -0,0 +1,148 @@
+use std::f32::consts::PI;
+use std::ops::{Add, Mul, Sub};
+
+// Implemented following http://www.musicdsp.org/files/Audio-EQ-Cookbook.txt
+
+/// The coefficients of a biquad filter, normalized so that `a0` is 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Coefficients<T = f32> {
+    pub b0: T,
+    pub b1: T,
+    pub b2: T,
+    pub a1: T,
+    pub a2: T,
+}
+
+impl<T> Coefficients<T> {
+    /// Builds coefficients that are already divided by `a0`.
+    #[inline]
+    pub fn from_normalized(b: [T; 3], a: [T; 2]) -> Coefficients<T> {
+        let [b0, b1, b2] = b;
+        let [a1, a2] = a;
+        Coefficients { b0, b1, b2, a1, a2 }
+    }
+}
+
+impl Coefficients<f32> {
+    /// A low-pass filter. A `q` of `FRAC_1_SQRT_2` gives a Butterworth filter.
+    pub fn low_pass(freq: f32, q: f32, sample_rate: f32) -> Coefficients {
+        let (cos_w0, alpha) = cos_alpha(freq, q, sample_rate);
+        Coefficients::normalize(
+            [(1.0 - cos_w0) / 2.0, 1.0 - cos_w0, (1.0 - cos_w0) / 2.0],
+            [1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha],
+        )
+    }
+
+    /// A high-pass filter. A `q` of `FRAC_1_SQRT_2` gives a Butterworth filter.
+    pub fn high_pass(freq: f32, q: f32, sample_rate: f32) -> Coefficients {
+        let (cos_w0, alpha) = cos_alpha(freq, q, sample_rate);
+        Coefficients::normalize(
+            [(1.0 + cos_w0) / 2.0, -1.0 - cos_w0, (1.0 + cos_w0) / 2.0],
+            [1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha],
+        )
+    }
+
+    /// An allpass filter, whose phase turns around `freq`.
+    pub fn all_pass(freq: f32, q: f32, sample_rate: f32) -> Coefficients {
+        let (cos_w0, alpha) = cos_alpha(freq, q, sample_rate);
+        Coefficients::normalize(
+            [1.0 - alpha, -2.0 * cos_w0, 1.0 + alpha],
+            [1.0 + alpha, -2.0 * cos_w0, 1.0 - alpha],
+        )
+    }
+
+    /// A peaking filter, which changes the gain around `freq` by `gain_db`.
+    pub fn peaking(freq: f32, q: f32, gain_db: f32, sample_rate: f32) -> Coefficients {
+        let (cos_w0, alpha) = cos_alpha(freq, q, sample_rate);
+        let a = 10f32.powf(gain_db / 40.0);
+        Coefficients::normalize(
+            [1.0 + alpha * a, -2.0 * cos_w0, 1.0 - alpha * a],
+            [1.0 + alpha / a, -2.0 * cos_w0, 1.0 - alpha / a],
+        )
+    }
+
+    #[inline]
+    fn normalize(b: [f32; 3], a: [f32; 3]) -> Coefficients {
+        Coefficients::from_normalized(
+            [b[0] / a[0], b[1] / a[0], b[2] / a[0]],
+            [a[1] / a[0], a[2] / a[0]],
+        )
+    }
+}
+
+/// Returns `cos(w0)` and `alpha` as defined by the cookbook.
+#[inline]
+fn cos_alpha(freq: f32, q: f32, sample_rate: f32) -> (f32, f32) {
+    let w0 = 2.0 * PI * freq / sample_rate;
+    (w0.cos(), w0.sin() / (2.0 * q))
+}
+
+/// A biquad filter applied to a single channel.
+#[derive(Clone, Debug, Default)]
+pub struct Biquad<T = f32> {
+    coefficients: Coefficients<T>,
+    // `[x_n1, x_n2, y_n1, y_n2]`
+    history: [T; 4],
+}
+
+impl<T> Biquad<T>
+where
+    T: Copy + Default + Add<Output = T> + Sub<Output = T> + Mul<Output = T>,
+{
+    #[inline]
+    pub fn new(coefficients: Coefficients<T>) -> Biquad<T> {
+        Biquad {
+            coefficients,
+            history: [T::default(); 4],
+        }
+    }
+
+    /// Changes the coefficients while keeping the history of the filter.
+    #[inline]
+    pub fn set_coefficients(&mut self, coefficients: Coefficients<T>) {
+        self.coefficients = coefficients;
+    }
+
+    #[inline]
+    pub fn process(&mut self, x_n: T) -> T {
+        let c = &self.coefficients;
+        let [x_n1, x_n2, y_n1, y_n2] = self.history;
+        let y_n = c.b0 * x_n + c.b1 * x_n1 + c.b2 * x_n2 - c.a1 * y_n1 - c.a2 * y_n2;
+        self.history = [x_n, x_n1, y_n, y_n1];
+        y_n
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+    use super::{Biquad, Coefficients};
+
+    fn gain(coefficients: Coefficients, freq: f32) -> f32 {
+        let mut filter = Biquad::new(coefficients);
+        let output: Vec<f32> = (0..48000)
+            .map(|i| filter.process((2.0 * PI * freq * i as f32 / 48000.0).sin()))
+            .collect();
+        output[24000..].iter().fold(0.0f32, |m, s| m.max(s.abs()))
+    }
+
+    #[test]
+    fn responses() {
+        let low = Coefficients::low_pass(1000.0, FRAC_1_SQRT_2, 48000.0);
+        assert!((gain(low, 100.0) - 1.0).abs() < 0.01);
+        assert!(gain(low, 10000.0) < 0.02);
+
+        let high = Coefficients::high_pass(1000.0, FRAC_1_SQRT_2, 48000.0);
+        assert!(gain(high, 100.0) < 0.02);
+        assert!((gain(high, 10000.0) - 1.0).abs() < 0.01);
+
+        let all = Coefficients::all_pass(1000.0, FRAC_1_SQRT_2, 48000.0);
+        assert!((gain(all, 100.0) - 1.0).abs() < 0.01);
+        assert!((gain(all, 10000.0) - 1.0).abs() < 0.01);
+
+        let peaking = Coefficients::peaking(1000.0, 1.0, 6.0, 48000.0);
+        assert!((gain(peaking, 1000.0) - 10f32.powf(6.0 / 20.0)).abs() < 0.01);
+        assert!((gain(peaking, 50.0) - 1.0).abs() < 0.02);
+    }
+}
@@ -0,0 +1,199 @@
+use std::cmp;
+use std::time::Duration;
+
+use crate::source::uniform::UniformSourceIterator;
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Blend` object.
+///
+/// # Panic
+///
+/// Panics if `ratio` isn't between 0 and 1.
+pub fn blend<I1, I2>(input1: I1, input2: I2, ratio: f32) -> Blend<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    assert!(
+        (0.0..=1.0).contains(&ratio),
+        "the ratio must be between 0 and 1"
+    );
+
+    let channels = input1.channels();
+    let rate = input1.sample_rate();
+
+    Blend {
+        input1: UniformSourceIterator::new(input1, channels, rate),
+        input2: UniformSourceIterator::new(input2, channels, rate),
+        ratio,
+    }
+}
+
+/// Filter that adds two sources together with a constant balance between them.
+#[derive(Clone)]
+pub struct Blend<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    input1: UniformSourceIterator<I1, I1::Item>,
+    input2: UniformSourceIterator<I2, I1::Item>,
+    // Weight of `input2`. `input1` is weighted by `1 - ratio`.
+    ratio: f32,
+}
+
+impl<I1, I2> Blend<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    /// Returns the weight of the second source, between 0 and 1.
+    #[inline]
+    pub fn ratio(&self) -> f32 {
+        self.ratio
+    }
+
+    /// Changes the weight of the second source. The first source is weighted by `1 - ratio`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `ratio` isn't between 0 and 1.
+    #[inline]
+    pub fn set_ratio(&mut self, ratio: f32) {
+        assert!(
+            (0.0..=1.0).contains(&ratio),
+            "the ratio must be between 0 and 1"
+        );
+        self.ratio = ratio;
+    }
+}
+
+impl<I1, I2> Iterator for Blend<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    type Item = I1::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I1::Item> {
+        let s1 = self.input1.next().map(|s| s.amplify(1.0 - self.ratio));
+        let s2 = self.input2.next().map(|s| s.amplify(self.ratio));
+
+        match (s1, s2) {
+            (Some(s1), Some(s2)) => Some(s1.saturating_add(s2)),
+            (Some(s1), None) => Some(s1),
+            (None, Some(s2)) => Some(s2),
+            (None, None) => None,
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let s1 = self.input1.size_hint();
+        let s2 = self.input2.size_hint();
+
+        let min = cmp::max(s1.0, s2.0);
+        let max = match (s1.1, s2.1) {
+            (Some(s1), Some(s2)) => Some(cmp::max(s1, s2)),
+            _ => None,
+        };
+
+        (min, max)
+    }
+}
+
+impl<I1, I2> ExactSizeIterator for Blend<I1, I2>
+where
+    I1: Source + ExactSizeIterator,
+    I1::Item: Sample,
+    I2: Source + ExactSizeIterator,
+    I2::Item: Sample,
+{
+}
+
+impl<I1, I2> Source for Blend<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let f1 = self.input1.current_frame_len();
+        let f2 = self.input2.current_frame_len();
+
+        match (f1, f2) {
+            (Some(f1), Some(f2)) => Some(cmp::min(f1, f2)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input1.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input1.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let f1 = self.input1.total_duration();
+        let f2 = self.input2.total_duration();
+
+        match (f1, f2) {
+            (Some(f1), Some(f2)) => Some(cmp::max(f1, f2)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn a() -> SamplesBuffer<f32> {
+        SamplesBuffer::new(1, 44100, vec![1.0, 0.5, -0.5, -1.0])
+    }
+
+    fn b() -> SamplesBuffer<f32> {
+        SamplesBuffer::new(1, 44100, vec![0.2, 0.4, 0.6, 0.8])
+    }
+
+    #[test]
+    fn extreme_ratios() {
+        let output: Vec<f32> = a().interleave_with(b(), 0.0).collect();
+        assert_eq!(output, a().collect::<Vec<_>>());
+
+        let output: Vec<f32> = a().interleave_with(b(), 1.0).collect();
+        assert_eq!(output, b().collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn weighted_sum() {
+        let output: Vec<f32> = a().interleave_with(b(), 0.25).collect();
+        let expected = [0.8, 0.475, -0.225, -0.55];
+        for (sample, expected) in output.iter().zip(&expected) {
+            assert!((sample - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn invalid_ratio() {
+        a().interleave_with(b(), 1.5);
+    }
+}
@@ -0,0 +1,108 @@
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Blocks` object.
+///
+/// # Panic
+///
+/// Panics if `frames_per_block` is zero.
+pub fn blocks<I>(input: I, frames_per_block: usize, pad_last: bool) -> Blocks<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(
+        frames_per_block >= 1,
+        "a block must hold at least one frame"
+    );
+
+    let block_len = frames_per_block * input.channels().max(1) as usize;
+    Blocks {
+        input,
+        block_len,
+        pad_last,
+    }
+}
+
+/// Iterator over fixed-size blocks of interleaved samples, built with `Source::blocks`.
+///
+/// The length of the blocks is computed from the number of channels that the source has when
+/// the iterator is built.
+#[derive(Clone, Debug)]
+pub struct Blocks<I> {
+    input: I,
+    // Number of samples in a block.
+    block_len: usize,
+    // True if the last block is completed with silence.
+    pad_last: bool,
+}
+
+impl<I> Blocks<I> {
+    /// Returns the number of samples in a block.
+    #[inline]
+    pub fn block_len(&self) -> usize {
+        self.block_len
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Blocks<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = Vec<I::Item>;
+
+    #[inline]
+    fn next(&mut self) -> Option<Vec<I::Item>> {
+        let mut block = Vec::with_capacity(self.block_len);
+        block.extend(self.input.by_ref().take(self.block_len));
+        if block.is_empty() {
+            return None;
+        }
+        if self.pad_last {
+            block.resize(self.block_len, I::Item::zero_value());
+        }
+        Some(block)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.input.size_hint();
+        (
+            min.div_ceil(self.block_len),
+            max.map(|max| max.div_ceil(self.block_len)),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::source::{CountingSource, Source};
+
+    #[test]
+    fn blocks_of_frames() {
+        let ramp = || CountingSource::new(2, 1000, Duration::from_millis(11));
+        let data: Vec<f32> = ramp().collect();
+        assert_eq!(data.len(), 22);
+
+        let blocks: Vec<Vec<f32>> = ramp().blocks(4, false).collect();
+        let lens: Vec<usize> = blocks.iter().map(Vec::len).collect();
+        assert_eq!(lens, [8, 8, 6]);
+        assert_eq!(blocks.concat(), data);
+
+        let blocks = ramp().blocks(4, true);
+        assert_eq!(blocks.block_len(), 8);
+        assert_eq!(blocks.size_hint(), (3, Some(3)));
+        let blocks: Vec<Vec<f32>> = blocks.collect();
+        assert!(blocks.iter().all(|block| block.len() == 8));
+        assert_eq!(blocks.concat()[..22], data);
+        assert_eq!(blocks.concat()[22..], [0.0, 0.0]);
+    }
+}
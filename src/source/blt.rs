@@ -1,10 +1,8 @@
-use std::f32::consts::PI;
 use std::time::Duration;
 
+use crate::source::biquad::{Biquad, Coefficients};
 use crate::Source;
 
-// Implemented following http://www.musicdsp.org/files/Audio-EQ-Cookbook.txt
-
 /// Internal function that builds a `BltFilter` object.
 pub fn low_pass<I>(input: I, freq: u32) -> BltFilter<I>
 where
@@ -13,11 +11,8 @@ where
     BltFilter {
         input,
         formula: BltFormula::LowPass { freq, q: 0.5 },
-        applier: None,
-        x_n1: 0.0,
-        x_n2: 0.0,
-        y_n1: 0.0,
-        y_n2: 0.0,
+        filter: Biquad::default(),
+        outdated: true,
     }
 }
 
@@ -28,11 +23,8 @@ where
     BltFilter {
         input,
         formula: BltFormula::HighPass { freq, q: 0.5 },
-        applier: None,
-        x_n1: 0.0,
-        x_n2: 0.0,
-        y_n1: 0.0,
-        y_n2: 0.0,
+        filter: Biquad::default(),
+        outdated: true,
     }
 }
 
@@ -40,24 +32,22 @@ where
 pub struct BltFilter<I> {
     input: I,
     formula: BltFormula,
-    applier: Option<BltApplier>,
-    x_n1: f32,
-    x_n2: f32,
-    y_n1: f32,
-    y_n2: f32,
+    filter: Biquad,
+    // Whether the coefficients of the filter must be computed again before the next sample.
+    outdated: bool,
 }
 
 impl<I> BltFilter<I> {
     /// Modifies this filter so that it becomes a low-pass filter.
     pub fn to_low_pass(&mut self, freq: u32) {
         self.formula = BltFormula::LowPass { freq, q: 0.5 };
-        self.applier = None;
+        self.outdated = true;
     }
 
     /// Modifies this filter so that it becomes a high-pass filter
     pub fn to_high_pass(&mut self, freq: u32) {
         self.formula = BltFormula::HighPass { freq, q: 0.5 };
-        self.applier = None;
+        self.outdated = true;
     }
 
     /// Returns a reference to the inner source.
@@ -89,8 +79,10 @@ where
     fn next(&mut self) -> Option<f32> {
         let last_in_frame = self.input.current_frame_len() == Some(1);
 
-        if self.applier.is_none() {
-            self.applier = Some(self.formula.to_applier(self.input.sample_rate()));
+        if self.outdated {
+            let coefficients = self.formula.to_coefficients(self.input.sample_rate());
+            self.filter.set_coefficients(coefficients);
+            self.outdated = false;
         }
 
         let sample = match self.input.next() {
@@ -98,19 +90,10 @@ where
             Some(s) => s,
         };
 
-        let result = self
-            .applier
-            .as_ref()
-            .unwrap()
-            .apply(sample, self.x_n1, self.x_n2, self.y_n1, self.y_n2);
-
-        self.y_n2 = self.y_n1;
-        self.x_n2 = self.x_n1;
-        self.y_n1 = result;
-        self.x_n1 = sample;
+        let result = self.filter.process(sample);
 
         if last_in_frame {
-            self.applier = None;
+            self.outdated = true;
         }
 
         Some(result)
@@ -156,63 +139,15 @@ enum BltFormula {
 }
 
 impl BltFormula {
-    fn to_applier(&self, sampling_frequency: u32) -> BltApplier {
+    fn to_coefficients(&self, sampling_frequency: u32) -> Coefficients {
+        let sampling_frequency = sampling_frequency as f32;
         match *self {
             BltFormula::LowPass { freq, q } => {
-                let w0 = 2.0 * PI * freq as f32 / sampling_frequency as f32;
-
-                let alpha = w0.sin() / (2.0 * q);
-                let b1 = 1.0 - w0.cos();
-                let b0 = b1 / 2.0;
-                let b2 = b0;
-                let a0 = 1.0 + alpha;
-                let a1 = -2.0 * w0.cos();
-                let a2 = 1.0 - alpha;
-
-                BltApplier {
-                    b0: b0 / a0,
-                    b1: b1 / a0,
-                    b2: b2 / a0,
-                    a1: a1 / a0,
-                    a2: a2 / a0,
-                }
+                Coefficients::low_pass(freq as f32, q, sampling_frequency)
             }
             BltFormula::HighPass { freq, q } => {
-                let w0 = 2.0 * PI * freq as f32 / sampling_frequency as f32;
-                let cos_w0 = w0.cos();
-                let alpha = w0.sin() / (2.0 * q);
-
-                let b0 = (1.0 + cos_w0) / 2.0;
-                let b1 = -1.0 - cos_w0;
-                let b2 = b0;
-                let a0 = 1.0 + alpha;
-                let a1 = -2.0 * cos_w0;
-                let a2 = 1.0 - alpha;
-
-                BltApplier {
-                    b0: b0 / a0,
-                    b1: b1 / a0,
-                    b2: b2 / a0,
-                    a1: a1 / a0,
-                    a2: a2 / a0,
-                }
+                Coefficients::high_pass(freq as f32, q, sampling_frequency)
             }
         }
     }
 }
-
-#[derive(Clone, Debug)]
-struct BltApplier {
-    b0: f32,
-    b1: f32,
-    b2: f32,
-    a1: f32,
-    a2: f32,
-}
-
-impl BltApplier {
-    #[inline]
-    fn apply(&self, x_n: f32, x_n1: f32, x_n2: f32, y_n1: f32, y_n2: f32) -> f32 {
-        self.b0 * x_n + self.b1 * x_n1 + self.b2 * x_n2 - self.a1 * y_n1 - self.a2 * y_n2
-    }
-}
@@ -0,0 +1,174 @@
+use std::sync::mpsc::{Receiver, TryRecvError};
+use std::time::Duration;
+
+use crate::buffer::SamplesBuffer;
+use crate::{Sample, Source};
+
+/// A source that plays the buffers received from a channel, one after the other.
+///
+/// This lets a producer send chunks of generated or decoded sound as they become available.
+/// Reading this source never blocks: while no buffer is available, silence is played. Once every
+/// sender has been dropped and all the buffers have been played, the source either ends or plays
+/// silence forever, depending on how it was built.
+///
+/// All the buffers must have the channels and sample rate given to `new`. Buffers with another
+/// format are dropped without being played; see `rejected_buffers`.
+pub struct BufferQueueSource<S> {
+    receiver: Receiver<SamplesBuffer<S>>,
+    current: Option<SamplesBuffer<S>>,
+    channels: u16,
+    sample_rate: u32,
+    end_on_close: bool,
+    // Number of silent samples left to finish the current frame of silence.
+    silence: u16,
+    rejected_buffers: u64,
+}
+
+impl<S> BufferQueueSource<S>
+where
+    S: Sample,
+{
+    /// Builds a new `BufferQueueSource` that plays the buffers received by `receiver`.
+    ///
+    /// If `end_on_close` is true, the source ends once all the senders are dropped and the last
+    /// buffer has been played. Otherwise it plays silence after that.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `channels` or `sample_rate` is zero.
+    #[inline]
+    pub fn new(
+        receiver: Receiver<SamplesBuffer<S>>,
+        channels: u16,
+        sample_rate: u32,
+        end_on_close: bool,
+    ) -> BufferQueueSource<S> {
+        assert!(channels >= 1);
+        assert!(sample_rate >= 1);
+
+        BufferQueueSource {
+            receiver,
+            current: None,
+            channels,
+            sample_rate,
+            end_on_close,
+            silence: 0,
+            rejected_buffers: 0,
+        }
+    }
+
+    /// Returns the number of buffers that were dropped because their channels or sample rate
+    /// didn't match.
+    #[inline]
+    pub fn rejected_buffers(&self) -> u64 {
+        self.rejected_buffers
+    }
+}
+
+impl<S> Iterator for BufferQueueSource<S>
+where
+    S: Sample,
+{
+    type Item = S;
+
+    #[inline]
+    fn next(&mut self) -> Option<S> {
+        if self.silence > 0 {
+            self.silence -= 1;
+            return Some(S::zero_value());
+        }
+
+        loop {
+            if let Some(sample) = self.current.as_mut().and_then(|buffer| buffer.next()) {
+                return Some(sample);
+            }
+
+            match self.receiver.try_recv() {
+                Ok(buffer) => {
+                    if buffer.channels() == self.channels
+                        && buffer.sample_rate() == self.sample_rate
+                    {
+                        self.current = Some(buffer);
+                    } else {
+                        self.rejected_buffers += 1;
+                    }
+                }
+                Err(TryRecvError::Disconnected) if self.end_on_close => return None,
+                Err(_) => {
+                    // Play a frame of silence while waiting for the next buffer.
+                    self.current = None;
+                    self.silence = self.channels - 1;
+                    return Some(S::zero_value());
+                }
+            }
+        }
+    }
+}
+
+impl<S> Source for BufferQueueSource<S>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.silence > 0 {
+            return Some(self.silence as usize);
+        }
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::BufferQueueSource;
+
+    #[test]
+    fn plays_buffers_in_order() {
+        let (tx, rx) = mpsc::channel();
+        let source = BufferQueueSource::new(rx, 2, 44100, true);
+
+        tx.send(SamplesBuffer::new(2, 44100, vec![1i16, 2, 3, 4]))
+            .unwrap();
+        tx.send(SamplesBuffer::new(2, 44100, vec![5i16, 6]))
+            .unwrap();
+        drop(tx);
+
+        assert_eq!(source.collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6]);
+    }
+
+    #[test]
+    fn silence_while_waiting() {
+        let (tx, rx) = mpsc::channel();
+        let mut source = BufferQueueSource::new(rx, 2, 44100, false);
+
+        assert_eq!(source.next(), Some(0));
+        assert_eq!(source.next(), Some(0));
+        tx.send(SamplesBuffer::new(2, 44100, vec![1i16, 2]))
+            .unwrap();
+        // A buffer with another format is skipped.
+        tx.send(SamplesBuffer::new(1, 44100, vec![7i16])).unwrap();
+        drop(tx);
+
+        let output: Vec<i16> = source.by_ref().take(4).collect();
+        assert_eq!(output, vec![1, 2, 0, 0]);
+        assert_eq!(source.rejected_buffers(), 1);
+    }
+}
@@ -0,0 +1,327 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::source::UniformSourceIterator;
+use crate::{Sample, Source};
+
+/// Maximum number of samples read from the inner source at once.
+const MAX_CHUNK_LEN: usize = 1024;
+
+/// Internal function that builds a `BufferingSource` object.
+pub fn buffering<I>(input: I, buffer: Duration) -> BufferingSource<I::Item>
+where
+    I: Source + Send + 'static,
+    I::Item: Sample + Send,
+{
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let frames = (buffer.as_secs_f32() * sample_rate as f32) as usize;
+    let capacity = frames.max(1) * channels as usize;
+    // Small chunks let slow sources fill the buffer regularly.
+    let chunk_len =
+        ((capacity / 8).clamp(1, MAX_CHUNK_LEN) / channels as usize).max(1) * channels as usize;
+
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            samples: VecDeque::with_capacity(capacity + chunk_len),
+            buffering: true,
+            finished: false,
+            alive: true,
+        }),
+        condvar: Condvar::new(),
+        capacity,
+    });
+
+    // The format can't change, so that the samples can be read without knowing where frames end.
+    let input = UniformSourceIterator::new(input, channels, sample_rate);
+    let thread_shared = shared.clone();
+    thread::spawn(move || fill(input, thread_shared, chunk_len));
+
+    BufferingSource {
+        handle: BufferingHandle { shared },
+        channels,
+        sample_rate,
+        silence: 0,
+    }
+}
+
+struct Shared<S> {
+    state: Mutex<State<S>>,
+    // Notified when samples are read, and when the `BufferingSource` is dropped.
+    condvar: Condvar,
+    // Number of samples of the prefetch buffer.
+    capacity: usize,
+}
+
+struct State<S> {
+    samples: VecDeque<S>,
+    // True while the buffer is being filled before playing.
+    buffering: bool,
+    // True once the inner source has ended.
+    finished: bool,
+    // False once the `BufferingSource` has been dropped.
+    alive: bool,
+}
+
+/// Reads the inner source on the prefetching thread.
+fn fill<I>(mut input: I, shared: Arc<Shared<I::Item>>, chunk_len: usize)
+where
+    I: Iterator,
+{
+    let mut chunk = Vec::with_capacity(chunk_len);
+    loop {
+        chunk.extend(input.by_ref().take(chunk_len));
+        let ended = chunk.len() < chunk_len;
+
+        let mut state = shared.state.lock().unwrap();
+        while state.alive && state.samples.len() >= shared.capacity {
+            state = shared.condvar.wait(state).unwrap();
+        }
+        if !state.alive {
+            return;
+        }
+        state.samples.extend(chunk.drain(..));
+        state.finished = ended;
+        if ended || state.samples.len() >= shared.capacity {
+            state.buffering = false;
+        }
+        drop(state);
+
+        if ended {
+            return;
+        }
+    }
+}
+
+/// What a `BufferingSource` is doing, for example to show a "buffering..." indicator.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BufferingState {
+    /// The buffer is being filled and silence is played until it is full.
+    Buffering {
+        /// How full the buffer is, from `0` to `100`.
+        percent: u8,
+    },
+    /// The samples of the inner source are played.
+    Playing,
+    /// The inner source has ended and all its samples have been played.
+    Ended,
+}
+
+/// A handle that reads the state of a `BufferingSource` from another thread.
+pub struct BufferingHandle<S> {
+    shared: Arc<Shared<S>>,
+}
+
+impl<S> Clone for BufferingHandle<S> {
+    #[inline]
+    fn clone(&self) -> BufferingHandle<S> {
+        BufferingHandle {
+            shared: self.shared.clone(),
+        }
+    }
+}
+
+impl<S> BufferingHandle<S> {
+    /// Returns what the source is doing.
+    pub fn state(&self) -> BufferingState {
+        let state = self.shared.state.lock().unwrap();
+        if state.finished && state.samples.is_empty() {
+            BufferingState::Ended
+        } else if state.buffering {
+            let percent = state.samples.len() * 100 / self.shared.capacity;
+            BufferingState::Buffering {
+                percent: percent.min(100) as u8,
+            }
+        } else {
+            BufferingState::Playing
+        }
+    }
+}
+
+/// A source that is read on a separate thread into a prefetch buffer, as is needed for sounds
+/// streamed from the network.
+///
+/// Silence is played until the buffer is full. If the buffer runs empty before the end of the
+/// inner source, the source goes back to buffering instead of playing the samples as they
+/// trickle in. Its state can be read with `state`, or from another thread with a handle.
+///
+/// The samples are converted to the channels and sample rate that the inner source has when the
+/// `BufferingSource` is built.
+pub struct BufferingSource<S> {
+    handle: BufferingHandle<S>,
+    channels: u16,
+    sample_rate: u32,
+    // Number of silent samples left to return for the current frame.
+    silence: u16,
+}
+
+impl<S> BufferingSource<S> {
+    /// Returns what the source is doing.
+    #[inline]
+    pub fn state(&self) -> BufferingState {
+        self.handle.state()
+    }
+
+    /// Returns a handle that reads the state of this source, which keeps working once the
+    /// source has been given to a sink.
+    #[inline]
+    pub fn handle(&self) -> BufferingHandle<S> {
+        self.handle.clone()
+    }
+}
+
+impl<S> Iterator for BufferingSource<S>
+where
+    S: Sample,
+{
+    type Item = S;
+
+    #[inline]
+    fn next(&mut self) -> Option<S> {
+        if self.silence > 0 {
+            self.silence -= 1;
+            return Some(S::zero_value());
+        }
+
+        let shared = &self.handle.shared;
+        let mut state = shared.state.lock().unwrap();
+        if !state.buffering {
+            if let Some(sample) = state.samples.pop_front() {
+                drop(state);
+                shared.condvar.notify_all();
+                return Some(sample);
+            }
+            if state.finished {
+                return None;
+            }
+            state.buffering = true;
+        }
+
+        // Play a frame of silence instead of waiting for the buffer to fill.
+        self.silence = self.channels - 1;
+        Some(S::zero_value())
+    }
+}
+
+impl<S> Source for BufferingSource<S>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl<S> Drop for BufferingSource<S> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.handle.shared.state.lock() {
+            state.alive = false;
+        }
+        self.handle.shared.condvar.notify_all();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc::{self, Receiver};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::{BufferingHandle, BufferingState};
+    use crate::source::Source;
+
+    /// A source whose samples are sent by the test, like a network stream.
+    struct Stream(Receiver<i16>);
+
+    impl Iterator for Stream {
+        type Item = i16;
+
+        fn next(&mut self) -> Option<i16> {
+            self.0.recv().ok()
+        }
+    }
+
+    impl Source for Stream {
+        fn current_frame_len(&self) -> Option<usize> {
+            None
+        }
+
+        fn channels(&self) -> u16 {
+            1
+        }
+
+        fn sample_rate(&self) -> u32 {
+            1000
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+    }
+
+    fn wait_for(handle: &BufferingHandle<i16>, expected: BufferingState) {
+        for _ in 0..1000 {
+            if handle.state() == expected {
+                return;
+            }
+            thread::sleep(Duration::from_millis(1));
+        }
+        panic!("{:?} instead of {:?}", handle.state(), expected);
+    }
+
+    #[test]
+    fn buffers_when_starved() {
+        let (tx, rx) = mpsc::channel();
+        // 80 samples are buffered, read in chunks of 10.
+        let mut source = Stream(rx).buffering(Duration::from_millis(80));
+        let handle = source.handle();
+        assert_eq!(source.state(), BufferingState::Buffering { percent: 0 });
+        assert_eq!(source.next(), Some(0));
+
+        for sample in 1..=40 {
+            tx.send(sample).unwrap();
+        }
+        wait_for(&handle, BufferingState::Buffering { percent: 50 });
+        assert_eq!(source.next(), Some(0));
+        for sample in 41..=80 {
+            tx.send(sample).unwrap();
+        }
+        wait_for(&handle, BufferingState::Playing);
+        let output: Vec<i16> = source.by_ref().take(80).collect();
+        assert_eq!(output, (1..=80).collect::<Vec<_>>());
+
+        // The buffer ran empty, so it is filled again before playing.
+        assert_eq!(source.next(), Some(0));
+        assert_eq!(source.state(), BufferingState::Buffering { percent: 0 });
+        for sample in 81..=160 {
+            tx.send(sample).unwrap();
+        }
+        wait_for(&handle, BufferingState::Playing);
+        assert_eq!(source.next(), Some(81));
+
+        // The end of the stream is played without waiting for the buffer to be full, after some
+        // silence if the prefetching thread hasn't seen the end yet.
+        drop(tx);
+        let output: Vec<i16> = source.by_ref().filter(|&sample| sample != 0).collect();
+        assert_eq!(output, (82..=160).collect::<Vec<_>>());
+        assert_eq!(source.state(), BufferingState::Ended);
+    }
+}
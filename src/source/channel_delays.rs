@@ -0,0 +1,224 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `ChannelDelays` object.
+///
+/// # Panic
+///
+/// Panics if the number of delays isn't the number of channels of `input`.
+pub fn channel_delays<I>(input: I, delays: &[Duration]) -> ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert_eq!(
+        delays.len(),
+        input.channels() as usize,
+        "there must be one delay per channel"
+    );
+
+    let sample_rate = input.sample_rate() as f64;
+    let lines: Vec<VecDeque<I::Item>> = delays
+        .iter()
+        .map(|delay| {
+            let frames = (delay.as_secs_f64() * sample_rate).round() as usize;
+            vec![I::Item::zero_value(); frames].into()
+        })
+        .collect();
+    let tail = lines.iter().map(|line| line.len()).max().unwrap_or(0);
+
+    ChannelDelays {
+        input,
+        lines,
+        tail,
+        max_delay: tail,
+        current_channel: 0,
+        ended: false,
+    }
+}
+
+/// Filter that delays each channel of a sound by its own duration.
+///
+/// The delays are rounded to whole frames. The ends of the delayed channels are played after the
+/// end of the inner source, so that the sound is longer by the longest delay.
+pub struct ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    // The last samples of each channel, oldest first.
+    lines: Vec<VecDeque<I::Item>>,
+    // Number of frames left to play once the inner source has ended.
+    tail: usize,
+    // The longest delay, in frames.
+    max_delay: usize,
+    // Channel of the next sample returned by the iterator.
+    current_channel: usize,
+    ended: bool,
+}
+
+impl<I> ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let channel = self.current_channel;
+        if channel == 0 && self.ended {
+            if self.tail == 0 {
+                return None;
+            }
+            self.tail -= 1;
+        }
+
+        let sample = if self.ended {
+            I::Item::zero_value()
+        } else {
+            match self.input.next() {
+                Some(sample) => sample,
+                None if channel == 0 => {
+                    self.ended = true;
+                    return self.next();
+                }
+                None => return None,
+            }
+        };
+
+        let line = &mut self.lines[channel];
+        let sample = if line.is_empty() {
+            sample
+        } else {
+            line.push_back(sample);
+            line.pop_front().unwrap()
+        };
+
+        self.current_channel = (self.current_channel + 1) % self.lines.len();
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let extra = self.tail * self.lines.len();
+        if self.ended {
+            return (extra, Some(extra));
+        }
+        let (min, max) = self.input.size_hint();
+        (
+            min.saturating_add(extra),
+            max.and_then(|max| max.checked_add(extra)),
+        )
+    }
+}
+
+impl<I> Source for ChannelDelays<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let tail = self.tail * self.lines.len();
+        if self.ended {
+            return Some(tail);
+        }
+        match self.input.current_frame_len() {
+            // The tail is played after the inner source.
+            Some(0) => Some(tail),
+            len => len,
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.lines.len() as u16
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let delay = Duration::from_secs_f64(self.max_delay as f64 / self.sample_rate() as f64);
+        self.input.total_duration().map(|duration| duration + delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn delays_each_channel() {
+        let data: Vec<i16> = (1..=10).flat_map(|n| [n, 10 * n, 100 * n]).collect();
+        let delays = [
+            Duration::ZERO,
+            Duration::from_millis(2),
+            Duration::from_millis(5),
+        ];
+        let source = SamplesBuffer::new(3, 1000, data).channel_delays(&delays);
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(15)));
+
+        let output: Vec<i16> = source.collect();
+        assert_eq!(output.len(), 3 * 15);
+        let channel = |c: usize| {
+            output
+                .iter()
+                .skip(c)
+                .step_by(3)
+                .copied()
+                .collect::<Vec<_>>()
+        };
+
+        let expected = |delay: usize, scale: i16| {
+            let mut samples = vec![0; delay];
+            samples.extend((1..=10).map(|n| n * scale));
+            samples.resize(15, 0);
+            samples
+        };
+        assert_eq!(channel(0), expected(0, 1));
+        assert_eq!(channel(1), expected(2, 10));
+        assert_eq!(channel(2), expected(5, 100));
+    }
+
+    #[test]
+    #[should_panic]
+    fn wrong_number_of_delays() {
+        let source = SamplesBuffer::new(2, 1000, vec![0i16; 4]);
+        let _ = source.channel_delays(&[Duration::ZERO]);
+    }
+}
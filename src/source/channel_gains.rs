@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `ChannelGains` object.
+///
+/// # Panic
+///
+/// Panics if the number of gains is different from the number of channels of the source.
+pub fn channel_gains<I>(input: I, gains: &[f32]) -> ChannelGains<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert_eq!(
+        gains.len(),
+        input.channels() as usize,
+        "the number of gains must match the number of channels"
+    );
+
+    ChannelGains {
+        input,
+        gains: gains.to_vec(),
+        current_channel: 0,
+    }
+}
+
+/// Filter that multiplies each channel by its own gain.
+#[derive(Clone, Debug)]
+pub struct ChannelGains<I> {
+    input: I,
+    // Channel number is used as index for the gain value.
+    gains: Vec<f32>,
+    // Channel of the next sample returned by the iterator.
+    current_channel: usize,
+}
+
+impl<I> ChannelGains<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Sets the gain for a given channel number. Will panic if channel number
+    /// was invalid.
+    #[inline]
+    pub fn set_gain(&mut self, channel: usize, gain: f32) {
+        self.gains[channel] = gain;
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for ChannelGains<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let gain = self.gains[self.current_channel];
+        self.current_channel = (self.current_channel + 1) % self.gains.len();
+        self.input.next().map(|value| value.amplify(gain))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for ChannelGains<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for ChannelGains<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn silences_right_channel() {
+        let input = SamplesBuffer::new(2, 44100, vec![10i16, 20, -10, -20, 30, 40]);
+        let output: Vec<i16> = input.channel_gains(&[1.0, 0.0]).collect();
+        assert_eq!(output, vec![10, 0, -10, 0, 30, 0]);
+    }
+
+    #[test]
+    fn trims_in_decibels() {
+        let input = SamplesBuffer::new(2, 44100, vec![0.5f32, 0.5, -0.8, -0.8]);
+        let output: Vec<f32> = input.channel_trim_db(&[0.0, -6.0]).collect();
+        assert_eq!(output[0], 0.5);
+        assert_eq!(output[2], -0.8);
+        // -6 dB is about half the amplitude.
+        assert!((output[1] - 0.25).abs() < 0.001);
+        assert!((output[3] + 0.4).abs() < 0.001);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_if_wrong_trim_count() {
+        let input = SamplesBuffer::new(6, 44100, vec![10i16; 6]);
+        input.channel_trim_db(&[0.0, -3.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_if_wrong_gain_count() {
+        let input = SamplesBuffer::new(2, 44100, vec![10i16, 20]);
+        input.channel_gains(&[1.0]);
+    }
+}
@@ -0,0 +1,182 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `ChannelPermute` object.
+///
+/// # Panic
+///
+/// - Panics if the length of `permutation` is different from the number of channels.
+/// - Panics if `permutation` contains a channel index that is out of range.
+pub fn channel_permute<I>(input: I, permutation: &[usize]) -> ChannelPermute<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let channels = input.channels() as usize;
+    assert_eq!(
+        permutation.len(),
+        channels,
+        "the permutation length must match the number of channels"
+    );
+    assert!(
+        permutation.iter().all(|&index| index < channels),
+        "channel index out of range in permutation"
+    );
+
+    ChannelPermute {
+        input,
+        permutation: permutation.to_vec(),
+        frame: Vec::with_capacity(channels),
+        position: channels,
+    }
+}
+
+/// Filter that reorders the channels of each frame according to an index map.
+#[derive(Clone, Debug)]
+pub struct ChannelPermute<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    // Output channel `n` is taken from input channel `permutation[n]`.
+    permutation: Vec<usize>,
+    // The current frame of the input.
+    frame: Vec<I::Item>,
+    // Position of the next output sample inside `frame`.
+    position: usize,
+}
+
+impl<I> ChannelPermute<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for ChannelPermute<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.position >= self.permutation.len() {
+            self.frame.clear();
+            self.frame
+                .extend(self.input.by_ref().take(self.permutation.len()));
+            if self.frame.len() < self.permutation.len() {
+                // Incomplete frames can't be permuted, so they are dropped.
+                return None;
+            }
+            self.position = 0;
+        }
+
+        let sample = self.frame[self.permutation[self.position]];
+        self.position += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.permutation.len() - self.position.min(self.permutation.len());
+        let (min, max) = self.input.size_hint();
+        (min + buffered, max.map(|max| max + buffered))
+    }
+}
+
+impl<I> Source for ChannelPermute<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        // The rest of `frame` has already been read from the inner source.
+        let buffered = self.permutation.len() - self.position.min(self.permutation.len());
+        self.input.current_frame_len().map(|len| len + buffered)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.permutation.len() as u16
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn swap_stereo() {
+        let input = SamplesBuffer::new(2, 44100, vec![1i16, 2, 3, 4, 5, 6]);
+        let output: Vec<i16> = input.permute_channels(&[1, 0]).collect();
+        assert_eq!(output, vec![2, 1, 4, 3, 6, 5]);
+    }
+
+    #[test]
+    fn duplicate_channel() {
+        let input = SamplesBuffer::new(3, 44100, vec![1i16, 2, 3, 4, 5, 6]);
+        let output: Vec<i16> = input.permute_channels(&[2, 0, 0]).collect();
+        assert_eq!(output, vec![3, 1, 1, 6, 4, 4]);
+    }
+
+    #[test]
+    fn frame_len_includes_buffered_samples() {
+        let input =
+            SamplesBuffer::new(2, 1000, vec![0i16; 100]).take_duration(Duration::from_millis(10));
+        let mut source = input.permute_channels(&[1, 0]);
+        for _ in 0..10 {
+            let len = source.current_frame_len().unwrap();
+            source.next();
+            assert_eq!(source.current_frame_len(), Some(len - 1));
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_if_wrong_length() {
+        let input = SamplesBuffer::new(2, 44100, vec![1i16, 2]);
+        input.permute_channels(&[0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn panic_if_index_out_of_range() {
+        let input = SamplesBuffer::new(2, 44100, vec![1i16, 2]);
+        input.permute_channels(&[0, 2]);
+    }
+}
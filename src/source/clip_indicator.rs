@@ -0,0 +1,235 @@
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `ClipIndicator` object.
+///
+/// # Panic
+///
+/// Panics if `decay_db_per_second` is negative.
+pub fn clip_indicator<I>(input: I, decay_db_per_second: f32) -> ClipIndicator<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(decay_db_per_second >= 0.0, "the decay must not be negative");
+
+    let decay_per_frame = decay_db_per_second / input.sample_rate().max(1) as f32;
+    ClipIndicator {
+        input,
+        handle: ClipIndicatorHandle {
+            shared: Arc::new(Shared {
+                peak: AtomicU32::new(0f32.to_bits()),
+                clipped: AtomicBool::new(false),
+            }),
+        },
+        decay: 10f32.powf(-decay_per_frame / 20.0),
+        peak: 0.0,
+        current_channel: 0,
+    }
+}
+
+#[derive(Debug)]
+struct Shared {
+    peak: AtomicU32,
+    clipped: AtomicBool,
+}
+
+/// Gives access to the peak held by a `ClipIndicator` from any thread.
+#[derive(Clone, Debug)]
+pub struct ClipIndicatorHandle {
+    shared: Arc<Shared>,
+}
+
+impl ClipIndicatorHandle {
+    /// Returns the held peak, as the absolute value of a sample between `0.0` and `1.0`.
+    #[inline]
+    pub fn peak(&self) -> f32 {
+        f32::from_bits(self.shared.peak.load(Ordering::Relaxed))
+    }
+
+    /// Returns the held peak in decibels relative to full scale. Silence gives negative infinity.
+    #[inline]
+    pub fn peak_db(&self) -> f32 {
+        20.0 * self.peak().log10()
+    }
+
+    /// Returns true if a sample reached full scale since the indicator was built or reset.
+    #[inline]
+    pub fn clipped(&self) -> bool {
+        self.shared.clipped.load(Ordering::Relaxed)
+    }
+
+    /// Turns the clip indicator off.
+    #[inline]
+    pub fn reset(&self) {
+        self.shared.clipped.store(false, Ordering::Relaxed);
+    }
+}
+
+/// A source that holds the peak of the sound, like the needle of a hardware peak meter.
+///
+/// The held peak jumps to every new peak instantly, then falls by a fixed number of decibels per
+/// second. A clip indicator lights up when a sample reaches full scale and stays lit until it is
+/// reset. The samples themselves are passed through unchanged. The peak is read with the handle
+/// returned by `handle`.
+#[derive(Debug)]
+pub struct ClipIndicator<I> {
+    input: I,
+    handle: ClipIndicatorHandle,
+    // Factor applied to the held peak after each frame.
+    decay: f32,
+    peak: f32,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+}
+
+impl<I> ClipIndicator<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a handle that gives access to the held peak.
+    #[inline]
+    pub fn handle(&self) -> ClipIndicatorHandle {
+        self.handle.clone()
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for ClipIndicator<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+
+        if self.current_channel == 0 {
+            self.peak *= self.decay;
+        }
+        self.current_channel += 1;
+        if self.current_channel >= self.input.channels() {
+            self.current_channel = 0;
+        }
+
+        let value = sample.to_f32().abs();
+        if value >= 1.0 {
+            self.handle.shared.clipped.store(true, Ordering::Relaxed);
+        }
+        self.peak = self.peak.max(value.min(1.0));
+        self.handle
+            .shared
+            .peak
+            .store(self.peak.to_bits(), Ordering::Relaxed);
+
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for ClipIndicator<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for ClipIndicator<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn peak_decays_at_rate() {
+        // A transient then two seconds of quieter sound, on both channels.
+        let mut data = vec![0.1f32; 2 * 2000];
+        data[2] = -0.8;
+        data[502 * 2] = 0.5;
+        let mut meter = SamplesBuffer::new(2, 1000, data).clip_indicator(20.0);
+        let handle = meter.handle();
+
+        meter.by_ref().take(4).for_each(drop);
+        assert_eq!(handle.peak(), 0.8);
+
+        // Half a second later, the peak has fallen by 10 dB.
+        meter.by_ref().take(500 * 2).for_each(drop);
+        assert!((handle.peak_db() - (20.0 * 0.8f32.log10() - 10.0)).abs() < 0.01);
+
+        // A new peak above the held value is taken instantly.
+        meter.by_ref().take(2).for_each(drop);
+        assert_eq!(handle.peak(), 0.5);
+
+        // The held value never falls below the level of the sound.
+        meter.by_ref().for_each(drop);
+        assert!((handle.peak() - 0.1).abs() < 1e-6);
+        assert!(!handle.clipped());
+    }
+
+    #[test]
+    fn clip_is_held_until_reset() {
+        let mut meter =
+            SamplesBuffer::new(1, 1000, vec![0.2f32, 1.0, 0.2, 0.2]).clip_indicator(6.0);
+        let handle = meter.handle();
+        meter.by_ref().take(2).for_each(drop);
+        assert!(handle.clipped());
+        meter.next();
+        assert!(handle.clipped());
+        handle.reset();
+        assert!(!handle.clipped());
+    }
+}
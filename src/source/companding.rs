@@ -0,0 +1,213 @@
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Parameter of the μ-law algorithm.
+const MU: f32 = 255.0;
+/// Parameter of the A-law algorithm.
+const A: f32 = 87.6;
+/// Number of quantization levels on each side of zero, as in 8-bit companded audio.
+const LEVELS: f32 = 127.0;
+
+/// Internal function that builds a `Companding` object.
+pub fn companding<I>(input: I, law: Law, direction: Direction) -> Companding<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    Companding {
+        input,
+        law,
+        direction,
+    }
+}
+
+/// Which companding algorithm is used.
+#[derive(Clone, Copy, Debug)]
+pub enum Law {
+    /// The μ-law algorithm, used in North America and Japan.
+    MuLaw,
+    /// The A-law algorithm, used in Europe.
+    ALaw,
+}
+
+/// Whether samples are compressed or expanded.
+#[derive(Clone, Copy, Debug)]
+pub enum Direction {
+    /// Converts linear samples to companded samples.
+    Encode,
+    /// Converts companded samples back to linear samples.
+    Decode,
+}
+
+/// Filter that converts between linear and companded samples.
+///
+/// Companded samples are quantized to 8 bits like in telephony, but are still returned as
+/// samples between -1.0 and 1.0 of the same type as the inner source.
+#[derive(Clone, Debug)]
+pub struct Companding<I> {
+    input: I,
+    law: Law,
+    direction: Direction,
+}
+
+impl<I> Companding<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+#[inline]
+fn encode(law: Law, value: f32) -> f32 {
+    let x = value.abs().min(1.0);
+    let y = match law {
+        Law::MuLaw => (1.0 + MU * x).ln() / (1.0 + MU).ln(),
+        Law::ALaw => {
+            if x < 1.0 / A {
+                A * x / (1.0 + A.ln())
+            } else {
+                (1.0 + (A * x).ln()) / (1.0 + A.ln())
+            }
+        }
+    };
+    ((y * LEVELS).round() / LEVELS).copysign(value)
+}
+
+#[inline]
+fn decode(law: Law, value: f32) -> f32 {
+    let y = value.abs().min(1.0);
+    let x = match law {
+        Law::MuLaw => ((1.0 + MU).powf(y) - 1.0) / MU,
+        Law::ALaw => {
+            if y < 1.0 / (1.0 + A.ln()) {
+                y * (1.0 + A.ln()) / A
+            } else {
+                (y * (1.0 + A.ln()) - 1.0).exp() / A
+            }
+        }
+    };
+    x.copysign(value)
+}
+
+impl<I> Iterator for Companding<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let value = self.input.next()?.to_f32();
+        let value = match self.direction {
+            Direction::Encode => encode(self.law, value),
+            Direction::Decode => decode(self.law, value),
+        };
+        Some(CpalSample::from(&value))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Companding<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Companding<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn input() -> Vec<f32> {
+        (-1000..=1000).map(|i| i as f32 / 1000.0).collect()
+    }
+
+    #[test]
+    fn mu_law_round_trip() {
+        let data = input();
+        let output: Vec<f32> = SamplesBuffer::new(1, 8000, data.clone())
+            .mu_law_encode()
+            .mu_law_decode()
+            .collect();
+
+        for (x, y) in data.iter().zip(&output) {
+            assert!((x - y).abs() <= 0.025 * x.abs() + 1e-4, "{} {}", x, y);
+        }
+    }
+
+    #[test]
+    fn a_law_round_trip() {
+        let data = input();
+        let output: Vec<f32> = SamplesBuffer::new(1, 8000, data.clone())
+            .a_law_encode()
+            .a_law_decode()
+            .collect();
+
+        for (x, y) in data.iter().zip(&output) {
+            assert!((x - y).abs() <= 0.025 * x.abs() + 3e-4, "{} {}", x, y);
+        }
+    }
+
+    #[test]
+    fn encode_boosts_quiet_sounds() {
+        let output: Vec<f32> = SamplesBuffer::new(1, 8000, vec![0.01f32, -0.01, 0.0, 1.0])
+            .mu_law_encode()
+            .collect();
+        assert!(output[0] > 0.2);
+        assert_eq!(output[1], -output[0]);
+        assert_eq!(output[2], 0.0);
+        assert_eq!(output[3], 1.0);
+    }
+}
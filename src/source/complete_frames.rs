@@ -0,0 +1,279 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `CompleteFrames` object.
+pub fn complete_frames<I>(input: I) -> CompleteFrames<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let span_left = input.current_frame_len();
+    let frame_channels = input.channels();
+    CompleteFrames {
+        input,
+        span_left,
+        frame_channels,
+        current_channel: 0,
+        padding: 0,
+        padded_frames: 0,
+        ended: false,
+    }
+}
+
+/// Source that makes sure that every frame of its inner source has a sample for each channel.
+///
+/// A malformed source can end, or change its format, in the middle of a frame. Every sample
+/// that follows is then played on the wrong channel. This source completes such frames with
+/// silence, and counts them.
+#[derive(Clone, Debug)]
+pub struct CompleteFrames<I> {
+    input: I,
+    // Number of samples left in the current span of the inner source, if known.
+    span_left: Option<usize>,
+    // Number of channels of the frame being returned.
+    frame_channels: u16,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+    // Number of samples of silence left to complete the current frame.
+    padding: u16,
+    padded_frames: u64,
+    // True once the inner source has ended.
+    ended: bool,
+}
+
+impl<I> CompleteFrames<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns the number of frames that had to be completed with silence so far.
+    #[inline]
+    pub fn padded_frames(&self) -> u64 {
+        self.padded_frames
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    /// Completes the current frame with silence.
+    fn start_padding(&mut self) {
+        self.padding = self.frame_channels - self.current_channel;
+        self.padded_frames += 1;
+    }
+
+    /// Moves to the next channel.
+    fn advance(&mut self) {
+        self.current_channel += 1;
+        if self.current_channel >= self.frame_channels {
+            self.current_channel = 0;
+        }
+    }
+}
+
+impl<I> Iterator for CompleteFrames<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.padding == 0 && !self.ended && self.current_channel != 0 {
+            // The current frame ends early if the span does or if the format changes.
+            if self.span_left == Some(0) || self.input.channels() != self.frame_channels {
+                self.start_padding();
+            }
+        }
+
+        if self.padding > 0 {
+            self.padding -= 1;
+            self.advance();
+            return Some(I::Item::zero_value());
+        }
+        if self.ended {
+            return None;
+        }
+
+        if self.current_channel == 0 {
+            if self.span_left == Some(0) {
+                self.span_left = self.input.current_frame_len();
+            }
+            self.frame_channels = self.input.channels().max(1);
+        }
+
+        match self.input.next() {
+            Some(sample) => {
+                self.span_left = self.span_left.map(|left| left.saturating_sub(1));
+                self.advance();
+                Some(sample)
+            }
+            None => {
+                self.ended = true;
+                if self.current_channel == 0 {
+                    return None;
+                }
+                self.start_padding();
+                self.next()
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let padding = self.padding as usize;
+        if self.ended {
+            return (padding, Some(padding));
+        }
+        let (min, max) = self.input.size_hint();
+        let max_padding = padding + self.frame_channels as usize - 1;
+        (
+            min.saturating_add(padding),
+            max.and_then(|max| max.checked_add(max_padding)),
+        )
+    }
+}
+
+impl<I> Source for CompleteFrames<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.padding > 0 {
+            return Some(self.padding as usize);
+        }
+        if self.ended {
+            return Some(0);
+        }
+        let channels = self.frame_channels as usize;
+        let current = self.current_channel as usize;
+        match self.span_left {
+            Some(0) if current != 0 => Some(channels - current),
+            Some(0) => {
+                let channels = self.input.channels().max(1) as usize;
+                self.input
+                    .current_frame_len()
+                    .map(|len| len.div_ceil(channels) * channels)
+            }
+            Some(len) => Some(len + (channels - (current + len) % channels) % channels),
+            None => None,
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        if self.current_channel != 0 || self.padding > 0 {
+            self.frame_channels
+        } else {
+            self.input.channels()
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn pads_truncated_last_frame() {
+        let input = SamplesBuffer::new(3, 1000, vec![1i16, 2, 3, 4, 5, 6, 7]);
+        let mut source = input.complete_frames();
+        let output: Vec<i16> = source.by_ref().collect();
+        assert_eq!(output, [1, 2, 3, 4, 5, 6, 7, 0, 0]);
+        assert_eq!(source.padded_frames(), 1);
+
+        let mut source = SamplesBuffer::new(2, 1000, vec![1i16, 2, 3, 4]).complete_frames();
+        assert_eq!(source.by_ref().count(), 4);
+        assert_eq!(source.padded_frames(), 0);
+    }
+
+    /// A stereo span of three samples followed by a mono span of two samples.
+    struct BrokenSpans {
+        position: usize,
+    }
+
+    impl Iterator for BrokenSpans {
+        type Item = i16;
+
+        fn next(&mut self) -> Option<i16> {
+            self.position += 1;
+            if self.position <= 5 {
+                Some(self.position as i16)
+            } else {
+                None
+            }
+        }
+    }
+
+    impl Source for BrokenSpans {
+        fn current_frame_len(&self) -> Option<usize> {
+            Some(if self.position < 3 {
+                3 - self.position
+            } else {
+                5 - self.position.min(5)
+            })
+        }
+
+        fn channels(&self) -> u16 {
+            if self.position < 3 {
+                2
+            } else {
+                1
+            }
+        }
+
+        fn sample_rate(&self) -> u32 {
+            1000
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            None
+        }
+    }
+
+    #[test]
+    fn pads_truncated_span() {
+        let mut source = BrokenSpans { position: 0 }.complete_frames();
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.current_frame_len(), Some(4));
+
+        let output: Vec<(i16, u16)> = std::iter::from_fn(|| {
+            let channels = source.channels();
+            source.next().map(|sample| (sample, channels))
+        })
+        .collect();
+        assert_eq!(output, [(1, 2), (2, 2), (3, 2), (0, 2), (4, 1), (5, 1)]);
+        assert_eq!(source.padded_frames(), 1);
+    }
+}
@@ -0,0 +1,236 @@
+use std::error::Error;
+use std::fmt;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// How long it takes for the measured correlation to follow the sound.
+const INTEGRATION_TIME: Duration = Duration::from_millis(300);
+
+/// Internal function that builds a `CorrelationMeter` object.
+pub fn correlation_meter<I>(input: I) -> Result<CorrelationMeter<I>, NotStereoError>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let channels = input.channels();
+    if channels != 2 {
+        return Err(NotStereoError { channels });
+    }
+
+    let samples = INTEGRATION_TIME.as_secs_f32() * input.sample_rate() as f32;
+    Ok(CorrelationMeter {
+        input,
+        handle: CorrelationHandle {
+            correlation: Arc::new(AtomicU32::new(0f32.to_bits())),
+        },
+        coeff: (-1.0 / samples.max(1.0)).exp(),
+        left: None,
+        product: 0.0,
+        left_energy: 0.0,
+        right_energy: 0.0,
+    })
+}
+
+/// Error returned when measuring the correlation of a source that doesn't have two channels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct NotStereoError {
+    /// The number of channels of the source.
+    pub channels: u16,
+}
+
+impl fmt::Display for NotStereoError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "the source has {} channels instead of 2", self.channels)
+    }
+}
+
+impl Error for NotStereoError {}
+
+/// Gives access to the correlation measured by a `CorrelationMeter` from any thread.
+#[derive(Clone, Debug)]
+pub struct CorrelationHandle {
+    correlation: Arc<AtomicU32>,
+}
+
+impl CorrelationHandle {
+    /// Returns the correlation between the left and the right channel over the last few hundred
+    /// milliseconds.
+    ///
+    /// `1.0` means that both channels play the same sound, `0.0` that they are unrelated and
+    /// `-1.0` that one is the inverse of the other, which cancels out when mixed down to mono.
+    /// Silence gives `0.0`.
+    #[inline]
+    pub fn correlation(&self) -> f32 {
+        f32::from_bits(self.correlation.load(Ordering::Relaxed))
+    }
+}
+
+/// A source that measures the phase correlation between the two channels of a stereo sound.
+///
+/// The samples themselves are passed through unchanged. The correlation is read with the handle
+/// returned by `handle`.
+pub struct CorrelationMeter<I> {
+    input: I,
+    handle: CorrelationHandle,
+    coeff: f32,
+    // The left channel of the current frame, once it has been returned.
+    left: Option<f32>,
+    // Smoothed product of both channels and energy of each channel.
+    product: f32,
+    left_energy: f32,
+    right_energy: f32,
+}
+
+impl<I> CorrelationMeter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a handle that gives access to the measured correlation.
+    #[inline]
+    pub fn handle(&self) -> CorrelationHandle {
+        self.handle.clone()
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for CorrelationMeter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+
+        match self.left.take() {
+            None => self.left = Some(sample.to_f32()),
+            Some(left) => {
+                let right = sample.to_f32();
+                let coeff = self.coeff;
+                let smooth = |average: &mut f32, value: f32| {
+                    *average = value + (*average - value) * coeff;
+                };
+                smooth(&mut self.product, left * right);
+                smooth(&mut self.left_energy, left * left);
+                smooth(&mut self.right_energy, right * right);
+
+                let energy = (self.left_energy * self.right_energy).sqrt();
+                let correlation = if energy > f32::MIN_POSITIVE {
+                    (self.product / energy).clamp(-1.0, 1.0)
+                } else {
+                    0.0
+                };
+                self.handle
+                    .correlation
+                    .store(correlation.to_bits(), Ordering::Relaxed);
+            }
+        }
+
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for CorrelationMeter<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for CorrelationMeter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{SineWave, Source};
+
+    /// Measures the correlation of a second of stereo sound built from `frame`.
+    fn measure(frame: impl FnMut(usize) -> [f32; 2]) -> f32 {
+        let data: Vec<f32> = (0..48000).flat_map(frame).collect();
+        let mut meter = SamplesBuffer::new(2, 48000, data)
+            .correlation_meter()
+            .unwrap();
+        let handle = meter.handle();
+        meter.by_ref().for_each(drop);
+        handle.correlation()
+    }
+
+    #[test]
+    fn identical_and_inverted() {
+        let sine: Vec<f32> = SineWave::new(440.0).take(48000).collect();
+        assert!((measure(|n| [sine[n], sine[n]]) - 1.0).abs() < 1e-3);
+        assert!((measure(|n| [sine[n], -sine[n]]) + 1.0).abs() < 1e-3);
+    }
+
+    #[test]
+    fn uncorrelated_noise() {
+        let mut seed = 0x1234_5678u32;
+        let mut random = move || {
+            seed ^= seed << 13;
+            seed ^= seed >> 17;
+            seed ^= seed << 5;
+            seed as f32 / u32::MAX as f32 - 0.5
+        };
+        assert!(measure(|_| [random(), random()]).abs() < 0.1);
+    }
+
+    #[test]
+    fn requires_stereo() {
+        let error = SineWave::new(440.0).correlation_meter().err().unwrap();
+        assert_eq!(error.channels, 1);
+    }
+}
@@ -0,0 +1,123 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Counted` object.
+pub fn counted<I>(input: I) -> (Counted<I>, Arc<AtomicU64>)
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let count = Arc::new(AtomicU64::new(0));
+    let counted = Counted {
+        input,
+        count: count.clone(),
+    };
+    (counted, count)
+}
+
+/// A source that counts the samples returned by its inner source.
+///
+/// The samples themselves are passed through unchanged. The count is read from the counter
+/// returned with the source, from any thread.
+#[derive(Debug)]
+pub struct Counted<I> {
+    input: I,
+    count: Arc<AtomicU64>,
+}
+
+impl<I> Counted<I> {
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Counted<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Counted<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Counted<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn counts_samples() {
+        let (mut source, count) = SamplesBuffer::new(2, 1000, vec![1i16; 600]).counted();
+        assert_eq!(count.load(Ordering::Relaxed), 0);
+
+        source.by_ref().take(250).for_each(drop);
+        assert_eq!(count.load(Ordering::Relaxed), 250);
+
+        assert_eq!(source.by_ref().count(), 350);
+        assert_eq!(source.next(), None);
+        assert_eq!(count.load(Ordering::Relaxed), 600);
+    }
+}
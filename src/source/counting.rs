@@ -0,0 +1,115 @@
+use std::time::Duration;
+
+use crate::Source;
+
+/// A source whose samples are a ramp: `0.0`, `1.0`, `2.0` and so on.
+///
+/// The value of each sample is its index, which makes it easy to check what a filter did to the
+/// samples in tests. The samples are not in the usual `-1.0..=1.0` range, so this source is not
+/// meant to be played.
+///
+/// Only available with the `test-support` feature.
+#[derive(Clone, Debug)]
+pub struct CountingSource {
+    channels: u16,
+    sample_rate: u32,
+    // Index of the next sample.
+    position: u64,
+    // Total number of samples.
+    len: u64,
+}
+
+impl CountingSource {
+    /// Builds a new `CountingSource` that lasts `duration`, rounded to whole frames.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `channels` or `sample_rate` is 0.
+    pub fn new(channels: u16, sample_rate: u32, duration: Duration) -> CountingSource {
+        assert!(channels >= 1);
+        assert!(sample_rate >= 1);
+
+        let frames = (duration.as_secs_f64() * sample_rate as f64).round() as u64;
+        CountingSource {
+            channels,
+            sample_rate,
+            position: 0,
+            len: frames * channels as u64,
+        }
+    }
+}
+
+impl Iterator for CountingSource {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.position >= self.len {
+            return None;
+        }
+        let sample = self.position as f32;
+        self.position += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = (self.len - self.position) as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl ExactSizeIterator for CountingSource {}
+
+impl Source for CountingSource {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let frames = self.len / self.channels as u64;
+        Some(Duration::from_nanos(
+            frames * 1_000_000_000 / self.sample_rate as u64,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::source::{CountingSource, Source};
+
+    #[test]
+    fn ramp() {
+        let source = CountingSource::new(2, 10, Duration::from_millis(300));
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.sample_rate(), 10);
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(300)));
+        assert_eq!(source.len(), 6);
+        assert_eq!(
+            source.collect::<Vec<_>>(),
+            vec![0.0, 1.0, 2.0, 3.0, 4.0, 5.0]
+        );
+    }
+
+    #[test]
+    fn with_filters() {
+        let output: Vec<f32> = CountingSource::new(2, 1000, Duration::from_millis(5))
+            .skip_duration(Duration::from_millis(3))
+            .collect();
+        assert_eq!(output, vec![6.0, 7.0, 8.0, 9.0]);
+    }
+}
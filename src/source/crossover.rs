@@ -0,0 +1,270 @@
+use std::collections::VecDeque;
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::source::biquad::{Biquad, Coefficients};
+use crate::Source;
+
+/// Maximum number of frames kept for a band that lags behind the others.
+const MAX_BUFFERED_FRAMES: usize = 1 << 16;
+
+/// Internal function that builds the `CrossoverBand` objects.
+///
+/// # Panic
+///
+/// Panics if the frequencies aren't strictly increasing or aren't between 0 and half the sample
+/// rate.
+pub fn crossover<I>(input: I, frequencies: &[f32]) -> Vec<CrossoverBand<I>>
+where
+    I: Source<Item = f32>,
+{
+    let sample_rate = input.sample_rate() as f32;
+    let channels = input.channels() as usize;
+    assert!(
+        frequencies
+            .iter()
+            .all(|&freq| freq > 0.0 && freq < sample_rate / 2.0),
+        "crossover frequencies must be between 0 and half the sample rate"
+    );
+    assert!(
+        frequencies.windows(2).all(|pair| pair[0] < pair[1]),
+        "crossover frequencies must be strictly increasing"
+    );
+
+    let num_bands = frequencies.len() + 1;
+    let shared = Arc::new(Mutex::new(Shared {
+        input,
+        buffers: vec![VecDeque::new(); num_bands],
+        alive: vec![true; num_bands],
+    }));
+
+    (0..num_bands)
+        .map(|band| {
+            // Band `n` is high-passed by the crossovers below it and low-passed by the next one.
+            // The crossovers above that one are applied as allpass filters to keep all the bands
+            // in phase.
+            let q = FRAC_1_SQRT_2;
+            let mut stages = Vec::new();
+            for (index, &freq) in frequencies.iter().enumerate() {
+                match index.cmp(&band) {
+                    // Linkwitz-Riley filters are two Butterworth filters in a row.
+                    std::cmp::Ordering::Less => {
+                        stages.extend([Coefficients::high_pass(freq, q, sample_rate); 2])
+                    }
+                    std::cmp::Ordering::Equal => {
+                        stages.extend([Coefficients::low_pass(freq, q, sample_rate); 2])
+                    }
+                    std::cmp::Ordering::Greater => {
+                        stages.push(Coefficients::all_pass(freq, q, sample_rate))
+                    }
+                }
+            }
+            let stages = (0..channels)
+                .map(|_| stages.iter().map(|&c| Biquad::new(c)).collect())
+                .collect();
+
+            CrossoverBand {
+                shared: shared.clone(),
+                band,
+                stages,
+                channels,
+                current_channel: 0,
+            }
+        })
+        .collect()
+}
+
+struct Shared<I> {
+    input: I,
+    // Samples read from the input but not yet by each band.
+    buffers: Vec<VecDeque<f32>>,
+    // Whether each band still exists.
+    alive: Vec<bool>,
+}
+
+/// One of the frequency bands of a source split by a crossover.
+///
+/// The bands are built from 4th order Linkwitz-Riley filters and are in phase with each other.
+/// Summing them gives back the magnitude of the original sound, but not its waveform: the phase
+/// is shifted like by an allpass filter at each crossover frequency. Like with `split`, samples
+/// are kept until all the bands have read them, up to 65536 frames. If a band lags behind by
+/// more than that, its oldest frames are dropped.
+pub struct CrossoverBand<I> {
+    shared: Arc<Mutex<Shared<I>>>,
+    // Index of the band, 0 being the lowest one.
+    band: usize,
+    // The filters applied to each channel, in order.
+    stages: Vec<Vec<Biquad>>,
+    channels: usize,
+    // Channel of the next sample returned by the iterator.
+    current_channel: usize,
+}
+
+impl<I> CrossoverBand<I> {
+    /// Returns the index of this band, the lowest band being 0.
+    #[inline]
+    pub fn band(&self) -> usize {
+        self.band
+    }
+}
+
+impl<I> Iterator for CrossoverBand<I>
+where
+    I: Source<Item = f32>,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        let sample = {
+            let mut shared = self.shared.lock().unwrap();
+            match shared.buffers[self.band].pop_front() {
+                Some(sample) => sample,
+                None => {
+                    let sample = shared.input.next()?;
+                    let Shared { buffers, alive, .. } = &mut *shared;
+                    for (band, buffer) in buffers.iter_mut().enumerate() {
+                        if band != self.band && alive[band] {
+                            buffer.push_back(sample);
+                            // Whole frames are dropped so that the channels stay in place.
+                            if buffer.len() > MAX_BUFFERED_FRAMES * self.channels {
+                                buffer.drain(..self.channels);
+                            }
+                        }
+                    }
+                    sample
+                }
+            }
+        };
+
+        let channel = self.current_channel;
+        self.current_channel = (self.current_channel + 1) % self.channels;
+        Some(
+            self.stages[channel]
+                .iter_mut()
+                .fold(sample, |sample, stage| stage.process(sample)),
+        )
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let shared = self.shared.lock().unwrap();
+        let buffered = shared.buffers[self.band].len();
+        let (min, max) = shared.input.size_hint();
+        (min + buffered, max.map(|max| max + buffered))
+    }
+}
+
+impl<I> Source for CrossoverBand<I>
+where
+    I: Source<Item = f32>,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let shared = self.shared.lock().unwrap();
+        let buffered = shared.buffers[self.band].len();
+        if buffered > 0 {
+            return Some(buffered);
+        }
+        shared.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels as u16
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.shared.lock().unwrap().input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.shared.lock().unwrap().input.total_duration()
+    }
+}
+
+impl<I> Drop for CrossoverBand<I> {
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.alive[self.band] = false;
+            shared.buffers[self.band].clear();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::{FRAC_1_SQRT_2, PI};
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::biquad::{Biquad, Coefficients};
+    use crate::source::Source;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn bands_sum_to_allpass() {
+        let data: Vec<f32> = (0..44100)
+            .map(|i| {
+                let t = i as f32 / 44100.0;
+                [100.0f32, 1000.0, 5000.0]
+                    .iter()
+                    .map(|freq| (2.0 * PI * freq * t).sin() * 0.3)
+                    .sum()
+            })
+            .collect();
+        let input = SamplesBuffer::new(1, 44100, data.clone());
+
+        let mut bands = input.crossover(&[1000.0]);
+        assert_eq!(bands.len(), 2);
+        let high: Vec<f32> = bands.pop().unwrap().collect();
+        let low: Vec<f32> = bands.pop().unwrap().collect();
+
+        // The sum of the bands is the input with its phase shifted by an allpass filter.
+        let mut allpass = Biquad::new(Coefficients::all_pass(1000.0, FRAC_1_SQRT_2, 44100.0));
+        for ((l, h), x) in low.iter().zip(&high).zip(data) {
+            assert!((l + h - allpass.process(x)).abs() < 1e-3);
+        }
+    }
+
+    #[test]
+    fn drops_whole_frames() {
+        let data: Vec<f32> = (0..70000).flat_map(|_| [0.0, 0.5, 0.0]).collect();
+        let mut bands = SamplesBuffer::new(3, 44100, data).crossover(&[1000.0]);
+        let high = bands.pop().unwrap();
+        let low = bands.pop().unwrap();
+
+        // The low band lags behind by the whole sound, so its oldest frames are dropped.
+        assert_eq!(high.count(), 210000);
+        let low: Vec<f32> = low.collect();
+        assert_eq!(low.len(), super::MAX_BUFFERED_FRAMES * 3);
+        for frame in low.chunks(3) {
+            assert_eq!(frame[0], 0.0);
+            assert_eq!(frame[2], 0.0);
+        }
+    }
+
+    #[test]
+    fn splits_frequencies() {
+        let data: Vec<f32> = (0..44100)
+            .map(|i| (2.0 * PI * 100.0 * i as f32 / 44100.0).sin())
+            .collect();
+        let bands = SamplesBuffer::new(1, 44100, data).crossover(&[1000.0, 5000.0]);
+        let outputs: Vec<Vec<f32>> = bands.into_iter().map(|band| band.collect()).collect();
+
+        assert!(rms(&outputs[0][4410..]) > 0.7);
+        assert!(rms(&outputs[1][4410..]) < 0.01);
+        assert!(rms(&outputs[2][4410..]) < 0.01);
+    }
+
+    #[test]
+    #[should_panic]
+    fn unsorted_frequencies() {
+        SamplesBuffer::new(1, 44100, vec![0.0f32]).crossover(&[5000.0, 1000.0]);
+    }
+}
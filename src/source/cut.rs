@@ -0,0 +1,174 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Cut` object.
+///
+/// # Panic
+///
+/// Panics if `start` is after `end`.
+pub fn cut<I>(input: I, start: Duration, end: Duration) -> Cut<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(start <= end, "the region must start before it ends");
+
+    let to_samples = |time: Duration| {
+        (time.as_secs_f64() * input.sample_rate() as f64).round() as u64 * input.channels() as u64
+    };
+
+    Cut {
+        start_samples: to_samples(start),
+        cut_samples: to_samples(end) - to_samples(start),
+        cut_duration: end - start,
+        start,
+        samples: 0,
+        input,
+    }
+}
+
+/// Source that removes a region of its inner source and plays what comes before and after it.
+///
+/// The region is rounded to whole frames.
+#[derive(Clone, Debug)]
+pub struct Cut<I> {
+    input: I,
+    start: Duration,
+    cut_duration: Duration,
+    // The region in samples, and the number of samples read before reaching it.
+    start_samples: u64,
+    cut_samples: u64,
+    samples: u64,
+}
+
+impl<I> Cut<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Cut<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.samples == self.start_samples && self.cut_samples > 0 {
+            for _ in 0..self.cut_samples {
+                self.input.next()?;
+            }
+            // Past the region, no more samples need to be counted.
+            self.cut_samples = 0;
+        }
+        let sample = self.input.next()?;
+        self.samples += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.input.size_hint();
+        if self.cut_samples == 0 || self.samples > self.start_samples {
+            return (min, max);
+        }
+
+        let before = (self.start_samples - self.samples) as usize;
+        let remove = |len: usize| {
+            if len <= before {
+                len
+            } else {
+                before + len.saturating_sub(before + self.cut_samples as usize)
+            }
+        };
+        (remove(min), max.map(remove))
+    }
+}
+
+impl<I> Source for Cut<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.cut_samples == 0 {
+            return self.input.current_frame_len();
+        }
+        // The frames end at the start of the region, where the format may change.
+        let before = (self.start_samples - self.samples) as usize;
+        match self.input.current_frame_len() {
+            Some(len) if before > 0 => Some(len.min(before)),
+            Some(_) => None,
+            None if before > 0 => Some(before),
+            None => None,
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration().map(|duration| {
+            let removed = duration.saturating_sub(self.start).min(self.cut_duration);
+            duration - removed
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{CountingSource, Source};
+
+    #[test]
+    fn removes_region() {
+        let source = CountingSource::new(2, 100, Duration::from_secs(1))
+            .cut(Duration::from_millis(200), Duration::from_millis(700));
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(500)));
+        assert_eq!(source.size_hint(), (100, Some(100)));
+
+        let output: Vec<f32> = source.collect();
+        let expected: Vec<f32> = (0..40).chain(140..200).map(|n| n as f32).collect();
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn region_past_the_end() {
+        let source = SamplesBuffer::new(1, 100, vec![1i16; 50]);
+        let source = source.cut(Duration::from_millis(300), Duration::from_secs(2));
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(300)));
+        assert_eq!(source.count(), 30);
+    }
+}
@@ -0,0 +1,474 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use crate::source::UniformSourceIterator;
+use crate::{Sample, Source};
+
+/// Maximum number of samples read from the inner source at once.
+const MAX_CHUNK_LEN: usize = 1024;
+
+/// Internal function that builds a `DecodeThread` object.
+pub fn decode_thread<I>(input: I, buffer: Duration) -> DecodeThread<I::Item>
+where
+    I: Source + Send + 'static,
+    I::Item: Sample + Send,
+{
+    DecodeWorker::new(buffer).decode(input)
+}
+
+/// A thread that decodes sounds in advance, one after the other.
+///
+/// Each sound gets its own buffer. The worker always decodes the first sound whose buffer isn't
+/// full, so the sounds are decoded in the order they were given, and a sound that is read slowly
+/// or never ends doesn't stop the ones after it from being decoded. The thread ends once this
+/// handle is dropped and all the sounds have been decoded or dropped.
+pub(crate) struct DecodeWorker {
+    shared: Arc<WorkerShared>,
+    buffer: Duration,
+}
+
+struct WorkerShared {
+    state: Mutex<WorkerState>,
+    // Notified when the worker has something to do.
+    condvar: Condvar,
+    // Notified when the worker waits because all the buffers are full.
+    idle: Condvar,
+}
+
+struct WorkerState {
+    // Sounds given to the worker since it last looked.
+    new_jobs: Vec<Box<dyn Job>>,
+    // True if a buffer ran low or a `DecodeThread` was dropped since the worker last looked.
+    wake: bool,
+    // False once the `DecodeWorker` has been dropped.
+    alive: bool,
+    // True while the worker waits for something to do.
+    idle: bool,
+}
+
+impl WorkerShared {
+    /// Makes the worker look at the buffers again.
+    fn wake(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            state.wake = true;
+        }
+        self.condvar.notify_one();
+    }
+}
+
+impl DecodeWorker {
+    /// Starts a worker that decodes each sound `buffer` in advance.
+    pub(crate) fn new(buffer: Duration) -> DecodeWorker {
+        let shared = Arc::new(WorkerShared {
+            state: Mutex::new(WorkerState {
+                new_jobs: Vec::new(),
+                wake: false,
+                alive: true,
+                idle: false,
+            }),
+            condvar: Condvar::new(),
+            idle: Condvar::new(),
+        });
+        let thread_shared = shared.clone();
+        thread::spawn(move || work(thread_shared));
+        DecodeWorker { shared, buffer }
+    }
+
+    /// Decodes `input` after the sounds given before it.
+    pub(crate) fn decode<I>(&self, input: I) -> DecodeThread<I::Item>
+    where
+        I: Source + Send + 'static,
+        I::Item: Sample + Send,
+    {
+        let channels = input.channels();
+        let sample_rate = input.sample_rate();
+        let frames = (self.buffer.as_secs_f32() * sample_rate as f32) as usize;
+        let capacity = frames.max(1) * channels as usize;
+        // Small chunks let slow sources fill the buffer regularly.
+        let chunk_len =
+            ((capacity / 8).clamp(1, MAX_CHUNK_LEN) / channels as usize).max(1) * channels as usize;
+
+        let ring = Arc::new(Mutex::new(Ring {
+            samples: VecDeque::with_capacity(capacity + chunk_len),
+            finished: false,
+            alive: true,
+        }));
+
+        // The format can't change, so that the samples can be read without knowing where frames
+        // end.
+        let input = UniformSourceIterator::new(input, channels, sample_rate);
+        let job = SourceJob {
+            input,
+            ring: ring.clone(),
+            capacity,
+            chunk: Vec::with_capacity(chunk_len),
+            chunk_len,
+        };
+        self.shared
+            .state
+            .lock()
+            .unwrap()
+            .new_jobs
+            .push(Box::new(job));
+        self.shared.condvar.notify_one();
+
+        DecodeThread {
+            ring,
+            worker: self.shared.clone(),
+            block: VecDeque::with_capacity(chunk_len),
+            chunk_len,
+            low_water: (capacity / 2).max(1),
+            channels,
+            sample_rate,
+            underrun: 0,
+            underruns: 0,
+        }
+    }
+
+    /// Waits until all the buffers are full, or their sounds have been decoded entirely.
+    #[cfg(test)]
+    pub(crate) fn wait_idle(&self) {
+        wait_idle(&self.shared);
+    }
+}
+
+impl Drop for DecodeWorker {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.alive = false;
+        }
+        self.shared.condvar.notify_one();
+    }
+}
+
+#[cfg(test)]
+fn wait_idle(shared: &WorkerShared) {
+    let mut state = shared.state.lock().unwrap();
+    while !state.idle || state.wake || !state.new_jobs.is_empty() {
+        state = shared.idle.wait(state).unwrap();
+    }
+}
+
+/// Result of decoding a chunk of a sound.
+enum Fill {
+    // The buffer of the sound is full, nothing was decoded.
+    Full,
+    // A chunk was added to the buffer.
+    Filled,
+    // The sound ended, or the `DecodeThread` was dropped.
+    Done,
+}
+
+/// A sound decoded by the worker.
+trait Job: Send {
+    fn fill(&mut self) -> Fill;
+}
+
+struct SourceJob<I>
+where
+    I: Iterator,
+{
+    input: I,
+    ring: Arc<Mutex<Ring<I::Item>>>,
+    capacity: usize,
+    chunk: Vec<I::Item>,
+    chunk_len: usize,
+}
+
+impl<I> Job for SourceJob<I>
+where
+    I: Iterator + Send,
+    I::Item: Send,
+{
+    fn fill(&mut self) -> Fill {
+        {
+            let ring = self.ring.lock().unwrap();
+            if !ring.alive {
+                return Fill::Done;
+            }
+            if ring.samples.len() >= self.capacity {
+                return Fill::Full;
+            }
+        }
+
+        // Decode without holding the lock, so that the audio thread can keep reading.
+        self.chunk.extend(self.input.by_ref().take(self.chunk_len));
+        let ended = self.chunk.len() < self.chunk_len;
+
+        let mut ring = self.ring.lock().unwrap();
+        ring.samples.extend(self.chunk.drain(..));
+        ring.finished = ended;
+        if ended {
+            Fill::Done
+        } else {
+            Fill::Filled
+        }
+    }
+}
+
+/// Runs the worker on its thread.
+fn work(shared: Arc<WorkerShared>) {
+    let mut jobs: Vec<Box<dyn Job>> = Vec::new();
+    loop {
+        // Decode a chunk of the first sound whose buffer isn't full, then start over so that the
+        // sounds given first are decoded first.
+        let mut index = 0;
+        let mut busy = false;
+        while index < jobs.len() {
+            match jobs[index].fill() {
+                Fill::Full => index += 1,
+                Fill::Filled => {
+                    busy = true;
+                    break;
+                }
+                Fill::Done => {
+                    jobs.remove(index);
+                    busy = true;
+                    break;
+                }
+            }
+        }
+        if busy {
+            continue;
+        }
+
+        let mut state = shared.state.lock().unwrap();
+        while !state.wake && state.new_jobs.is_empty() {
+            state.idle = true;
+            shared.idle.notify_all();
+            if !state.alive && jobs.is_empty() {
+                return;
+            }
+            state = shared.condvar.wait(state).unwrap();
+        }
+        state.idle = false;
+        state.wake = false;
+        jobs.append(&mut state.new_jobs);
+    }
+}
+
+struct Ring<S> {
+    samples: VecDeque<S>,
+    // True once the inner source has ended.
+    finished: bool,
+    // False once the `DecodeThread` has been dropped.
+    alive: bool,
+}
+
+/// A source that is read on a separate thread.
+///
+/// The samples are read in advance and stored in a buffer, so that reading this source never
+/// blocks, even when the inner source is slow to decode. If the buffer runs empty, silence is
+/// returned until more samples are available; this is counted as an underrun.
+///
+/// The samples are taken from the buffer in blocks, and the decoding thread is only woken up
+/// when the buffer falls below half of its size, so that reading a sample is usually as cheap as
+/// reading it from a `Vec`.
+///
+/// The samples are converted to the channels and sample rate that the inner source has when the
+/// `DecodeThread` is built.
+pub struct DecodeThread<S> {
+    ring: Arc<Mutex<Ring<S>>>,
+    worker: Arc<WorkerShared>,
+    // Samples taken from `ring` and not returned yet.
+    block: VecDeque<S>,
+    chunk_len: usize,
+    // The decoding thread is woken up when `ring` falls below this number of samples.
+    low_water: usize,
+    channels: u16,
+    sample_rate: u32,
+    // Number of silent samples left to return for the current underrun.
+    underrun: u16,
+    underruns: u64,
+}
+
+impl<S> DecodeThread<S> {
+    /// Returns the number of times the buffer ran empty before the end of the inner source.
+    #[inline]
+    pub fn underruns(&self) -> u64 {
+        self.underruns
+    }
+
+    /// Waits until the buffer is full, or the inner source has been decoded entirely.
+    #[cfg(test)]
+    pub(crate) fn wait_idle(&self) {
+        wait_idle(&self.worker);
+    }
+
+    /// Takes the next block of samples from the buffer. Returns true if the inner source has ended
+    /// and everything it produced was taken.
+    fn take_block(&mut self) -> bool {
+        let mut ring = self.ring.lock().unwrap();
+        let before = ring.samples.len();
+        let len = before.min(self.chunk_len);
+        self.block.extend(ring.samples.drain(..len));
+        let finished = ring.finished;
+        let ended = finished && ring.samples.is_empty();
+        drop(ring);
+
+        if !finished && before >= self.low_water && before - len < self.low_water {
+            self.worker.wake();
+        }
+        ended
+    }
+}
+
+impl<S> Iterator for DecodeThread<S>
+where
+    S: Sample,
+{
+    type Item = S;
+
+    #[inline]
+    fn next(&mut self) -> Option<S> {
+        if self.underrun > 0 {
+            self.underrun -= 1;
+            return Some(S::zero_value());
+        }
+        if let Some(sample) = self.block.pop_front() {
+            return Some(sample);
+        }
+
+        let ended = self.take_block();
+        match self.block.pop_front() {
+            Some(sample) => Some(sample),
+            None if ended => None,
+            None => {
+                // Play a frame of silence instead of waiting for the decoding thread.
+                self.underruns += 1;
+                self.underrun = self.channels - 1;
+                Some(S::zero_value())
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let ring = self.ring.lock().unwrap();
+        let len = self.block.len() + ring.samples.len() + self.underrun as usize;
+        if ring.finished {
+            (len, Some(len))
+        } else {
+            (len, None)
+        }
+    }
+}
+
+impl<S> Source for DecodeThread<S>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl<S> Drop for DecodeThread<S> {
+    fn drop(&mut self) {
+        if let Ok(mut ring) = self.ring.lock() {
+            ring.alive = false;
+        }
+        self.worker.wake();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use std::time::Duration;
+
+    use super::DecodeWorker;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    /// A source that takes a long time to produce each sample.
+    fn slow_source(data: Vec<i16>) -> impl Source<Item = i16> + Send {
+        SamplesBuffer::new(1, 1000, data).periodic_access(Duration::from_millis(1), |_| {
+            thread::sleep(Duration::from_micros(50))
+        })
+    }
+
+    #[test]
+    fn reads_in_advance() {
+        let data: Vec<i16> = (1..=500).collect();
+        let mut source = slow_source(data.clone()).decode_in_thread(Duration::from_secs(1));
+        assert_eq!(source.channels(), 1);
+        assert_eq!(source.sample_rate(), 1000);
+
+        source.wait_idle();
+
+        let output: Vec<i16> = source.by_ref().collect();
+        assert_eq!(output, data);
+        assert_eq!(source.underruns(), 0);
+    }
+
+    #[test]
+    fn refills_when_half_empty() {
+        let data: Vec<i16> = (1..=1000).collect();
+        let mut source =
+            SamplesBuffer::new(1, 1000, data.clone()).decode_in_thread(Duration::from_millis(100));
+
+        let mut output = Vec::new();
+        while output.len() < data.len() {
+            source.wait_idle();
+            assert!(source.size_hint().0 >= 100.min(data.len() - output.len()));
+            output.extend(source.by_ref().take(60));
+        }
+        assert_eq!(output, data);
+        assert_eq!(source.underruns(), 0);
+    }
+
+    #[test]
+    fn worker_decodes_in_order() {
+        let log = Arc::new(Mutex::new(Vec::new()));
+        let sound = |id: u8| {
+            let log = log.clone();
+            SamplesBuffer::new(1, 1000, vec![id as i16; 100])
+                .periodic_access(Duration::from_millis(10), move |_| {
+                    log.lock().unwrap().push(id)
+                })
+        };
+
+        let worker = DecodeWorker::new(Duration::from_secs(1));
+        let first = worker.decode(sound(1));
+        let second = worker.decode(sound(2));
+        worker.wait_idle();
+
+        // The first sound is decoded entirely before the second one starts.
+        let log = log.lock().unwrap();
+        assert_eq!(log.len(), 22);
+        assert!(log[..11].iter().all(|&id| id == 1));
+        assert!(log[11..].iter().all(|&id| id == 2));
+        assert_eq!(first.size_hint(), (100, Some(100)));
+        assert_eq!(second.size_hint(), (100, Some(100)));
+    }
+
+    #[test]
+    fn never_blocks() {
+        let data: Vec<i16> = (1..=500).collect();
+        let mut source = slow_source(data).decode_in_thread(Duration::from_secs(1));
+
+        // The first samples aren't decoded yet, but reading doesn't wait for them.
+        assert_eq!(source.next(), Some(0));
+        assert!(source.underruns() > 0);
+    }
+}
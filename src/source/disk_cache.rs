@@ -0,0 +1,326 @@
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Condvar, Mutex};
+use std::thread;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::UniformSourceIterator;
+use crate::{Sample, Source};
+
+/// How far ahead of the furthest position read so far the source is written to the file.
+const WRITE_AHEAD: Duration = Duration::from_secs(10);
+
+/// Number of frames written to the file at once.
+const CHUNK_FRAMES: usize = 1024;
+
+/// Size of a sample in the file, which stores them as little-endian `f32`.
+const SAMPLE_SIZE: u64 = 4;
+
+/// Internal function that builds a `DiskCache` object.
+pub fn disk_cache<I>(input: I, path: &Path) -> io::Result<DiskCache<I::Item>>
+where
+    I: Source + Send + 'static,
+    I::Item: Sample + Send,
+{
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let total_duration = input.total_duration();
+
+    let writer = BufWriter::new(File::create(path)?);
+    let reader = BufReader::new(File::open(path)?);
+
+    let shared = Arc::new(Shared {
+        state: Mutex::new(State {
+            written: 0,
+            needed: 0,
+            finished: false,
+            alive: true,
+        }),
+        condvar: Condvar::new(),
+    });
+
+    // The format can't change, so that a position in the file is a position in time.
+    let input = UniformSourceIterator::<I, I::Item>::new(input, channels, sample_rate);
+    let write_ahead = (WRITE_AHEAD.as_secs_f64() * sample_rate as f64) as u64 * channels as u64;
+    let thread_shared = shared.clone();
+    let chunk_len = CHUNK_FRAMES * channels as usize;
+    thread::spawn(move || write(input, writer, thread_shared, write_ahead, chunk_len));
+
+    Ok(DiskCache {
+        shared,
+        reader,
+        path: path.to_path_buf(),
+        channels,
+        sample_rate,
+        total_duration,
+        position: 0,
+        underrun: 0,
+        underruns: 0,
+        marker: PhantomData,
+    })
+}
+
+struct Shared {
+    state: Mutex<State>,
+    // Notified when more samples are needed and when the `DiskCache` is dropped.
+    condvar: Condvar,
+}
+
+struct State {
+    // Number of samples available in the file. This is a whole number of frames, except at the
+    // end of the source.
+    written: u64,
+    // Furthest position requested by the reader.
+    needed: u64,
+    // True once the inner source has been entirely written.
+    finished: bool,
+    // False once the `DiskCache` has been dropped.
+    alive: bool,
+}
+
+/// Writes the inner source to the file on the writing thread.
+fn write<I>(
+    mut input: I,
+    mut writer: BufWriter<File>,
+    shared: Arc<Shared>,
+    write_ahead: u64,
+    chunk_len: usize,
+) where
+    I: Iterator,
+    I::Item: Sample,
+{
+    loop {
+        {
+            let mut state = shared.state.lock().unwrap();
+            while state.alive && state.written >= state.needed + write_ahead {
+                state = shared.condvar.wait(state).unwrap();
+            }
+            if !state.alive {
+                return;
+            }
+        }
+
+        let mut len = 0;
+        let mut failed = false;
+        for sample in input.by_ref().take(chunk_len) {
+            if writer.write_all(&sample.to_f32().to_le_bytes()).is_err() {
+                failed = true;
+                break;
+            }
+            len += 1;
+        }
+        failed = failed || writer.flush().is_err();
+
+        let mut state = shared.state.lock().unwrap();
+        if failed {
+            // An error ends the cached source early, like a decoding error would. Some of the
+            // samples of this chunk may still be in the `BufWriter`, so only the ones that reach
+            // the file are kept.
+            if let Ok(metadata) = writer.get_ref().metadata() {
+                state.written = state.written.max(metadata.len() / SAMPLE_SIZE);
+            }
+        } else {
+            state.written += len as u64;
+        }
+        let ended = failed || len < chunk_len;
+        state.finished = ended;
+        drop(state);
+
+        if ended {
+            return;
+        }
+    }
+}
+
+/// A source whose samples are stored in a file while it plays, which allows seeking in it.
+///
+/// The inner source is written to the file on a separate thread, up to 10 seconds ahead of the
+/// furthest position read so far. Reading never waits for that thread, so this can be played
+/// directly on the audio thread: if the part being read hasn't been written yet, for example right
+/// after seeking far ahead, silence is returned until it is. This is counted as an underrun. The
+/// file is removed when the `DiskCache` is dropped.
+///
+/// The samples are converted to the channels and sample rate that the inner source has when the
+/// `DiskCache` is built.
+pub struct DiskCache<S> {
+    shared: Arc<Shared>,
+    reader: BufReader<File>,
+    path: PathBuf,
+    channels: u16,
+    sample_rate: u32,
+    total_duration: Option<Duration>,
+    // Index of the next sample to read.
+    position: u64,
+    // Number of silent samples left to return for the current underrun.
+    underrun: u16,
+    underruns: u64,
+    marker: PhantomData<S>,
+}
+
+impl<S> DiskCache<S> {
+    /// Moves to the given position, relative to the start of the source.
+    ///
+    /// Positions after the end of the source make it end.
+    pub fn seek(&mut self, position: Duration) -> io::Result<()> {
+        let frame = (position.as_secs_f64() * self.sample_rate as f64).round() as u64;
+        self.position = frame * self.channels as u64;
+        self.reader
+            .seek(SeekFrom::Start(self.position * SAMPLE_SIZE))?;
+        Ok(())
+    }
+
+    /// Returns the current position, relative to the start of the source.
+    #[inline]
+    pub fn position(&self) -> Duration {
+        let frames = self.position / self.channels as u64;
+        Duration::from_secs_f64(frames as f64 / self.sample_rate as f64)
+    }
+
+    /// Returns the number of times silence was returned because the part being read wasn't
+    /// written to the file yet.
+    #[inline]
+    pub fn underruns(&self) -> u64 {
+        self.underruns
+    }
+}
+
+impl<S> Iterator for DiskCache<S>
+where
+    S: Sample,
+{
+    type Item = S;
+
+    #[inline]
+    fn next(&mut self) -> Option<S> {
+        if self.underrun > 0 {
+            self.underrun -= 1;
+            return Some(S::zero_value());
+        }
+
+        {
+            let mut state = self.shared.state.lock().unwrap();
+            if self.position + 1 > state.needed {
+                state.needed = self.position + 1;
+                self.shared.condvar.notify_all();
+            }
+            if self.position >= state.written {
+                if state.finished {
+                    return None;
+                }
+                // The file is written a frame at a time, so this is the start of a frame. Play
+                // a frame of silence instead of waiting for the writing thread.
+                self.underruns += 1;
+                self.underrun = self.channels - 1;
+                return Some(S::zero_value());
+            }
+        }
+
+        let mut bytes = [0; SAMPLE_SIZE as usize];
+        self.reader.read_exact(&mut bytes).ok()?;
+        self.position += 1;
+        Some(CpalSample::from(&f32::from_le_bytes(bytes)))
+    }
+}
+
+impl<S> Source for DiskCache<S>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.total_duration
+    }
+}
+
+impl<S> Drop for DiskCache<S> {
+    fn drop(&mut self) {
+        if let Ok(mut state) = self.shared.state.lock() {
+            state.alive = false;
+        }
+        self.shared.condvar.notify_all();
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{DiskCache, Source};
+
+    #[test]
+    fn seek_lands_on_sample() {
+        let path = std::env::temp_dir().join(format!("rodio-disk-cache-{}", std::process::id()));
+        // None of the samples is 0, so that they can't be mistaken for the silence of an underrun.
+        let data: Vec<i16> = (0..20000).map(|i| (i / 2 + 1) as i16).collect();
+        let mut source = SamplesBuffer::new(2, 1000, data.clone())
+            .cache_to_disk(&path)
+            .unwrap();
+        assert!(path.exists());
+
+        let next = |source: &mut DiskCache<i16>| source.find(|&sample| sample != 0);
+        assert_eq!(next(&mut source), Some(1));
+        source.seek(Duration::from_millis(7500)).unwrap();
+        assert_eq!(source.position(), Duration::from_millis(7500));
+        assert_eq!(next(&mut source), Some(7501));
+        assert_eq!(next(&mut source), Some(7501));
+        assert_eq!(next(&mut source), Some(7502));
+
+        // Back to a part that was already played.
+        source.seek(Duration::from_millis(2)).unwrap();
+        let output: Vec<i16> = source.by_ref().filter(|&sample| sample != 0).collect();
+        assert_eq!(output, data[4..]);
+
+        source.seek(Duration::from_secs(60)).unwrap();
+        assert!(source.all(|sample| sample == 0));
+
+        drop(source);
+        assert!(!path.exists());
+    }
+
+    #[test]
+    fn underrun_plays_silent_frames() {
+        let path =
+            std::env::temp_dir().join(format!("rodio-disk-cache-underrun-{}", std::process::id()));
+        // The source is slow, so the first frames aren't written when they are read.
+        let data: Vec<i16> = (1..=900).collect();
+        let slow = SamplesBuffer::new(3, 1000, data.clone())
+            .periodic_access(Duration::from_millis(1), |_| {
+                std::thread::sleep(Duration::from_millis(1))
+            });
+        let mut source = slow.cache_to_disk(&path).unwrap();
+
+        // Silence comes in whole frames and reading it doesn't wait for the writing thread.
+        let output: Vec<i16> = source.by_ref().collect();
+        assert!(source.underruns() > 0);
+        assert_eq!(output.len() % 3, 0);
+        let frames: Vec<&[i16]> = output
+            .chunks(3)
+            .filter(|frame| frame.iter().any(|&sample| sample != 0))
+            .collect();
+        assert_eq!(frames.concat(), data);
+        assert!(output
+            .chunks(3)
+            .all(|frame| frame.iter().all(|&s| s != 0) || frame.iter().all(|&s| s == 0)));
+    }
+}
@@ -0,0 +1,217 @@
+use std::cmp;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::envelope::{time_constant, EnvelopeFollower};
+use crate::source::uniform::UniformSourceIterator;
+use crate::{Sample, Source};
+
+/// Level above which the side-chain is considered active, about -40 dBFS.
+const KEY_THRESHOLD: f32 = 0.01;
+
+/// Internal function that builds a `Duck` object.
+pub fn duck<I1, I2>(
+    input: I1,
+    side_chain: I2,
+    amount: f32,
+    attack: Duration,
+    release: Duration,
+) -> Duck<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    let channels = input.channels();
+    let rate = input.sample_rate();
+    let samples_per_second = rate as f32 * channels as f32;
+
+    Duck {
+        input,
+        side_chain: UniformSourceIterator::new(side_chain, channels, rate),
+        amount: amount.clamp(0.0, 1.0),
+        attack_coeff: time_constant(attack, samples_per_second),
+        release_coeff: time_constant(release, samples_per_second),
+        envelope: EnvelopeFollower::new(Duration::from_secs(0), release, samples_per_second),
+        gain: 1.0,
+    }
+}
+
+/// Lowers the volume of a source while a side-chain source is playing, and mixes both.
+#[derive(Clone)]
+pub struct Duck<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    input: I1,
+    side_chain: UniformSourceIterator<I2, I1::Item>,
+    // Fraction of the volume removed from `input` while the side-chain is active.
+    amount: f32,
+    attack_coeff: f32,
+    release_coeff: f32,
+    // Peak level of the side-chain.
+    envelope: EnvelopeFollower,
+    // Gain currently applied to `input`.
+    gain: f32,
+}
+
+impl<I1, I2> Duck<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    /// Returns the gain currently applied to the ducked source.
+    #[inline]
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Returns a reference to the ducked source.
+    #[inline]
+    pub fn inner(&self) -> &I1 {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the ducked source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I1 {
+        &mut self.input
+    }
+
+    /// Returns the ducked source.
+    #[inline]
+    pub fn into_inner(self) -> I1 {
+        self.input
+    }
+}
+
+impl<I1, I2> Iterator for Duck<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    type Item = I1::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I1::Item> {
+        let sample = self.input.next();
+        let key = self.side_chain.next();
+
+        let level = key.map(|k| k.to_f32().abs()).unwrap_or(0.0);
+        let (target, coeff) = if self.envelope.process(level) > KEY_THRESHOLD {
+            (1.0 - self.amount, self.attack_coeff)
+        } else {
+            (1.0, self.release_coeff)
+        };
+        self.gain = target + (self.gain - target) * coeff;
+
+        match (sample, key) {
+            (Some(s), Some(k)) => Some(s.amplify(self.gain).saturating_add(k)),
+            (Some(s), None) => Some(s.amplify(self.gain)),
+            (None, Some(k)) => Some(k),
+            (None, None) => None,
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let s1 = self.input.size_hint();
+        let s2 = self.side_chain.size_hint();
+
+        let min = cmp::max(s1.0, s2.0);
+        let max = match (s1.1, s2.1) {
+            (Some(s1), Some(s2)) => Some(cmp::max(s1, s2)),
+            _ => None,
+        };
+
+        (min, max)
+    }
+}
+
+impl<I1, I2> Source for Duck<I1, I2>
+where
+    I1: Source,
+    I1::Item: Sample,
+    I2: Source,
+    I2::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let f1 = self.input.current_frame_len();
+        let f2 = self.side_chain.current_frame_len();
+
+        match (f1, f2) {
+            (Some(f1), Some(f2)) => Some(cmp::min(f1, f2)),
+            _ => None,
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let f1 = self.input.total_duration();
+        let f2 = self.side_chain.total_duration();
+
+        match (f1, f2) {
+            (Some(f1), Some(f2)) => Some(cmp::max(f1, f2)),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn ducks_while_side_chain_plays() {
+        // One second of music at 1000 Hz.
+        let music = SamplesBuffer::new(1, 1000, vec![0.5f32; 1000]);
+        // The voice is loud for 0.3 seconds, then silent.
+        let mut voice = vec![0.5f32; 300];
+        voice.extend(vec![0.0f32; 700]);
+        let voice = SamplesBuffer::new(1, 1000, voice);
+
+        let mut source = music.duck_by(
+            voice,
+            0.75,
+            Duration::from_millis(10),
+            Duration::from_millis(50),
+        );
+
+        // Let the attack settle while the voice is loud.
+        for _ in 0..200 {
+            source.next();
+        }
+        assert!((source.gain() - 0.25).abs() < 0.01);
+        assert!((source.next().unwrap() - (0.5 * 0.25 + 0.5)).abs() < 0.01);
+
+        // Let the release settle once the voice is silent.
+        for _ in 201..900 {
+            source.next();
+        }
+        assert!((source.gain() - 1.0).abs() < 0.01);
+        assert!((source.next().unwrap() - 0.5).abs() < 0.01);
+    }
+}
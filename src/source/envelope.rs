@@ -0,0 +1,52 @@
+//! Helpers shared by the filters that follow the level of a signal.
+
+use std::time::Duration;
+
+/// Returns the coefficient of a one-pole smoothing filter that settles in `time`.
+///
+/// `samples_per_second` is the number of samples processed per second, in other words the
+/// sample rate multiplied by the number of channels.
+pub(crate) fn time_constant(time: Duration, samples_per_second: f32) -> f32 {
+    let samples = time.as_secs_f32() * samples_per_second;
+    if samples <= 0.0 {
+        0.0
+    } else {
+        (-1.0 / samples).exp()
+    }
+}
+
+/// Follows the level of a signal, rising with the attack time and falling with the release time.
+#[derive(Clone, Debug)]
+pub(crate) struct EnvelopeFollower {
+    attack_coeff: f32,
+    release_coeff: f32,
+    value: f32,
+}
+
+impl EnvelopeFollower {
+    pub(crate) fn new(attack: Duration, release: Duration, samples_per_second: f32) -> Self {
+        EnvelopeFollower {
+            attack_coeff: time_constant(attack, samples_per_second),
+            release_coeff: time_constant(release, samples_per_second),
+            value: 0.0,
+        }
+    }
+
+    /// Feeds a new level and returns the updated envelope.
+    #[inline]
+    pub(crate) fn process(&mut self, level: f32) -> f32 {
+        let coeff = if level > self.value {
+            self.attack_coeff
+        } else {
+            self.release_coeff
+        };
+        self.value = level + (self.value - level) * coeff;
+        self.value
+    }
+
+    /// Returns the current envelope.
+    #[inline]
+    pub(crate) fn value(&self) -> f32 {
+        self.value
+    }
+}
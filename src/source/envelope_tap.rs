@@ -0,0 +1,152 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::envelope::EnvelopeFollower;
+use crate::{Sample, Source};
+
+/// Internal function that builds an `EnvelopeTap` object.
+pub fn envelope_tap<I>(
+    input: I,
+    attack: Duration,
+    release: Duration,
+) -> (EnvelopeTap<I>, Arc<AtomicU32>)
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let samples_per_second = input.sample_rate() as f32 * input.channels() as f32;
+    let value = Arc::new(AtomicU32::new(0f32.to_bits()));
+    let tap = EnvelopeTap {
+        input,
+        envelope: EnvelopeFollower::new(attack, release, samples_per_second),
+        value: value.clone(),
+    };
+    (tap, value)
+}
+
+/// A source that follows the amplitude envelope of its inner source, for example to drive
+/// visuals or to trigger other effects.
+///
+/// The samples themselves are passed through unchanged. The envelope is stored as the bits of a
+/// `f32` in the value returned with the source, and can be read from any thread with
+/// `f32::from_bits`.
+#[derive(Debug)]
+pub struct EnvelopeTap<I> {
+    input: I,
+    envelope: EnvelopeFollower,
+    value: Arc<AtomicU32>,
+}
+
+impl<I> EnvelopeTap<I> {
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for EnvelopeTap<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+        let envelope = self.envelope.process(sample.to_f32().abs());
+        self.value.store(envelope.to_bits(), Ordering::Relaxed);
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for EnvelopeTap<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for EnvelopeTap<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::Ordering;
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn follows_transient() {
+        // 10 ms at full scale, then silence.
+        let mut data = vec![1.0f32; 480];
+        data.extend(vec![0.0; 48000]);
+        let data_copy = data.clone();
+        let (mut source, envelope) = SamplesBuffer::new(1, 48000, data)
+            .envelope_follower(Duration::from_millis(1), Duration::from_millis(100));
+        let envelope = move || f32::from_bits(envelope.load(Ordering::Relaxed));
+        assert_eq!(envelope(), 0.0);
+
+        // The envelope reaches the level of the transient quickly.
+        let output: Vec<f32> = source.by_ref().take(480).collect();
+        let peak = envelope();
+        assert!(peak > 0.99, "{}", peak);
+
+        // After the release time, it has fallen to about a third, like a one-pole filter.
+        let rest: Vec<f32> = source.by_ref().take(4800).collect();
+        assert!(
+            (envelope() - peak * (-1f32).exp()).abs() < 0.01,
+            "{}",
+            envelope()
+        );
+
+        // The samples are unchanged.
+        assert_eq!([output, rest].concat(), data_copy[..5280]);
+    }
+}
@@ -0,0 +1,176 @@
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::envelope::EnvelopeFollower;
+use crate::{Sample, Source};
+
+/// Internal function that builds an `Expander` object.
+pub fn expander<I>(
+    input: I,
+    threshold_db: f32,
+    ratio: f32,
+    attack: Duration,
+    release: Duration,
+) -> Expander<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let samples_per_second = input.sample_rate() as f32 * input.channels() as f32;
+
+    Expander {
+        input,
+        threshold_db,
+        ratio: ratio.max(1.0),
+        envelope: EnvelopeFollower::new(attack, release, samples_per_second),
+    }
+}
+
+/// Filter that attenuates the sound when its level is below a threshold.
+#[derive(Clone, Debug)]
+pub struct Expander<I> {
+    input: I,
+    threshold_db: f32,
+    // Each decibel below the threshold becomes `ratio` decibels below it.
+    ratio: f32,
+    envelope: EnvelopeFollower,
+}
+
+impl<I> Expander<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns the gain currently applied to the sound.
+    #[inline]
+    pub fn gain(&self) -> f32 {
+        let level = self.envelope.value();
+        if level <= 0.0 {
+            return 0.0;
+        }
+
+        let level_db = 20.0 * level.log10();
+        if level_db >= self.threshold_db {
+            1.0
+        } else {
+            let gain_db = (level_db - self.threshold_db) * (self.ratio - 1.0);
+            10f32.powf(gain_db / 20.0)
+        }
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Expander<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+        self.envelope.process(sample.to_f32().abs());
+        Some(sample.amplify(self.gain()))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Expander<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Expander<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn attenuates_below_threshold() {
+        // -40 dBFS, 20 dB below the threshold.
+        let input = SamplesBuffer::new(1, 1000, vec![0.01f32; 1000]);
+        let output: Vec<f32> = input
+            .expand(
+                -20.0,
+                2.0,
+                Duration::from_millis(1),
+                Duration::from_millis(10),
+            )
+            .collect();
+
+        // With a ratio of 2 the sound ends up 40 dB below the threshold, at -60 dBFS.
+        let last = *output.last().unwrap();
+        assert!((last - 0.001).abs() < 0.0001);
+    }
+
+    #[test]
+    fn passes_above_threshold() {
+        let input = SamplesBuffer::new(1, 1000, vec![0.5f32; 1000]);
+        let output: Vec<f32> = input
+            .expand(
+                -20.0,
+                2.0,
+                Duration::from_millis(1),
+                Duration::from_millis(10),
+            )
+            .collect();
+
+        // Skip the attack of the envelope.
+        for sample in &output[100..] {
+            assert_eq!(*sample, 0.5);
+        }
+    }
+}
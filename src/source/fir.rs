@@ -0,0 +1,189 @@
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Fir` object.
+///
+/// # Panic
+///
+/// Panics if `coefficients` is empty.
+pub fn fir<I>(input: I, coefficients: Vec<f32>) -> Fir<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(
+        !coefficients.is_empty(),
+        "a FIR filter needs at least one tap"
+    );
+
+    let channels = input.channels();
+    Fir {
+        history: vec![0.0; coefficients.len() * channels as usize],
+        coefficients,
+        channels,
+        position: 0,
+        current_channel: 0,
+        input,
+    }
+}
+
+/// Filter that convolves each channel with a finite impulse response.
+///
+/// The output is `y[n] = c[0] x[n] + c[1] x[n - 1] + ... + c[k] x[n - k]`, computed directly for
+/// each sample, on each channel separately. The filter is causal, so a linear-phase filter with
+/// `k + 1` taps delays the sound by `k / 2` samples.
+#[derive(Clone, Debug)]
+pub struct Fir<I> {
+    input: I,
+    coefficients: Vec<f32>,
+    channels: u16,
+    // Last inputs of each channel, interleaved, as many frames as there are coefficients.
+    history: Vec<f32>,
+    // Frame of `history` that holds the current input.
+    position: usize,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+}
+
+impl<I> Fir<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns the coefficients of the filter.
+    #[inline]
+    pub fn coefficients(&self) -> &[f32] {
+        &self.coefficients
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Fir<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.current_channel == 0 {
+            // Starts from silence again if the number of channels has changed.
+            let channels = self.input.channels();
+            if channels != self.channels {
+                self.channels = channels;
+                self.history = vec![0.0; self.coefficients.len() * channels as usize];
+                self.position = 0;
+            }
+        }
+
+        let value = self.input.next()?.to_f32();
+
+        let channels = self.channels as usize;
+        let channel = self.current_channel as usize;
+        let taps = self.coefficients.len();
+        self.history[self.position * channels + channel] = value;
+
+        let mut output = 0.0;
+        for (delay, coefficient) in self.coefficients.iter().enumerate() {
+            let frame = (self.position + taps - delay) % taps;
+            output += coefficient * self.history[frame * channels + channel];
+        }
+
+        self.current_channel += 1;
+        if self.current_channel >= self.channels {
+            self.current_channel = 0;
+            self.position = (self.position + 1) % taps;
+        }
+
+        Some(CpalSample::from(&output))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Fir<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Fir<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn moving_average_smooths_step() {
+        // A step on the left channel, an inverted one on the right channel.
+        let data: Vec<f32> = (0..20)
+            .flat_map(|n| if n < 10 { [0.0, 0.0] } else { [1.0, -1.0] })
+            .collect();
+        let output: Vec<f32> = SamplesBuffer::new(2, 1000, data)
+            .fir(vec![0.25; 4])
+            .collect();
+        let left: Vec<f32> = output.iter().step_by(2).copied().collect();
+        let right: Vec<f32> = output.iter().skip(1).step_by(2).copied().collect();
+
+        assert!(left[..10].iter().all(|&value| value == 0.0));
+        assert_eq!(left[10..14], [0.25, 0.5, 0.75, 1.0]);
+        assert!(left[14..].iter().all(|&value| value == 1.0));
+        assert_eq!(right[10..14], [-0.25, -0.5, -0.75, -1.0]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn no_taps() {
+        let _ = SamplesBuffer::new(1, 1000, vec![0.0f32]).fir(Vec::new());
+    }
+}
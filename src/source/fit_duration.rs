@@ -0,0 +1,153 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `FitDuration` object.
+pub fn fit_duration<I>(input: I, duration: Duration) -> FitDuration<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let frames = (duration.as_secs_f64() * sample_rate as f64).round() as u64;
+
+    FitDuration {
+        input: Some(input),
+        duration,
+        remaining_samples: frames * channels as u64,
+        channels,
+        sample_rate,
+    }
+}
+
+/// A source that lasts exactly a given duration.
+///
+/// The inner source is cut if it is longer, and silence is added after it if it is shorter.
+#[derive(Clone, Debug)]
+pub struct FitDuration<I> {
+    // `None` once the inner source has ended.
+    input: Option<I>,
+    duration: Duration,
+    remaining_samples: u64,
+    // Format of the source, kept for the silence.
+    channels: u16,
+    sample_rate: u32,
+}
+
+impl<I> FitDuration<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source, or `None` if it has ended.
+    #[inline]
+    pub fn inner(&self) -> Option<&I> {
+        self.input.as_ref()
+    }
+
+    /// Returns a mutable reference to the inner source, or `None` if it has ended.
+    #[inline]
+    pub fn inner_mut(&mut self) -> Option<&mut I> {
+        self.input.as_mut()
+    }
+}
+
+impl<I> Iterator for FitDuration<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.remaining_samples == 0 {
+            return None;
+        }
+        self.remaining_samples -= 1;
+
+        if let Some(input) = &mut self.input {
+            if let Some(sample) = input.next() {
+                return Some(sample);
+            }
+            self.input = None;
+        }
+        Some(I::Item::zero_value())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.remaining_samples as usize;
+        (remaining, Some(remaining))
+    }
+}
+
+impl<I> ExactSizeIterator for FitDuration<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for FitDuration<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let remaining = self.remaining_samples as usize;
+        match &self.input {
+            Some(input) => input
+                .current_frame_len()
+                .map(|len| len.min(remaining))
+                .or(Some(remaining)),
+            None => Some(remaining),
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input
+            .as_ref()
+            .map_or(self.channels, |input| input.channels())
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input
+            .as_ref()
+            .map_or(self.sample_rate, |input| input.sample_rate())
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        Some(self.duration)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn pads_short_source() {
+        let source =
+            SamplesBuffer::new(2, 4, vec![1i16, 2, 3, 4]).fit_duration(Duration::from_secs(1));
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(1)));
+        assert_eq!(source.len(), 8);
+        assert_eq!(source.collect::<Vec<_>>(), vec![1, 2, 3, 4, 0, 0, 0, 0]);
+    }
+
+    #[test]
+    fn cuts_long_source() {
+        let source = SamplesBuffer::new(1, 4, vec![1i16, 2, 3, 4, 5, 6])
+            .fit_duration(Duration::from_millis(750));
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(750)));
+        assert_eq!(source.collect::<Vec<_>>(), vec![1, 2, 3]);
+    }
+}
@@ -0,0 +1,235 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// How the channels of a source are mapped to a larger number of channels.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default)]
+pub enum UpmixMode {
+    /// The last channel of the source is copied to every extra channel. A mono sound plays on
+    /// every speaker. This is what happens when a source is played on a device with more channels.
+    #[default]
+    Duplicate,
+    /// A mono sound plays on the first two channels, the front left and front right speakers.
+    /// The other extra channels are silent.
+    FrontOnly,
+    /// A mono sound plays in the center at the same loudness: on the center channel of layouts
+    /// with at least three channels, and on both channels at -3 dB in stereo. The other extra
+    /// channels are silent.
+    Pan,
+}
+
+/// Internal function that builds a `ForceChannels` object.
+///
+/// # Panic
+///
+/// Panics if `channels` is 0.
+pub fn force_channels<I>(input: I, channels: u16, mode: UpmixMode) -> ForceChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(channels >= 1, "at least one channel is required");
+
+    ForceChannels {
+        input,
+        channels,
+        mode,
+        frame: Vec::with_capacity(channels as usize),
+        position: 0,
+    }
+}
+
+/// Filter that converts a source to a given number of channels.
+///
+/// Extra channels are filled as `UpmixMode` tells. When the source has more channels than
+/// requested, the extra ones are dropped.
+#[derive(Clone, Debug)]
+pub struct ForceChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    channels: u16,
+    mode: UpmixMode,
+    // The current output frame.
+    frame: Vec<I::Item>,
+    // Position of the next output sample inside `frame`.
+    position: usize,
+}
+
+impl<I> ForceChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+/// Fills `output` with a frame of `channels` channels built from `input`.
+fn upmix<S>(input: &[S], channels: usize, mode: UpmixMode, output: &mut Vec<S>)
+where
+    S: Sample,
+{
+    output.clear();
+    let kept = input.len().min(channels);
+    output.extend_from_slice(&input[..kept]);
+
+    let last = input[input.len() - 1];
+    match (mode, input.len()) {
+        (UpmixMode::Duplicate, _) => output.resize(channels, last),
+        (UpmixMode::FrontOnly, 1) => {
+            output.resize(channels.min(2), last);
+            output.resize(channels, S::zero_value());
+        }
+        (UpmixMode::Pan, 1) if channels == 2 => {
+            let value = last.amplify(FRAC_1_SQRT_2);
+            output.clear();
+            output.extend_from_slice(&[value, value]);
+        }
+        (UpmixMode::Pan, 1) if channels >= 3 => {
+            output.clear();
+            output.resize(channels, S::zero_value());
+            output[2] = last;
+        }
+        _ => output.resize(channels, S::zero_value()),
+    }
+}
+
+impl<I> Iterator for ForceChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.position < self.frame.len() {
+            let sample = self.frame[self.position];
+            self.position += 1;
+            return Some(sample);
+        }
+
+        let channels = self.input.channels();
+        if channels == self.channels {
+            return self.input.next();
+        }
+
+        let input: Vec<I::Item> = self.input.by_ref().take(channels as usize).collect();
+        if input.len() < channels as usize {
+            // Incomplete frames can't be converted, so they are dropped.
+            return None;
+        }
+
+        let mut frame = std::mem::take(&mut self.frame);
+        upmix(&input, self.channels as usize, self.mode, &mut frame);
+        self.frame = frame;
+        self.position = 1;
+        Some(self.frame[0])
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.frame.len() - self.position;
+        let channels = self.input.channels() as usize;
+        let (min, max) = self.input.size_hint();
+        let scale = |n: usize| n / channels.max(1) * self.channels as usize + buffered;
+        (scale(min), max.map(scale))
+    }
+}
+
+impl<I> Source for ForceChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let channels = self.input.channels() as usize;
+        let buffered = self.frame.len() - self.position;
+        self.input
+            .current_frame_len()
+            .map(|len| len / channels.max(1) * self.channels as usize + buffered)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UpmixMode;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn mono() -> SamplesBuffer<f32> {
+        SamplesBuffer::new(1, 1000, vec![0.5, -0.5])
+    }
+
+    #[test]
+    fn duplicate() {
+        let source = mono().force_channels(2, UpmixMode::Duplicate);
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.collect::<Vec<_>>(), [0.5, 0.5, -0.5, -0.5]);
+
+        let source = SamplesBuffer::new(2, 1000, vec![1.0f32, 2.0]);
+        let output: Vec<f32> = source.force_channels(3, UpmixMode::default()).collect();
+        assert_eq!(output, [1.0, 2.0, 2.0]);
+    }
+
+    #[test]
+    fn front_only() {
+        let output: Vec<f32> = mono().force_channels(6, UpmixMode::FrontOnly).collect();
+        assert_eq!(
+            output,
+            [0.5, 0.5, 0.0, 0.0, 0.0, 0.0, -0.5, -0.5, 0.0, 0.0, 0.0, 0.0]
+        );
+    }
+
+    #[test]
+    fn pan() {
+        let output: Vec<f32> = mono().force_channels(6, UpmixMode::Pan).collect();
+        assert_eq!(output[..6], [0.0, 0.0, 0.5, 0.0, 0.0, 0.0]);
+
+        let output: Vec<f32> = mono().force_channels(2, UpmixMode::Pan).collect();
+        assert!((output[0] - 0.5 * std::f32::consts::FRAC_1_SQRT_2).abs() < 1e-6);
+        assert_eq!(output[0], output[1]);
+    }
+
+    #[test]
+    fn drops_channels() {
+        let source = SamplesBuffer::new(3, 1000, vec![1i16, 2, 3, 4, 5, 6]);
+        let output: Vec<i16> = source.force_channels(2, UpmixMode::Pan).collect();
+        assert_eq!(output, [1, 2, 4, 5]);
+    }
+}
@@ -0,0 +1,187 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `GainEnvelope` object.
+///
+/// # Panic
+///
+/// Panics if `points` is empty.
+pub fn gain_envelope<I>(input: I, points: &[(Duration, f32)]) -> GainEnvelope<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(!points.is_empty(), "at least one breakpoint is required");
+
+    let mut points = points.to_vec();
+    points.sort_by_key(|&(time, _)| time);
+
+    GainEnvelope {
+        input,
+        points,
+        next_point: 0,
+        frame: 0,
+        current_channel: 0,
+        gain: 0.0,
+    }
+}
+
+/// Filter that applies a gain that varies linearly between breakpoints.
+///
+/// Before the first breakpoint the gain is the level of the first one, and after the last
+/// breakpoint it stays at the level of the last one.
+#[derive(Clone, Debug)]
+pub struct GainEnvelope<I> {
+    input: I,
+    // The breakpoints, sorted by time.
+    points: Vec<(Duration, f32)>,
+    // Index of the first breakpoint after the current frame.
+    next_point: usize,
+    // Index of the current frame from the start of the source.
+    frame: u64,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+    // Gain of the current frame.
+    gain: f32,
+}
+
+impl<I> GainEnvelope<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    /// Computes the gain of the current frame.
+    fn update_gain(&mut self) {
+        let time = self.frame as f64 / self.input.sample_rate() as f64;
+        while self.next_point < self.points.len()
+            && self.points[self.next_point].0.as_secs_f64() <= time
+        {
+            self.next_point += 1;
+        }
+
+        self.gain = if self.next_point == 0 {
+            self.points[0].1
+        } else if self.next_point == self.points.len() {
+            self.points[self.points.len() - 1].1
+        } else {
+            let (start, from) = self.points[self.next_point - 1];
+            let (end, to) = self.points[self.next_point];
+            let start = start.as_secs_f64();
+            let progress = (time - start) / (end.as_secs_f64() - start);
+            from + (to - from) * progress as f32
+        };
+    }
+}
+
+impl<I> Iterator for GainEnvelope<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.current_channel == 0 {
+            self.update_gain();
+        }
+
+        let sample = self.input.next()?;
+
+        self.current_channel += 1;
+        if self.current_channel >= self.input.channels() {
+            self.current_channel = 0;
+            self.frame += 1;
+        }
+
+        Some(sample.amplify(self.gain))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for GainEnvelope<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for GainEnvelope<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn gain_at_breakpoints() {
+        let points = [
+            (Duration::from_millis(100), 0.5),
+            (Duration::from_millis(300), 1.0),
+            (Duration::from_millis(400), 0.0),
+        ];
+        let input = SamplesBuffer::new(2, 1000, vec![1.0f32; 2 * 600]);
+        let output: Vec<f32> = input.gain_envelope(&points).collect();
+        assert_eq!(output.len(), 2 * 600);
+
+        for &(time, level) in &points {
+            let frame = time.as_millis() as usize;
+            assert!((output[2 * frame] - level).abs() < 1e-6);
+            assert!((output[2 * frame + 1] - level).abs() < 1e-6);
+        }
+        // Halfway between two breakpoints.
+        assert!((output[2 * 200] - 0.75).abs() < 1e-6);
+        // Before the first and after the last breakpoint.
+        assert_eq!(output[0], 0.5);
+        assert_eq!(output[2 * 500], 0.0);
+    }
+}
@@ -0,0 +1,200 @@
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `GatePlayback` object.
+pub fn gate_playback<I>(input: I, threshold_db: f32, hold: Duration) -> GatePlayback<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let hold_frames = (hold.as_secs_f64() * input.sample_rate() as f64).round() as u64;
+
+    GatePlayback {
+        input,
+        threshold: 10f32.powf(threshold_db / 20.0),
+        hold_frames,
+        // The quiet parts at the beginning are skipped as well.
+        quiet_frames: hold_frames,
+        frame: Vec::new(),
+        position: 0,
+    }
+}
+
+/// A source that only plays the parts of its inner source that are louder than a threshold,
+/// and skips the others.
+///
+/// A frame is loud if any of its samples reaches the threshold. The quiet frames that follow a
+/// loud one are still played for a while, so that the sound isn't cut between waves or during a
+/// short decay.
+#[derive(Clone, Debug)]
+pub struct GatePlayback<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    // Linear amplitude below which a sample is considered quiet.
+    threshold: f32,
+    hold_frames: u64,
+    // Number of consecutive quiet frames read so far.
+    quiet_frames: u64,
+    // The current frame.
+    frame: Vec<I::Item>,
+    // Position of the next sample inside `frame`.
+    position: usize,
+}
+
+impl<I> GatePlayback<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for GatePlayback<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        while self.position >= self.frame.len() {
+            let channels = self.input.channels() as usize;
+            self.frame.clear();
+            self.frame.extend(self.input.by_ref().take(channels.max(1)));
+            self.position = 0;
+            if self.frame.is_empty() {
+                return None;
+            }
+
+            let loud = self
+                .frame
+                .iter()
+                .any(|sample| sample.to_f32().abs() >= self.threshold);
+            if loud {
+                self.quiet_frames = 0;
+            } else {
+                self.quiet_frames = self.quiet_frames.saturating_add(1);
+                if self.quiet_frames > self.hold_frames {
+                    self.frame.clear();
+                }
+            }
+        }
+
+        let sample = self.frame[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.frame.len() - self.position;
+        (buffered, self.input.size_hint().1.map(|max| max + buffered))
+    }
+}
+
+impl<I> Source for GatePlayback<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        // The frames of the inner source can be skipped entirely, so only the rest of the
+        // current frame is known.
+        let buffered = self.frame.len() - self.position;
+        if buffered > 0 {
+            Some(buffered)
+        } else {
+            self.input
+                .current_frame_len()
+                .map(|len| len.min(self.input.channels() as usize))
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        self.input.is_infinite()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    /// A square wave at half the sample rate.
+    fn tone(amplitude: f32, frames: usize) -> Vec<f32> {
+        (0..frames)
+            .flat_map(|n| {
+                let value = if n % 2 == 0 { amplitude } else { -amplitude };
+                [value, value]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tones_back_to_back() {
+        let mut data = vec![0.0f32; 2 * 300];
+        data.extend(tone(0.5, 800));
+        data.extend(vec![0.001f32; 2 * 4000]);
+        data.extend(tone(0.25, 800));
+        data.extend(vec![0.0f32; 2 * 300]);
+
+        let output: Vec<f32> = SamplesBuffer::new(2, 8000, data)
+            .gate_playback(-30.0, Duration::from_millis(5))
+            .collect();
+
+        // Each tone is followed by 5 ms of the quiet part.
+        let mut expected = tone(0.5, 800);
+        expected.extend(vec![0.001f32; 2 * 40]);
+        expected.extend(tone(0.25, 800));
+        expected.extend(vec![0.0f32; 2 * 40]);
+        assert_eq!(output, expected);
+    }
+
+    #[test]
+    fn silence_is_skipped() {
+        let source = SamplesBuffer::new(1, 1000, vec![0i16; 1000]);
+        assert_eq!(source.gate_playback(-60.0, Duration::ZERO).count(), 0);
+    }
+}
@@ -0,0 +1,239 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// One of the two channels of a stereo sound.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Channel {
+    /// The first channel.
+    Left,
+    /// The second channel.
+    Right,
+}
+
+impl Channel {
+    #[inline]
+    fn index(self) -> u16 {
+        match self {
+            Channel::Left => 0,
+            Channel::Right => 1,
+        }
+    }
+}
+
+/// Internal function that builds a `Haas` object.
+pub fn haas<I>(input: I, delay: Duration, side: Channel) -> Haas<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let frames = (delay.as_secs_f64() * input.sample_rate() as f64).round() as usize;
+
+    Haas {
+        mono: input.channels() == 1,
+        input,
+        side,
+        delay_line: vec![I::Item::zero_value(); frames].into(),
+        frames,
+        current_channel: 0,
+        mono_sample: None,
+        tail: frames,
+        ended: false,
+    }
+}
+
+/// Filter that delays one channel of a sound by a few milliseconds.
+///
+/// The ear locates a sound on the side that it reaches first, and merges a copy that comes
+/// shortly after into a single wider sound. This is the Haas effect. Mono sources are turned
+/// into stereo ones first. The end of the delayed channel is played after the end of the inner
+/// source, so that the sound is a bit longer.
+pub struct Haas<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    side: Channel,
+    // The last samples of the delayed channel, oldest first.
+    delay_line: VecDeque<I::Item>,
+    // Number of frames of the delay.
+    frames: usize,
+    // True if the inner source has a single channel, which is played on both.
+    mono: bool,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+    // The current sample of a mono source, once the left channel has been returned.
+    mono_sample: Option<I::Item>,
+    // Number of frames left to play once the inner source has ended.
+    tail: usize,
+    ended: bool,
+}
+
+impl<I> Haas<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Haas<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let channel = self.current_channel;
+        if channel == 0 && self.ended {
+            if self.tail == 0 {
+                return None;
+            }
+            self.tail -= 1;
+        }
+
+        let sample = if self.ended {
+            I::Item::zero_value()
+        } else if let Some(sample) = self.mono_sample.take() {
+            sample
+        } else {
+            match self.input.next() {
+                Some(sample) => {
+                    if self.mono {
+                        self.mono_sample = Some(sample);
+                    }
+                    sample
+                }
+                None if channel == 0 => {
+                    self.ended = true;
+                    return self.next();
+                }
+                None => return None,
+            }
+        };
+
+        let sample = if channel == self.side.index() && self.frames > 0 {
+            self.delay_line.push_back(sample);
+            self.delay_line.pop_front().unwrap()
+        } else {
+            sample
+        };
+
+        self.current_channel += 1;
+        if self.current_channel >= self.channels() {
+            self.current_channel = 0;
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let channels = self.channels() as usize;
+        let extra = self.tail * channels + self.mono_sample.is_some() as usize;
+        if self.ended {
+            return (extra, Some(extra));
+        }
+        let (min, max) = self.input.size_hint();
+        let factor = if self.mono { 2 } else { 1 };
+        (
+            min.saturating_mul(factor).saturating_add(extra),
+            max.and_then(|max| max.checked_mul(factor)?.checked_add(extra)),
+        )
+    }
+}
+
+impl<I> Source for Haas<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.ended {
+            return Some(self.tail * self.channels() as usize);
+        }
+        let pending = self.mono_sample.is_some() as usize;
+        match self.input.current_frame_len() {
+            // The tail is played after the inner source.
+            Some(0) => Some(self.tail * self.channels() as usize + pending),
+            Some(len) if self.mono => Some(len * 2 + pending),
+            len => len,
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        if self.mono {
+            2
+        } else {
+            self.input.channels()
+        }
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let delay = Duration::from_secs_f64(self.frames as f64 / self.sample_rate() as f64);
+        self.input.total_duration().map(|duration| duration + delay)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::Channel;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn delays_one_channel() {
+        let data: Vec<i16> = (1..=20).flat_map(|n| [n, -n]).collect();
+        let source =
+            SamplesBuffer::new(2, 1000, data).haas(Duration::from_millis(3), Channel::Right);
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(23)));
+
+        let output: Vec<i16> = source.collect();
+        assert_eq!(output.len(), 46);
+        let left: Vec<i16> = output.iter().step_by(2).copied().collect();
+        let right: Vec<i16> = output.iter().skip(1).step_by(2).copied().collect();
+
+        assert_eq!(left[..20], (1..=20).collect::<Vec<_>>()[..]);
+        assert_eq!(left[20..], [0, 0, 0]);
+        assert_eq!(right[..3], [0, 0, 0]);
+        assert_eq!(right[3..], (1..=20).map(|n| -n).collect::<Vec<_>>()[..]);
+    }
+
+    #[test]
+    fn mono_becomes_stereo() {
+        let source = SamplesBuffer::new(1, 1000, vec![1i16, 2, 3]);
+        let source = source.haas(Duration::from_millis(1), Channel::Left);
+        assert_eq!(source.channels(), 2);
+        assert_eq!(source.collect::<Vec<_>>(), [0, 1, 1, 2, 2, 3, 3, 0]);
+    }
+}
@@ -0,0 +1,164 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds an `Invert` object that inverts all channels.
+pub fn invert<I>(input: I) -> Invert<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    Invert {
+        input,
+        channels: None,
+        current_channel: 0,
+    }
+}
+
+/// Internal function that builds an `Invert` object that only inverts some channels.
+///
+/// # Panic
+///
+/// Panics if the length of `channels` is different from the number of channels of the source.
+pub fn invert_channels<I>(input: I, channels: &[bool]) -> Invert<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert_eq!(
+        channels.len(),
+        input.channels() as usize,
+        "one flag per channel is required"
+    );
+
+    Invert {
+        input,
+        channels: Some(channels.to_vec()),
+        current_channel: 0,
+    }
+}
+
+/// Filter that flips the polarity of the sound.
+#[derive(Clone, Debug)]
+pub struct Invert<I> {
+    input: I,
+    // Whether each channel is inverted. `None` means that all of them are.
+    channels: Option<Vec<bool>>,
+    // Channel of the next sample returned by the iterator.
+    current_channel: usize,
+}
+
+impl<I> Invert<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Invert<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let inverted = match &self.channels {
+            Some(channels) => {
+                let inverted = channels[self.current_channel];
+                self.current_channel = (self.current_channel + 1) % channels.len();
+                inverted
+            }
+            None => true,
+        };
+
+        // `amplify` saturates, so the minimum value of integer samples doesn't overflow.
+        self.input
+            .next()
+            .map(|value| if inverted { value.amplify(-1.0) } else { value })
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Invert<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Invert<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn sum_with_inverted_is_silence() {
+        let source =
+            SamplesBuffer::new(2, 44100, vec![10i16, -20, 300, 32767, -32767, 0]).buffered();
+        let output: Vec<i16> = source.clone().mix(source.phase_invert()).collect();
+        assert_eq!(output, vec![0; 6]);
+    }
+
+    #[test]
+    fn min_value_does_not_overflow() {
+        let source = SamplesBuffer::new(1, 44100, vec![i16::MIN]);
+        let output: Vec<i16> = source.phase_invert().collect();
+        assert_eq!(output, vec![i16::MAX]);
+    }
+
+    #[test]
+    fn invert_single_channel() {
+        let source = SamplesBuffer::new(2, 44100, vec![1.0f32, 1.0, -0.5, -0.5]);
+        let output: Vec<f32> = source.phase_invert_channels(&[false, true]).collect();
+        assert_eq!(output, vec![1.0, -1.0, -0.5, 0.5]);
+    }
+}
@@ -0,0 +1,304 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::envelope::time_constant;
+use crate::{Sample, Source};
+
+/// How long the gain takes to recover after a peak.
+const RELEASE: Duration = Duration::from_millis(100);
+
+/// A lookahead brickwall limiter.
+///
+/// Samples are delayed by the lookahead duration, which lets the gain go down before a peak is
+/// played so that the output never exceeds the ceiling. The gain ramps down linearly across the
+/// lookahead and reaches the required value at the peak, so that limiting doesn't click. It then
+/// recovers over 100 ms. While no ceiling is set the samples are passed through without any
+/// delay.
+#[derive(Clone, Debug)]
+pub struct Limiter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    ceiling: Option<f32>,
+    // Number of samples that are delayed while limiting.
+    lookahead: usize,
+    release_coeff: f32,
+    // Samples waiting to be played.
+    delayed: VecDeque<I::Item>,
+    // Increasing gains required by the last `lookahead + 1` samples read, along with the index
+    // of the sample. The front is the smallest of these gains.
+    required_gains: VecDeque<(u64, f32)>,
+    // The smallest required gain of each of the last `lookahead + 1` windows of samples. Their
+    // average is the gain of the next sample played, which ramps the gain down linearly and
+    // reaches the gain required by a peak when it is played.
+    window_gains: VecDeque<f32>,
+    window_sum: f64,
+    // Index of the next required gain pushed, which is ahead of the samples read once the
+    // input has ended.
+    read_index: u64,
+    // Index of the next sample played.
+    play_index: u64,
+    // True until the limiter state is rebuilt, after the limiter has been disabled.
+    needs_reset: bool,
+    gain: f32,
+}
+
+impl<I> Limiter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Builds a new limiter. `ceiling` is a linear amplitude; `None` disables the limiter.
+    pub fn new(input: I, ceiling: Option<f32>, lookahead: Duration) -> Limiter<I> {
+        let samples_per_second = input.sample_rate() as f32 * input.channels() as f32;
+        let lookahead = (lookahead.as_secs_f32() * input.sample_rate() as f32) as usize
+            * input.channels() as usize;
+
+        Limiter {
+            input,
+            ceiling,
+            lookahead: lookahead.max(1),
+            release_coeff: time_constant(RELEASE, samples_per_second),
+            delayed: VecDeque::with_capacity(lookahead + 1),
+            required_gains: VecDeque::new(),
+            window_gains: VecDeque::with_capacity(lookahead + 2),
+            window_sum: 0.0,
+            read_index: 0,
+            play_index: 0,
+            needs_reset: true,
+            gain: 1.0,
+        }
+    }
+
+    /// Sets the maximum amplitude of the output. `None` disables the limiter.
+    #[inline]
+    pub fn set_ceiling(&mut self, ceiling: Option<f32>) {
+        self.ceiling = ceiling;
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    /// Forgets the gains required by the samples read so far, as if none of them was above the
+    /// ceiling.
+    fn reset(&mut self) {
+        let window = self.lookahead + 1;
+        self.required_gains.clear();
+        self.window_gains.clear();
+        self.window_gains.extend(std::iter::repeat_n(1.0, window));
+        self.window_sum = window as f64;
+        self.read_index = self.play_index + self.delayed.len() as u64;
+    }
+
+    /// Reads a sample from the input and keeps track of the gain it requires.
+    fn read_sample(&mut self, ceiling: f32) -> bool {
+        let sample = match self.input.next() {
+            Some(sample) => sample,
+            None => return false,
+        };
+
+        let level = sample.to_f32().abs();
+        let required = if level > ceiling {
+            ceiling / level
+        } else {
+            1.0
+        };
+        self.push_required(required);
+        self.delayed.push_back(sample);
+        true
+    }
+
+    /// Keeps track of the gain required by the next sample.
+    fn push_required(&mut self, required: f32) {
+        let window = self.lookahead as u64 + 1;
+
+        // Smallest required gain of the last `window` samples.
+        while let Some(&(_, gain)) = self.required_gains.back() {
+            if gain >= required {
+                self.required_gains.pop_back();
+            } else {
+                break;
+            }
+        }
+        self.required_gains.push_back((self.read_index, required));
+        while let Some(&(i, _)) = self.required_gains.front() {
+            if i + window <= self.read_index {
+                self.required_gains.pop_front();
+            } else {
+                break;
+            }
+        }
+        let min = self.required_gains.front().map_or(1.0, |&(_, gain)| gain);
+
+        // Average of the last `window` minimums.
+        self.window_gains.push_back(min);
+        self.window_sum += min as f64;
+        if let Some(old) = self.window_gains.pop_front() {
+            self.window_sum -= old as f64;
+        }
+        self.read_index += 1;
+    }
+}
+
+impl<I> Iterator for Limiter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let ceiling = match self.ceiling {
+            Some(ceiling) => ceiling,
+            None => {
+                // Play what is left from when the limiter was enabled.
+                self.needs_reset = true;
+                return match self.delayed.pop_front() {
+                    Some(sample) => {
+                        self.play_index += 1;
+                        Some(sample)
+                    }
+                    None => self.input.next(),
+                };
+            }
+        };
+
+        if self.needs_reset {
+            self.reset();
+            self.needs_reset = false;
+        }
+
+        while self.delayed.len() <= self.lookahead {
+            if !self.read_sample(ceiling) {
+                break;
+            }
+        }
+        let sample = self.delayed.pop_front()?;
+
+        // Once the input has ended, the samples after it are taken as silent.
+        while self.read_index <= self.play_index + self.lookahead as u64 {
+            self.push_required(1.0);
+        }
+        self.play_index += 1;
+
+        // The averaged gain is low enough for every sample up to the end of the lookahead, and
+        // never changes by more than `1 / (lookahead + 1)` from one sample to the next.
+        let window = self.lookahead as f64 + 1.0;
+        let target = (self.window_sum / window) as f32;
+        if target < self.gain {
+            self.gain = target;
+        } else {
+            self.gain = target + (self.gain - target) * self.release_coeff;
+        }
+
+        let sample = sample.amplify(self.gain);
+        let value = sample.to_f32();
+        if value.abs() > ceiling {
+            // Rounding errors can leave the sample slightly above the ceiling.
+            Some(CpalSample::from(&ceiling.copysign(value)))
+        } else {
+            Some(sample)
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (min, max) = self.input.size_hint();
+        let delayed = self.delayed.len();
+        (min + delayed, max.map(|max| max + delayed))
+    }
+}
+
+impl<I> Source for Limiter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input
+            .current_frame_len()
+            .map(|val| val + self.delayed.len())
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Limiter;
+
+    #[test]
+    fn stays_below_ceiling() {
+        let data: Vec<f32> = (0..1000)
+            .map(|i| if i % 100 == 50 { 3.0 } else { 0.5 })
+            .collect();
+        let input = SamplesBuffer::new(2, 1000, data);
+        let output: Vec<f32> = Limiter::new(input, Some(0.8), Duration::from_millis(5)).collect();
+
+        assert_eq!(output.len(), 1000);
+        assert!(output.iter().all(|s| s.abs() <= 0.8));
+    }
+
+    #[test]
+    fn gain_ramps_down() {
+        let mut data = vec![0.5f32; 1000];
+        data.extend(vec![2.0f32; 10]);
+        data.extend(vec![0.5f32; 1000]);
+        let input = SamplesBuffer::new(1, 48000, data.clone());
+        // 5 ms at 48 kHz is 240 samples.
+        let output: Vec<f32> = Limiter::new(input, Some(0.8), Duration::from_millis(5)).collect();
+        assert_eq!(output.len(), data.len());
+
+        let gains: Vec<f32> = output.iter().zip(&data).map(|(o, i)| o / i).collect();
+        assert!(gains.iter().all(|&gain| gain <= 1.0));
+        assert!((gains[1000] - 0.4).abs() < 1e-4);
+        // Going from 1.0 to 0.4 across the lookahead.
+        let max_step = 0.6 / 241.0 + 1e-5;
+        assert!(gains.windows(2).all(|w| (w[1] - w[0]).abs() <= max_step));
+        assert!(gains[1000 - 240] < 1.0);
+    }
+
+    #[test]
+    fn disabled_is_passthrough() {
+        let input = SamplesBuffer::new(1, 1000, vec![0.5f32, 2.0, -3.0]);
+        let output: Vec<f32> = Limiter::new(input, None, Duration::from_millis(5)).collect();
+        assert_eq!(output, vec![0.5, 2.0, -3.0]);
+    }
+}
@@ -0,0 +1,179 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `LoopRegion` object.
+///
+/// # Panic
+///
+/// Panics if `start` isn't before `end`.
+pub fn loop_region<I>(
+    input: I,
+    start: Duration,
+    end: Duration,
+    fade: Duration,
+) -> LoopRegion<I::Item>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(start < end, "the loop must start before it ends");
+
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let to_samples = |time: Duration| {
+        (time.as_secs_f64() * sample_rate as f64).round() as usize * channels as usize
+    };
+
+    let data: Vec<I::Item> = input.take(to_samples(end)).collect();
+    let end = data.len();
+    let start = to_samples(start).min(end);
+    // The crossfade uses the samples just before the start of the loop.
+    let fade = to_samples(fade).min(start).min(end - start);
+
+    LoopRegion {
+        data,
+        channels,
+        sample_rate,
+        start,
+        end,
+        fade,
+        position: 0,
+    }
+}
+
+/// A source that plays the beginning of a sound, then loops forever over a region of it.
+///
+/// The sound is stored in memory up to the end of the loop; the rest is never read. When a
+/// crossfade is set, the end of the loop is crossfaded with the samples that come just before its
+/// start, so that jumping back is seamless.
+#[derive(Clone, Debug)]
+pub struct LoopRegion<S> {
+    data: Vec<S>,
+    channels: u16,
+    sample_rate: u32,
+    // Index of the first and after the last sample of the loop.
+    start: usize,
+    end: usize,
+    // Number of samples of the crossfade.
+    fade: usize,
+    // Index of the next sample.
+    position: usize,
+}
+
+impl<S> LoopRegion<S> {
+    /// Returns true if the loop has been entered.
+    #[inline]
+    pub fn is_looping(&self) -> bool {
+        self.position >= self.start
+    }
+}
+
+impl<S> Iterator for LoopRegion<S>
+where
+    S: Sample,
+{
+    type Item = S;
+
+    #[inline]
+    fn next(&mut self) -> Option<S> {
+        if self.start >= self.end {
+            return None;
+        }
+
+        let fade_start = self.end - self.fade;
+        let sample = if self.position >= fade_start {
+            let offset = self.position - fade_start;
+            let channels = self.channels as usize;
+            let factor = (offset / channels + 1) as f32 / (self.fade / channels) as f32;
+            let fading_in = self.data[self.start - self.fade + offset];
+            self.data[self.position]
+                .amplify(1.0 - factor)
+                .saturating_add(fading_in.amplify(factor))
+        } else {
+            self.data[self.position]
+        };
+
+        self.position += 1;
+        if self.position >= self.end {
+            self.position = self.start;
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.start >= self.end {
+            (0, Some(0))
+        } else {
+            (usize::MAX, None)
+        }
+    }
+}
+
+impl<S> Source for LoopRegion<S>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::source::{CountingSource, Source};
+
+    fn ramp() -> CountingSource {
+        CountingSource::new(1, 100, Duration::from_secs(10))
+    }
+
+    #[test]
+    fn jumps_back_at_loop_end() {
+        let mut source = ramp().loop_region(Duration::from_secs(2), Duration::from_secs(3));
+        let output: Vec<f32> = source.by_ref().take(500).collect();
+
+        assert_eq!(
+            output[..300],
+            (0..300).map(|i| i as f32).collect::<Vec<_>>()[..]
+        );
+        assert_eq!(output[300], 200.0);
+        assert_eq!(output[399], 299.0);
+        assert_eq!(output[400], 200.0);
+        assert!(source.is_looping());
+    }
+
+    #[test]
+    fn crossfade_at_seam() {
+        let source = ramp().loop_region_with_crossfade(
+            Duration::from_secs(2),
+            Duration::from_secs(3),
+            Duration::from_millis(100),
+        );
+        let output: Vec<f32> = source.take(400).collect();
+
+        assert_eq!(output[289], 289.0);
+        // The end of the loop fades into the samples just before its start.
+        assert!((output[290] - (290.0 * 0.9 + 190.0 * 0.1)).abs() < 1e-3);
+        assert!((output[299] - 199.0).abs() < 1e-3);
+        assert_eq!(output[300], 200.0);
+    }
+}
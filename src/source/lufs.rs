@@ -0,0 +1,407 @@
+use std::collections::VecDeque;
+use std::f64::consts::PI;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::biquad::{Biquad, Coefficients};
+use crate::{Sample, Source};
+
+/// Number of 100 ms sub-blocks in the momentary window.
+const MOMENTARY_BLOCKS: usize = 4;
+
+/// Number of 100 ms sub-blocks in the short-term window.
+const SHORT_TERM_BLOCKS: usize = 30;
+
+/// Blocks quieter than this are ignored by the integrated loudness.
+const ABSOLUTE_GATE: f64 = -70.0;
+
+/// Blocks quieter than the ungated integrated loudness minus this are ignored as well.
+const RELATIVE_GATE: f64 = 10.0;
+
+/// Internal function that builds a `LufsMeter` object.
+pub fn lufs_meter<I>(input: I) -> LufsMeter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+
+    LufsMeter {
+        input,
+        handle: LufsHandle {
+            state: Arc::new(Mutex::new(State {
+                momentary: 0.0,
+                short_term: 0.0,
+                blocks: Vec::new(),
+            })),
+        },
+        filters: (0..channels)
+            .map(|_| KWeighting::new(sample_rate))
+            .collect(),
+        sample_rate,
+        sub_blocks: VecDeque::with_capacity(SHORT_TERM_BLOCKS + 1),
+        sub_block_sum: 0.0,
+        sub_block_frames: 0,
+        frame_sum: 0.0,
+        current_channel: 0,
+    }
+}
+
+/// Returns the loudness of a weighted mean square, in LUFS.
+fn loudness(energy: f64) -> f32 {
+    (-0.691 + 10.0 * energy.log10()) as f32
+}
+
+/// The energy of a weighted mean square with the given loudness.
+fn energy(loudness: f64) -> f64 {
+    10f64.powf((loudness + 0.691) / 10.0)
+}
+
+/// Weight of each channel, following the usual order of 5.1 and 7.1 layouts.
+fn channel_weight(channel: u16, channels: u16) -> f64 {
+    match (channels, channel) {
+        // The LFE channel is ignored.
+        (6, 3) | (8, 3) => 0.0,
+        // Surround channels.
+        (6, 4..=5) | (8, 4..=7) => 1.41,
+        _ => 1.0,
+    }
+}
+
+/// The K-weighting filter of BS.1770: a high shelf followed by a high-pass filter.
+#[derive(Clone, Debug)]
+struct KWeighting {
+    shelf: Biquad<f64>,
+    high_pass: Biquad<f64>,
+}
+
+impl KWeighting {
+    fn new(sample_rate: u32) -> KWeighting {
+        let rate = sample_rate as f64;
+
+        let (f0, gain, q) = (1681.974450955533, 3.999843853973347, 0.7071752369554196);
+        let k = (PI * f0 / rate).tan();
+        let vh = 10f64.powf(gain / 20.0);
+        let vb = vh.powf(0.4996667741545416);
+        let a0 = 1.0 + k / q + k * k;
+        let shelf = Biquad::new(Coefficients::from_normalized(
+            [
+                (vh + vb * k / q + k * k) / a0,
+                2.0 * (k * k - vh) / a0,
+                (vh - vb * k / q + k * k) / a0,
+            ],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ));
+
+        let (f0, q) = (38.13547087602444, 0.5003270373238773);
+        let k = (PI * f0 / rate).tan();
+        let a0 = 1.0 + k / q + k * k;
+        let high_pass = Biquad::new(Coefficients::from_normalized(
+            [1.0, -2.0, 1.0],
+            [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        ));
+
+        KWeighting { shelf, high_pass }
+    }
+
+    #[inline]
+    fn process(&mut self, x: f64) -> f64 {
+        self.high_pass.process(self.shelf.process(x))
+    }
+}
+
+struct State {
+    // Weighted mean squares of the momentary and short-term windows.
+    momentary: f64,
+    short_term: f64,
+    // Weighted mean square of each 400 ms block, every 100 ms.
+    blocks: Vec<f64>,
+}
+
+/// Gives access to the loudness measured by a `LufsMeter` source.
+///
+/// This can be cloned and sent to another thread. All the values are in LUFS, and are negative
+/// infinity while the sound is silent.
+#[derive(Clone)]
+pub struct LufsHandle {
+    state: Arc<Mutex<State>>,
+}
+
+impl LufsHandle {
+    /// Returns the loudness of the last 400 ms.
+    #[inline]
+    pub fn momentary(&self) -> f32 {
+        loudness(self.state.lock().unwrap().momentary)
+    }
+
+    /// Returns the loudness of the last 3 seconds.
+    #[inline]
+    pub fn short_term(&self) -> f32 {
+        loudness(self.state.lock().unwrap().short_term)
+    }
+
+    /// Returns the loudness of everything played so far, ignoring the silent and the very quiet
+    /// parts.
+    pub fn integrated(&self) -> f32 {
+        let state = self.state.lock().unwrap();
+
+        let mean_above = |threshold: f64| {
+            let (sum, count) = state
+                .blocks
+                .iter()
+                .filter(|&&block| block > threshold)
+                .fold((0.0, 0), |(sum, count), &block| (sum + block, count + 1));
+            if count == 0 {
+                0.0
+            } else {
+                sum / count as f64
+            }
+        };
+
+        let absolute = energy(ABSOLUTE_GATE);
+        let ungated = mean_above(absolute);
+        if ungated == 0.0 {
+            return f32::NEG_INFINITY;
+        }
+        let relative = ungated * 10f64.powf(-RELATIVE_GATE / 10.0);
+        loudness(mean_above(absolute.max(relative)))
+    }
+}
+
+/// A source that measures the loudness of the sound it plays, as defined by ITU-R BS.1770.
+///
+/// The samples themselves are passed through unchanged. The loudness is read with the handle
+/// returned by `handle`.
+pub struct LufsMeter<I> {
+    input: I,
+    handle: LufsHandle,
+    // The K-weighting filter of each channel.
+    filters: Vec<KWeighting>,
+    sample_rate: u32,
+    // Sum of the weighted squares of each of the last 100 ms sub-blocks, and its number of
+    // frames.
+    sub_blocks: VecDeque<(f64, usize)>,
+    sub_block_sum: f64,
+    sub_block_frames: usize,
+    // Sum of the weighted squares of the current frame.
+    frame_sum: f64,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+}
+
+impl<I> LufsMeter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a handle that gives access to the measured loudness.
+    #[inline]
+    pub fn handle(&self) -> LufsHandle {
+        self.handle.clone()
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    /// Adds the current frame to the current sub-block, and updates the loudness when the
+    /// sub-block is complete.
+    fn end_frame(&mut self) {
+        self.sub_block_sum += self.frame_sum;
+        self.sub_block_frames += 1;
+        self.frame_sum = 0.0;
+        if self.sub_block_frames < (self.sample_rate as usize / 10).max(1) {
+            return;
+        }
+
+        self.sub_blocks
+            .push_back((self.sub_block_sum, self.sub_block_frames));
+        if self.sub_blocks.len() > SHORT_TERM_BLOCKS {
+            self.sub_blocks.pop_front();
+        }
+        self.sub_block_sum = 0.0;
+        self.sub_block_frames = 0;
+
+        let mean = |blocks: usize| {
+            let (sum, frames) = self
+                .sub_blocks
+                .iter()
+                .rev()
+                .take(blocks)
+                .fold((0.0, 0), |(sum, frames), &(s, f)| (sum + s, frames + f));
+            sum / frames as f64
+        };
+        let momentary = mean(MOMENTARY_BLOCKS);
+        let short_term = mean(SHORT_TERM_BLOCKS);
+
+        let mut state = self.handle.state.lock().unwrap();
+        state.momentary = momentary;
+        state.short_term = short_term;
+        if self.sub_blocks.len() >= MOMENTARY_BLOCKS {
+            state.blocks.push(momentary);
+        }
+    }
+
+    /// Starts measuring again if the format of the inner source has changed.
+    fn check_format(&mut self) {
+        let channels = self.input.channels();
+        let sample_rate = self.input.sample_rate();
+        if channels as usize != self.filters.len() || sample_rate != self.sample_rate {
+            self.filters = (0..channels)
+                .map(|_| KWeighting::new(sample_rate))
+                .collect();
+            self.sample_rate = sample_rate;
+            self.sub_blocks.clear();
+            self.sub_block_sum = 0.0;
+            self.sub_block_frames = 0;
+        }
+    }
+}
+
+impl<I> Iterator for LufsMeter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.current_channel == 0 {
+            self.check_format();
+        }
+
+        let sample = self.input.next()?;
+
+        let channels = self.filters.len() as u16;
+        let channel = self.current_channel;
+        let filtered = self.filters[channel as usize].process(sample.to_f32() as f64);
+        self.frame_sum += channel_weight(channel, channels) * filtered * filtered;
+
+        self.current_channel += 1;
+        if self.current_channel >= channels {
+            self.current_channel = 0;
+            self.end_frame();
+        }
+
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for LufsMeter<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for LufsMeter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    /// A stereo sine wave at 1 kHz with the same peak level in both channels.
+    fn sine(level_db: f32, seconds: usize) -> SamplesBuffer<f32> {
+        let amplitude = 10f32.powf(level_db / 20.0);
+        let data: Vec<f32> = (0..48000 * seconds)
+            .flat_map(|i| {
+                let value = amplitude * (2.0 * PI * 1000.0 * i as f32 / 48000.0).sin();
+                [value, value]
+            })
+            .collect();
+        SamplesBuffer::new(2, 48000, data)
+    }
+
+    #[test]
+    fn reference_sine() {
+        // A stereo 1 kHz sine at -23 dBFS measures -23 LUFS, as in EBU Tech 3341.
+        let meter = sine(-23.0, 20).lufs_meter();
+        let handle = meter.handle();
+        let output: Vec<f32> = meter.collect();
+        assert_eq!(output.len(), 48000 * 20 * 2);
+
+        assert!(
+            (handle.integrated() + 23.0).abs() < 0.1,
+            "{}",
+            handle.integrated()
+        );
+        assert!((handle.momentary() + 23.0).abs() < 0.1);
+        assert!((handle.short_term() + 23.0).abs() < 0.1);
+    }
+
+    #[test]
+    fn silent_parts_are_gated() {
+        // Ten seconds of sine followed by ten seconds of silence.
+        let mut data: Vec<f32> = sine(-20.0, 10).collect();
+        data.extend(vec![0.0; data.len()]);
+        let meter = SamplesBuffer::new(2, 48000, data).lufs_meter();
+        let handle = meter.handle();
+        meter.for_each(drop);
+
+        assert!(
+            (handle.integrated() + 20.0).abs() < 0.1,
+            "{}",
+            handle.integrated()
+        );
+        assert!(handle.momentary() < -70.0);
+    }
+
+    #[test]
+    fn silence_is_negative_infinity() {
+        let meter = SamplesBuffer::new(1, 48000, vec![0i16; 48000]).lufs_meter();
+        let handle = meter.handle();
+        meter.for_each(drop);
+        assert_eq!(handle.integrated(), f32::NEG_INFINITY);
+        assert_eq!(handle.momentary(), f32::NEG_INFINITY);
+    }
+}
@@ -0,0 +1,240 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `MaxChannels` object.
+///
+/// # Panic
+///
+/// Panics if `max` is 0.
+pub fn max_channels<I>(input: I, max: u16) -> MaxChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(max >= 1, "at least one channel is required");
+
+    MaxChannels {
+        input,
+        max,
+        frame: Vec::new(),
+        position: 0,
+    }
+}
+
+/// Filter that downmixes sources that have more than a given number of channels.
+///
+/// Sources with at most that many channels are passed through untouched.
+#[derive(Clone, Debug)]
+pub struct MaxChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    max: u16,
+    // The current downmixed frame.
+    frame: Vec<I::Item>,
+    // Position of the next output sample inside `frame`.
+    position: usize,
+}
+
+impl<I> MaxChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+/// Downmixes a frame to stereo, assuming the usual order of the channels: front left, front
+/// right, center, LFE, back left, back right, side left, side right.
+fn downmix_to_stereo(input: &[f32]) -> [f32; 2] {
+    let (front, center, back) = match *input {
+        [l, r, c] => ([l, r], c, [0.0, 0.0]),
+        [l, r, bl, br] => ([l, r], 0.0, [bl, br]),
+        [l, r, c, bl, br] => ([l, r], c, [bl, br]),
+        // The LFE channel is dropped.
+        [l, r, c, _, bl, br] => ([l, r], c, [bl, br]),
+        [l, r, c, _, bl, br, sl, sr] => ([l, r], c, [bl + sl, br + sr]),
+        _ => {
+            // Unknown layout: alternate the channels between left and right.
+            let mut output = [0.0, 0.0];
+            for (channel, sample) in input.iter().enumerate() {
+                output[channel % 2] += sample;
+            }
+            return output;
+        }
+    };
+
+    [
+        front[0] + FRAC_1_SQRT_2 * (center + back[0]),
+        front[1] + FRAC_1_SQRT_2 * (center + back[1]),
+    ]
+}
+
+fn downmix(input: &[f32], max: usize, output: &mut Vec<f32>) {
+    output.clear();
+    match max {
+        1 => {
+            let [left, right] = downmix_to_stereo(input);
+            output.push((left + right) / 2.0);
+        }
+        2 => output.extend_from_slice(&downmix_to_stereo(input)),
+        _ => {
+            // Fold the extra channels into the ones that are kept.
+            output.extend_from_slice(&input[..max]);
+            for (channel, sample) in input.iter().enumerate().skip(max) {
+                output[channel % max] += FRAC_1_SQRT_2 * sample;
+            }
+        }
+    }
+}
+
+impl<I> Iterator for MaxChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.position < self.frame.len() {
+            let sample = self.frame[self.position];
+            self.position += 1;
+            return Some(sample);
+        }
+
+        let channels = self.input.channels();
+        if channels <= self.max {
+            return self.input.next();
+        }
+
+        let input: Vec<f32> = self
+            .input
+            .by_ref()
+            .take(channels as usize)
+            .map(|s| s.to_f32())
+            .collect();
+        if input.len() < channels as usize {
+            // Incomplete frames can't be downmixed, so they are dropped.
+            return None;
+        }
+
+        let mut output = Vec::with_capacity(self.max as usize);
+        downmix(&input, self.max as usize, &mut output);
+        self.frame.clear();
+        self.frame
+            .extend(output.iter().map(<I::Item as CpalSample>::from));
+        self.position = 1;
+        Some(self.frame[0])
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.frame.len() - self.position;
+        let channels = self.input.channels();
+        if channels <= self.max {
+            let (min, max) = self.input.size_hint();
+            return (min + buffered, max.map(|max| max + buffered));
+        }
+
+        let (min, max) = self.input.size_hint();
+        let scale = |n: usize| n / channels as usize * self.max as usize + buffered;
+        (scale(min), max.map(scale))
+    }
+}
+
+impl<I> Source for MaxChannels<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let channels = self.input.channels();
+        let buffered = self.frame.len() - self.position;
+        if channels <= self.max {
+            return self.input.current_frame_len().map(|len| len + buffered);
+        }
+        self.input
+            .current_frame_len()
+            .map(|len| len / channels as usize * self.max as usize + buffered)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels().min(self.max)
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::FRAC_1_SQRT_2;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn surround_to_stereo() {
+        // FL, FR, FC, LFE, BL, BR
+        let frame = vec![0.1f32, 0.2, 0.3, 1.0, 0.4, 0.5];
+        let source = SamplesBuffer::new(6, 48000, [frame.clone(), frame].concat()).max_channels(2);
+        assert_eq!(source.channels(), 2);
+
+        let output: Vec<f32> = source.collect();
+        assert_eq!(output.len(), 4);
+        let left = 0.1 + FRAC_1_SQRT_2 * (0.3 + 0.4);
+        let right = 0.2 + FRAC_1_SQRT_2 * (0.3 + 0.5);
+        for (sample, expected) in output.iter().zip(&[left, right, left, right]) {
+            assert!((sample - expected).abs() < 1e-6);
+        }
+    }
+
+    #[test]
+    fn mono_is_untouched() {
+        let data = vec![1i16, -2, 3, i16::MIN];
+        let source = SamplesBuffer::new(1, 48000, data.clone()).max_channels(2);
+        assert_eq!(source.channels(), 1);
+        assert_eq!(source.collect::<Vec<_>>(), data);
+    }
+
+    #[test]
+    fn stereo_to_mono() {
+        let source = SamplesBuffer::new(2, 48000, vec![0.2f32, 0.4]).max_channels(1);
+        assert_eq!(source.channels(), 1);
+        let output: Vec<f32> = source.collect();
+        assert!((output[0] - 0.3).abs() < 1e-6);
+        assert_eq!(output.len(), 1);
+    }
+}
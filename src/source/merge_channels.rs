@@ -0,0 +1,251 @@
+use std::error::Error;
+use std::fmt;
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Interleaves mono sources into a source with one channel per input.
+///
+/// The first source becomes the first channel, and so on. The result ends as soon as one of the
+/// sources ends, so that its last frame is complete. This is the reverse of
+/// `Source::split_channels`.
+///
+/// Returns an error if `sources` is empty, if one of them isn't mono, or if they don't all have
+/// the same sample rate.
+pub fn merge_channels<S>(sources: Vec<S>) -> Result<MergeChannels<S>, MergeChannelsError>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    let sample_rate = match sources.first() {
+        Some(source) => source.sample_rate(),
+        None => return Err(MergeChannelsError::NoSources),
+    };
+    for (index, source) in sources.iter().enumerate() {
+        if source.channels() != 1 {
+            return Err(MergeChannelsError::NotMono {
+                index,
+                channels: source.channels(),
+            });
+        }
+        if source.sample_rate() != sample_rate {
+            return Err(MergeChannelsError::SampleRateMismatch {
+                index,
+                expected: sample_rate,
+                found: source.sample_rate(),
+            });
+        }
+    }
+    if sources.len() > u16::MAX as usize {
+        return Err(MergeChannelsError::TooManySources);
+    }
+
+    Ok(MergeChannels {
+        sources,
+        sample_rate,
+        frame: Vec::new(),
+        position: 0,
+    })
+}
+
+/// Error returned by `merge_channels`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum MergeChannelsError {
+    /// No source was given.
+    NoSources,
+    /// More than `u16::MAX` sources were given.
+    TooManySources,
+    /// A source has more than one channel.
+    NotMono {
+        /// The index of the source.
+        index: usize,
+        /// The number of channels of the source.
+        channels: u16,
+    },
+    /// A source doesn't have the sample rate of the first one.
+    SampleRateMismatch {
+        /// The index of the source.
+        index: usize,
+        /// The sample rate of the first source.
+        expected: u32,
+        /// The sample rate of the source.
+        found: u32,
+    },
+}
+
+impl fmt::Display for MergeChannelsError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MergeChannelsError::NoSources => write!(f, "no source to merge"),
+            MergeChannelsError::TooManySources => write!(f, "too many sources to merge"),
+            MergeChannelsError::NotMono { index, channels } => {
+                write!(f, "source {} has {} channels instead of 1", index, channels)
+            }
+            MergeChannelsError::SampleRateMismatch {
+                index,
+                expected,
+                found,
+            } => write!(
+                f,
+                "source {} has a sample rate of {} instead of {}",
+                index, found, expected
+            ),
+        }
+    }
+}
+
+impl Error for MergeChannelsError {}
+
+/// A source that interleaves mono sources, built with `merge_channels`.
+pub struct MergeChannels<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    sources: Vec<S>,
+    sample_rate: u32,
+    // The current frame, and the position of the next sample to return in it.
+    frame: Vec<S::Item>,
+    position: usize,
+}
+
+impl<S> MergeChannels<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    /// Returns the merged sources.
+    #[inline]
+    pub fn into_inner(self) -> Vec<S> {
+        self.sources
+    }
+}
+
+impl<S> Iterator for MergeChannels<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    type Item = S::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<S::Item> {
+        if self.position >= self.frame.len() {
+            self.frame.clear();
+            for source in self.sources.iter_mut() {
+                self.frame.push(source.next()?);
+            }
+            self.position = 0;
+        }
+
+        self.position += 1;
+        Some(self.frame[self.position - 1])
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.frame.len() - self.position;
+        let channels = self.sources.len();
+        let (min, max) = self.sources.iter().map(|source| source.size_hint()).fold(
+            (usize::MAX, None),
+            |(min, max), (source_min, source_max)| {
+                let max = match (max, source_max) {
+                    (Some(max), Some(source_max)) => Some(usize::min(max, source_max)),
+                    (max, source_max) => max.or(source_max),
+                };
+                (min.min(source_min), max)
+            },
+        );
+        (
+            pending.saturating_add(min.saturating_mul(channels)),
+            max.map(|max| pending.saturating_add(max.saturating_mul(channels))),
+        )
+    }
+}
+
+impl<S> Source for MergeChannels<S>
+where
+    S: Source,
+    S::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.sources.len() as u16
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.sources
+            .iter()
+            .map(|source| source.total_duration())
+            .min()
+            .flatten()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{merge_channels, MergeChannelsError};
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{CountingSource, Source};
+
+    #[test]
+    fn interleaves_ramps() {
+        let left = SamplesBuffer::new(1, 1000, (0..10).collect::<Vec<i16>>());
+        let right = SamplesBuffer::new(1, 1000, (100..105).collect::<Vec<i16>>());
+        let merged = merge_channels(vec![left, right]).unwrap();
+        assert_eq!(merged.channels(), 2);
+        assert_eq!(merged.sample_rate(), 1000);
+        assert_eq!(merged.size_hint(), (10, Some(10)));
+        assert_eq!(
+            merged.collect::<Vec<_>>(),
+            [0, 100, 1, 101, 2, 102, 3, 103, 4, 104]
+        );
+    }
+
+    #[test]
+    fn split_then_merge() {
+        let source = CountingSource::new(3, 1000, Duration::from_millis(100));
+        let data: Vec<f32> = source.clone().collect();
+        let channels = source.split_channels();
+        let merged = merge_channels(channels).unwrap();
+        assert_eq!(merged.channels(), 3);
+        assert_eq!(merged.collect::<Vec<_>>(), data);
+    }
+
+    #[test]
+    fn errors() {
+        let mono = |rate| SamplesBuffer::new(1, rate, vec![0i16; 4]);
+        assert_eq!(
+            merge_channels(Vec::<SamplesBuffer<i16>>::new()).err(),
+            Some(MergeChannelsError::NoSources)
+        );
+        assert_eq!(
+            merge_channels(vec![mono(1000), SamplesBuffer::new(2, 1000, vec![0i16; 4])]).err(),
+            Some(MergeChannelsError::NotMono {
+                index: 1,
+                channels: 2
+            })
+        );
+        assert_eq!(
+            merge_channels(vec![mono(1000), mono(1000), mono(2000)]).err(),
+            Some(MergeChannelsError::SampleRateMismatch {
+                index: 2,
+                expected: 1000,
+                found: 2000
+            })
+        );
+    }
+}
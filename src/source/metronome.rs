@@ -0,0 +1,315 @@
+use std::f32::consts::PI;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Duration of a click.
+const CLICK_DURATION: Duration = Duration::from_millis(20);
+
+/// Frequency of a normal click and of an accented one.
+const CLICK_FREQ: f32 = 1000.0;
+const ACCENT_FREQ: f32 = 1500.0;
+
+/// Peak amplitude of a click.
+const CLICK_AMPLITUDE: f32 = 0.5;
+
+/// Internal function that builds a `WithMetronome` object.
+///
+/// # Panic
+///
+/// Panics if `bpm` isn't strictly positive.
+pub fn with_metronome<I>(input: I, bpm: f32) -> WithMetronome<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let metronome = Metronome::new(input.sample_rate(), bpm);
+    WithMetronome {
+        input,
+        metronome,
+        click: 0.0,
+        current_channel: 0,
+    }
+}
+
+/// An infinite source that produces the clicks of a metronome.
+///
+/// Has one channel. The clicks start exactly every `60 / bpm` seconds, rounded to the nearest
+/// sample, starting with the first sample. The timing is computed from the start of the source,
+/// so that rounding errors don't add up.
+#[derive(Clone, Debug)]
+pub struct Metronome {
+    sample_rate: u32,
+    bpm: f32,
+    // Number of beats in a bar, or 0 if no beat is accented.
+    beats_per_bar: u32,
+    // Index of the next beat, and index of the sample where it starts.
+    beat: u64,
+    next_beat: u64,
+    // Index of the next sample, and of the sample where the current click started.
+    num_sample: u64,
+    click_start: u64,
+    click_freq: f32,
+    click_len: u64,
+    decay: f32,
+}
+
+impl Metronome {
+    /// Builds a metronome that clicks `bpm` times per minute.
+    ///
+    /// # Panic
+    ///
+    /// - Panics if the sample rate is zero.
+    /// - Panics if `bpm` isn't strictly positive.
+    ///
+    pub fn new(sample_rate: u32, bpm: f32) -> Metronome {
+        assert!(sample_rate != 0);
+        assert!(bpm > 0.0, "bpm must be strictly positive");
+
+        let click_len = (CLICK_DURATION.as_secs_f64() * sample_rate as f64) as u64;
+        Metronome {
+            sample_rate,
+            bpm,
+            beats_per_bar: 0,
+            beat: 0,
+            next_beat: 0,
+            num_sample: 0,
+            click_start: 0,
+            click_freq: CLICK_FREQ,
+            click_len,
+            // The click fades to about 1% of its amplitude.
+            decay: 4.6 / click_len.max(1) as f32,
+        }
+    }
+
+    /// Accents the first beat of each bar of `beats_per_bar` beats, by playing it higher.
+    ///
+    /// `0` disables the accents, which is the default.
+    #[inline]
+    pub fn with_time_signature(mut self, beats_per_bar: u32) -> Metronome {
+        self.beats_per_bar = beats_per_bar;
+        self
+    }
+
+    /// Returns the number of beats per minute.
+    #[inline]
+    pub fn bpm(&self) -> f32 {
+        self.bpm
+    }
+
+    /// Returns the index of the sample where the given beat starts.
+    fn beat_start(&self, beat: u64) -> u64 {
+        (beat as f64 * 60.0 * self.sample_rate as f64 / self.bpm as f64).round() as u64
+    }
+}
+
+impl Iterator for Metronome {
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        if self.num_sample == self.next_beat {
+            let accent =
+                self.beats_per_bar != 0 && self.beat.is_multiple_of(self.beats_per_bar as u64);
+            self.click_freq = if accent { ACCENT_FREQ } else { CLICK_FREQ };
+            self.click_start = self.num_sample;
+            self.beat += 1;
+            self.next_beat = self.beat_start(self.beat);
+        }
+
+        let elapsed = self.num_sample - self.click_start;
+        self.num_sample += 1;
+        if elapsed >= self.click_len {
+            return Some(0.0);
+        }
+
+        // The click starts at its peak, so that its start is sharp.
+        let phase = 2.0 * PI * self.click_freq * elapsed as f32 / self.sample_rate as f32;
+        Some(CLICK_AMPLITUDE * (-self.decay * elapsed as f32).exp() * phase.cos())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (usize::MAX, None)
+    }
+}
+
+impl Source for Metronome {
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        true
+    }
+}
+
+/// Filter that adds the clicks of a metronome to every channel of a source.
+///
+/// The metronome uses the sample rate that the source has when the filter is built.
+#[derive(Clone, Debug)]
+pub struct WithMetronome<I> {
+    input: I,
+    metronome: Metronome,
+    // Value of the click for the current frame.
+    click: f32,
+    current_channel: u16,
+}
+
+impl<I> WithMetronome<I> {
+    /// Returns a reference to the metronome.
+    #[inline]
+    pub fn metronome(&self) -> &Metronome {
+        &self.metronome
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for WithMetronome<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+
+        if self.current_channel == 0 {
+            self.click = self.metronome.next().unwrap_or(0.0);
+        }
+        self.current_channel += 1;
+        if self.current_channel >= self.input.channels() {
+            self.current_channel = 0;
+        }
+
+        let click: I::Item = CpalSample::from(&self.click);
+        Some(sample.saturating_add(click))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for WithMetronome<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for WithMetronome<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        self.input.is_infinite()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metronome;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    /// Returns the indices of the samples where a click starts.
+    fn onsets(samples: &[f32]) -> Vec<usize> {
+        (0..samples.len())
+            .filter(|&n| samples[n] != 0.0 && (n == 0 || samples[n - 1] == 0.0))
+            .collect()
+    }
+
+    #[test]
+    fn clicks_at_beat_interval() {
+        let samples: Vec<f32> = Metronome::new(48000, 120.0).take(48000 * 3).collect();
+        assert_eq!(onsets(&samples), [0, 24000, 48000, 72000, 96000, 120000]);
+
+        // 60 / 70 seconds isn't a whole number of samples.
+        let samples: Vec<f32> = Metronome::new(48000, 70.0).take(48000 * 4).collect();
+        assert_eq!(onsets(&samples), [0, 41143, 82286, 123429, 164571]);
+    }
+
+    #[test]
+    fn accents_downbeat() {
+        let mut metronome = Metronome::new(48000, 120.0).with_time_signature(3);
+        let beats: Vec<Vec<f32>> = (0..4)
+            .map(|_| metronome.by_ref().take(24000).collect())
+            .collect();
+        assert_eq!(beats[0], beats[3]);
+        assert_eq!(beats[1], beats[2]);
+        assert_ne!(beats[0], beats[1]);
+    }
+
+    #[test]
+    fn overlays_source() {
+        let source = SamplesBuffer::new(2, 48000, vec![0.25f32; 48000 * 2]).with_metronome(60.0);
+        assert_eq!(source.channels(), 2);
+        let samples: Vec<f32> = source.collect();
+        assert_eq!(samples.len(), 48000 * 2);
+        assert_eq!(samples[0], 0.75);
+        assert_eq!(samples[1], 0.75);
+        assert_eq!(samples[48000 * 2 - 1], 0.25);
+    }
+}
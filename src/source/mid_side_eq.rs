@@ -0,0 +1,239 @@
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::biquad::{Biquad, Coefficients};
+use crate::source::NotStereoError;
+use crate::{Sample, Source};
+
+/// Internal function that builds a `MidSideEq` object.
+pub fn mid_side_eq<I>(
+    input: I,
+    mid_bands: &[EqBand],
+    side_bands: &[EqBand],
+) -> Result<MidSideEq<I>, NotStereoError>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let channels = input.channels();
+    if channels != 2 {
+        return Err(NotStereoError { channels });
+    }
+
+    let sample_rate = input.sample_rate();
+    let filters = |bands: &[EqBand]| {
+        bands
+            .iter()
+            .map(|band| {
+                Biquad::new(Coefficients::peaking(
+                    band.freq,
+                    band.q.max(0.01),
+                    band.gain_db,
+                    sample_rate as f32,
+                ))
+            })
+            .collect()
+    };
+    Ok(MidSideEq {
+        mid: filters(mid_bands),
+        side: filters(side_bands),
+        input,
+        right: None,
+    })
+}
+
+/// A band of a parametric equalizer, which boosts or cuts the frequencies around `freq`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EqBand {
+    /// The center frequency of the band, in Hz.
+    pub freq: f32,
+    /// The gain at the center frequency, in decibels. Negative values cut.
+    pub gain_db: f32,
+    /// The quality factor of the band. Higher values make it narrower. `0.7` is fairly wide.
+    pub q: f32,
+}
+
+impl EqBand {
+    /// Builds a new `EqBand`.
+    #[inline]
+    pub fn new(freq: f32, gain_db: f32, q: f32) -> EqBand {
+        EqBand { freq, gain_db, q }
+    }
+}
+
+/// A source that equalizes the mid and the side of a stereo sound separately.
+///
+/// The mid is what both channels have in common and the side is their difference. Boosting the
+/// side in a band widens the stereo image at these frequencies, without changing the center of
+/// the mix.
+pub struct MidSideEq<I> {
+    input: I,
+    mid: Vec<Biquad>,
+    side: Vec<Biquad>,
+    // The right channel of the current frame after the equalizer, until it is returned.
+    right: Option<f32>,
+}
+
+impl<I> MidSideEq<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for MidSideEq<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if let Some(right) = self.right.take() {
+            return Some(CpalSample::from(&right));
+        }
+
+        // The left sample is only returned once the right one is known.
+        let left = self.input.next()?.to_f32();
+        let right = match self.input.next() {
+            Some(right) => right.to_f32(),
+            None => return Some(CpalSample::from(&left)),
+        };
+
+        let mid = self
+            .mid
+            .iter_mut()
+            .fold((left + right) / 2.0, |mid, filter| filter.process(mid));
+        let side = self
+            .side
+            .iter_mut()
+            .fold((left - right) / 2.0, |side, filter| filter.process(side));
+        self.right = Some(mid - side);
+        Some(CpalSample::from(&(mid + side)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.right.is_some() as usize;
+        let (min, max) = self.input.size_hint();
+        (
+            min.saturating_add(pending),
+            max.and_then(|max| max.checked_add(pending)),
+        )
+    }
+}
+
+impl<I> ExactSizeIterator for MidSideEq<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for MidSideEq<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let pending = self.right.is_some() as usize;
+        self.input.current_frame_len().map(|len| len + pending)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::EqBand;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{NotStereoError, Source};
+
+    /// Returns the amplitude of `freq` in `samples`, played at 48kHz.
+    fn amplitude(samples: &[f32], freq: f32) -> f32 {
+        let (re, im) = samples
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(re, im), (n, sample)| {
+                let phase = 2.0 * PI * freq * n as f32 / 48000.0;
+                (re + sample * phase.cos(), im + sample * phase.sin())
+            });
+        2.0 * (re * re + im * im).sqrt() / samples.len() as f32
+    }
+
+    #[test]
+    fn side_boost_widens() {
+        // The mid holds 1kHz and 5kHz, the side holds the same frequencies at lower levels.
+        let sine = |freq: f32, n: usize| (2.0 * PI * freq * n as f32 / 48000.0).sin();
+        let data: Vec<f32> = (0..48000)
+            .flat_map(|n| {
+                let mid = 0.2 * sine(1000.0, n) + 0.2 * sine(5000.0, n);
+                let side = 0.1 * sine(1000.0, n) + 0.1 * sine(5000.0, n);
+                [mid + side, mid - side]
+            })
+            .collect();
+        let source = SamplesBuffer::new(2, 48000, data)
+            .mid_side_eq(&[], &[EqBand::new(1000.0, 6.0, 2.0)])
+            .unwrap();
+        let output: Vec<f32> = source.collect();
+
+        let mid: Vec<f32> = output[9600..]
+            .chunks(2)
+            .map(|frame| (frame[0] + frame[1]) / 2.0)
+            .collect();
+        let side: Vec<f32> = output[9600..]
+            .chunks(2)
+            .map(|frame| (frame[0] - frame[1]) / 2.0)
+            .collect();
+
+        // The side is boosted by 6dB at 1kHz only, and the mid is unchanged.
+        assert!((amplitude(&side, 1000.0) - 0.2).abs() < 0.01);
+        assert!((amplitude(&side, 5000.0) - 0.1).abs() < 0.01);
+        assert!((amplitude(&mid, 1000.0) - 0.2).abs() < 1e-3);
+        assert!((amplitude(&mid, 5000.0) - 0.2).abs() < 1e-3);
+    }
+
+    #[test]
+    fn requires_stereo() {
+        let source = SamplesBuffer::new(1, 48000, vec![0.0f32; 10]);
+        assert_eq!(
+            source.mid_side_eq(&[], &[]).err(),
+            Some(NotStereoError { channels: 1 })
+        );
+    }
+}
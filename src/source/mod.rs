@@ -1,59 +1,181 @@
 //! Sources of sound and various filters.
 
+use std::sync::atomic::{AtomicU32, AtomicU64};
+use std::sync::Arc;
 use std::time::Duration;
 
+use crate::buffer::SamplesBuffer;
 use crate::Sample;
 
+pub use self::align_phase::align_phase;
 pub use self::amplify::Amplify;
+pub use self::amplify_shared::{AmplifyShared, VolumeHandle};
+pub use self::analyze::{analyze, AudioStats};
+pub use self::at_rate::AtRate;
+pub use self::binaural::Binaural;
+pub use self::blend::Blend;
+pub use self::blocks::Blocks;
 pub use self::blt::BltFilter;
+pub use self::buffer_queue::BufferQueueSource;
 pub use self::buffered::Buffered;
+pub use self::buffering::{BufferingHandle, BufferingSource, BufferingState};
+pub use self::channel_delays::ChannelDelays;
+pub use self::channel_gains::ChannelGains;
+pub use self::channel_permute::ChannelPermute;
 pub use self::channel_volume::ChannelVolume;
+pub use self::clip_indicator::{ClipIndicator, ClipIndicatorHandle};
+pub use self::companding::Companding;
+pub use self::complete_frames::CompleteFrames;
+pub use self::correlation::{CorrelationHandle, CorrelationMeter, NotStereoError};
+pub use self::counted::Counted;
+#[cfg(any(test, feature = "test-support"))]
+pub use self::counting::CountingSource;
 pub use self::crossfade::Crossfade;
+pub use self::crossover::CrossoverBand;
+pub use self::cut::Cut;
+pub use self::decode_thread::DecodeThread;
+pub(crate) use self::decode_thread::DecodeWorker;
 pub use self::delay::Delay;
+pub use self::disk_cache::DiskCache;
 pub use self::done::Done;
+pub use self::duck::Duck;
 pub use self::empty::Empty;
+pub use self::envelope_tap::EnvelopeTap;
+pub use self::expander::Expander;
 pub use self::fadein::FadeIn;
+pub use self::fir::Fir;
+pub use self::fit_duration::FitDuration;
+pub use self::force_channels::{ForceChannels, UpmixMode};
 pub use self::from_factory::{from_factory, FromFactoryIter};
 pub use self::from_iter::{from_iter, FromIter};
+pub use self::gain_envelope::GainEnvelope;
+pub use self::gate_playback::GatePlayback;
+pub use self::haas::{Channel, Haas};
+pub use self::invert::Invert;
+pub use self::limiter::Limiter;
+pub use self::loop_region::LoopRegion;
+pub use self::lufs::{LufsHandle, LufsMeter};
+pub use self::max_channels::MaxChannels;
+pub use self::merge_channels::{merge_channels, MergeChannels, MergeChannelsError};
+pub use self::metronome::{Metronome, WithMetronome};
+pub use self::mid_side_eq::{EqBand, MidSideEq};
 pub use self::mix::Mix;
+pub use self::normalize::NormalizeStreaming;
+pub use self::on_position::OnPosition;
+pub use self::oversample::Oversampled;
+pub use self::pad_to_multiple::PadToMultiple;
 pub use self::pausable::Pausable;
 pub use self::periodic::PeriodicAccess;
+pub use self::pseudo_stereo::PseudoStereo;
+pub use self::ramps::Ramps;
+pub use self::remove_center::RemoveCenter;
 pub use self::repeat::Repeat;
+pub use self::repeat_crossfade::RepeatCrossfade;
+pub use self::replaygain::{scan_replaygain, ReplayGainInfo};
+pub use self::room_reverb::{RoomReverb, RoomReverbHandle};
 pub use self::samples_converter::SamplesConverter;
+pub use self::silence::Silence;
 pub use self::sine::SineWave;
 pub use self::skip::SkipDuration;
 pub use self::spatial::Spatial;
+pub use self::spectrum::{Spectrum, SpectrumHandle};
 pub use self::speed::Speed;
+pub use self::stop_on_silence::StopOnSilence;
 pub use self::stoppable::Stoppable;
 pub use self::take::TakeDuration;
+pub use self::tee::Tee;
 pub use self::uniform::UniformSourceIterator;
+pub use self::window::WindowFn;
+pub use self::windowed::Windowed;
 pub use self::zero::Zero;
 
+pub(crate) use self::repeat_crossfade::repeat_crossfade_infinite;
+
+mod align_phase;
 mod amplify;
+mod amplify_shared;
+mod analyze;
+mod at_rate;
+mod binaural;
+mod biquad;
+mod blend;
+mod blocks;
 mod blt;
+mod buffer_queue;
 mod buffered;
+mod buffering;
+mod channel_delays;
+mod channel_gains;
+mod channel_permute;
 mod channel_volume;
+mod clip_indicator;
+mod companding;
+mod complete_frames;
+mod correlation;
+mod counted;
+#[cfg(any(test, feature = "test-support"))]
+mod counting;
 mod crossfade;
+mod crossover;
+mod cut;
+mod decode_thread;
 mod delay;
+mod disk_cache;
 mod done;
+mod duck;
 mod empty;
+mod envelope;
+mod envelope_tap;
+mod expander;
 mod fadein;
+mod fir;
+mod fit_duration;
+mod force_channels;
 mod from_factory;
 mod from_iter;
+mod gain_envelope;
+mod gate_playback;
+mod haas;
+mod invert;
+mod limiter;
+mod loop_region;
+mod lufs;
+mod max_channels;
+mod merge_channels;
+mod metronome;
+mod mid_side_eq;
 mod mix;
+mod normalize;
+mod on_position;
+mod oversample;
+mod pad_to_multiple;
 mod pausable;
 mod periodic;
+mod pseudo_stereo;
+mod ramps;
+mod remove_center;
 mod repeat;
+mod repeat_crossfade;
+mod replaygain;
+mod room_reverb;
 mod samples_converter;
+mod silence;
 mod sine;
 mod skip;
 mod spatial;
+mod spectrum;
 mod speed;
+mod stop_on_silence;
 mod stoppable;
 mod take;
+mod tee;
 mod uniform;
+mod waveform_peaks;
+mod windowed;
 mod zero;
 
+pub mod window;
+
 /// A source of samples.
 ///
 /// # A quick lesson about sounds
@@ -144,6 +266,45 @@ where
     /// `None` indicates at the same time "infinite" or "unknown".
     fn total_duration(&self) -> Option<Duration>;
 
+    /// Returns `true` if this source is known to never end, such as a `SineWave` or a source
+    /// repeated with `repeat_infinite`.
+    ///
+    /// The default implementation returns `false`, because a source whose total duration is
+    /// unknown, such as a decoder, usually ends. Sources that never end override it.
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        false
+    }
+
+    /// Returns `true` unless this source is known to never end.
+    ///
+    /// This is the opposite of `is_infinite`.
+    #[inline]
+    fn is_finite(&self) -> bool {
+        !self.is_infinite()
+    }
+
+    /// Fills `out` with the next samples and returns the number of samples written.
+    ///
+    /// Fewer than `out.len()` samples are only written once the source has ended. The block may
+    /// cross the end of the current frame, so callers that care about format changes should
+    /// limit the length of `out` to `current_frame_len()`.
+    ///
+    /// The default implementation calls `next` for each sample. Sources that can produce whole
+    /// blocks at once override it, so that filters can process blocks without a call per sample.
+    #[inline]
+    fn next_block(&mut self, out: &mut [Self::Item]) -> usize {
+        let mut written = 0;
+        for slot in out.iter_mut() {
+            match self.next() {
+                Some(sample) => *slot = sample,
+                None => break,
+            }
+            written += 1;
+        }
+        written
+    }
+
     /// Stores the source in a buffer in addition to returning it. This iterator can be cloned.
     #[inline]
     fn buffered(self) -> Buffered<Self>
@@ -238,6 +399,19 @@ where
         fadein::fadein(self, duration)
     }
 
+    /// Fades the sound in at its beginning and out at its end, so that it doesn't click at
+    /// either end.
+    ///
+    /// The fade out needs to know the total duration of the sound and is skipped if
+    /// `total_duration` returns `None`.
+    #[inline]
+    fn ramps(self, fade_in: Duration, fade_out: Duration) -> Ramps<Self>
+    where
+        Self: Sized,
+    {
+        ramps::ramps(self, fade_in, fade_out)
+    }
+
     /// Calls the `access` closure on `Self` the first time the source is iterated and every
     /// time `period` elapses.
     ///
@@ -336,79 +510,1147 @@ where
     {
         blt::high_pass(self, freq)
     }
-}
 
-impl<S> Source for Box<dyn Source<Item = S>>
-where
-    S: Sample,
-{
+    /// Multiplies each channel of the sound by its own gain.
+    ///
+    /// The number of gains must be equal to the number of channels of the source.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `gains.len()` is different from `channels()`.
     #[inline]
-    fn current_frame_len(&self) -> Option<usize> {
-        (**self).current_frame_len()
+    fn channel_gains(self, gains: &[f32]) -> ChannelGains<Self>
+    where
+        Self: Sized,
+    {
+        channel_gains::channel_gains(self, gains)
     }
 
+    /// Trims the level of each channel by its own number of decibels, for example to match the
+    /// levels of the speakers of a surround setup.
+    ///
+    /// The number of trims must be equal to the number of channels of the source. This is the
+    /// same as `channel_gains` with the gains given in decibels.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `trims_db.len()` is different from `channels()`.
     #[inline]
-    fn channels(&self) -> u16 {
-        (**self).channels()
+    fn channel_trim_db(self, trims_db: &[f32]) -> ChannelGains<Self>
+    where
+        Self: Sized,
+    {
+        let gains: Vec<f32> = trims_db
+            .iter()
+            .map(|trim| 10f32.powf(trim / 20.0))
+            .collect();
+        channel_gains::channel_gains(self, &gains)
     }
 
+    /// Reorders the channels of each frame of the sound.
+    ///
+    /// Output channel `n` is taken from input channel `permutation[n]`. For example `[1, 0]`
+    /// swaps the left and right channels of a stereo sound.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `permutation.len()` is different from `channels()` or if one of the indices is
+    /// out of range.
     #[inline]
-    fn sample_rate(&self) -> u32 {
-        (**self).sample_rate()
+    fn permute_channels(self, permutation: &[usize]) -> ChannelPermute<Self>
+    where
+        Self: Sized,
+    {
+        channel_permute::channel_permute(self, permutation)
     }
 
+    /// Swaps the left and right channels of a stereo sound.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the sound doesn't have exactly two channels.
     #[inline]
-    fn total_duration(&self) -> Option<Duration> {
-        (**self).total_duration()
+    fn swap_channels(self) -> ChannelPermute<Self>
+    where
+        Self: Sized,
+    {
+        channel_permute::channel_permute(self, &[1, 0])
     }
-}
 
-impl<S> Source for Box<dyn Source<Item = S> + Send>
-where
-    S: Sample,
-{
+    /// Lowers the volume of this sound while `side_chain` is playing, and mixes both together.
+    ///
+    /// Whenever `side_chain` is audible, the volume of this sound is lowered by `amount`, a
+    /// value between `0.0` (no change) and `1.0` (silence). `attack` is how quickly the volume
+    /// goes down, and `release` how quickly it goes back up once `side_chain` is silent.
+    ///
+    /// `side_chain` is converted to the channels and sample rate of this sound.
+    ///
+    /// # Example
+    ///
+    /// ```ignore
+    /// use std::time::Duration;
+    ///
+    /// let source = music.duck_by(voice, 0.7, Duration::from_millis(20), Duration::from_millis(500));
+    /// ```
     #[inline]
-    fn current_frame_len(&self) -> Option<usize> {
-        (**self).current_frame_len()
+    fn duck_by<S>(
+        self,
+        side_chain: S,
+        amount: f32,
+        attack: Duration,
+        release: Duration,
+    ) -> Duck<Self, S>
+    where
+        Self: Sized,
+        S: Source,
+        S::Item: Sample,
+    {
+        duck::duck(self, side_chain, amount, attack, release)
     }
 
+    /// Applies a downward expander to the sound.
+    ///
+    /// When the level of the sound goes below `threshold_db` (in dBFS), it is attenuated so that
+    /// each decibel below the threshold becomes `ratio` decibels below it. Unlike a noise gate,
+    /// quiet passages are not muted but lowered progressively. Loud content is left unchanged.
+    ///
+    /// `attack` and `release` control how quickly the detected level follows the sound when it
+    /// gets louder or quieter.
     #[inline]
-    fn channels(&self) -> u16 {
-        (**self).channels()
+    fn expand(
+        self,
+        threshold_db: f32,
+        ratio: f32,
+        attack: Duration,
+        release: Duration,
+    ) -> Expander<Self>
+    where
+        Self: Sized,
+    {
+        expander::expander(self, threshold_db, ratio, attack, release)
     }
 
+    /// Flips the polarity of the sound by negating every sample.
+    ///
+    /// Mixing a sound with its inverted copy results in silence.
     #[inline]
-    fn sample_rate(&self) -> u32 {
-        (**self).sample_rate()
+    fn phase_invert(self) -> Invert<Self>
+    where
+        Self: Sized,
+    {
+        invert::invert(self)
     }
 
+    /// Flips the polarity of the channels for which `channels` is `true`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `channels.len()` is different from `channels()`.
     #[inline]
-    fn total_duration(&self) -> Option<Duration> {
-        (**self).total_duration()
+    fn phase_invert_channels(self, channels: &[bool]) -> Invert<Self>
+    where
+        Self: Sized,
+    {
+        invert::invert_channels(self, channels)
     }
-}
 
-impl<S> Source for Box<dyn Source<Item = S> + Send + Sync>
-where
-    S: Sample,
-{
+    /// Splits this source into two halves that both produce the same samples.
+    ///
+    /// This is useful to play a sound while also analyzing it, for example for a visualizer.
+    /// The samples that one half reads ahead of the other are buffered. If one half lags behind
+    /// by more than 65536 frames, its oldest frames are dropped.
     #[inline]
-    fn current_frame_len(&self) -> Option<usize> {
-        (**self).current_frame_len()
+    fn split(self) -> (Tee<Self>, Tee<Self>)
+    where
+        Self: Sized,
+    {
+        tee::tee(self)
     }
 
+    /// Ends the sound once it has been continuously quieter than `threshold_db` (in dBFS) for
+    /// `duration`.
+    ///
+    /// Dips in the level that are shorter than `duration` don't stop the sound. The silent part
+    /// itself is played before the sound ends.
     #[inline]
-    fn channels(&self) -> u16 {
-        (**self).channels()
+    fn stop_on_silence(self, threshold_db: f32, duration: Duration) -> StopOnSilence<Self>
+    where
+        Self: Sized,
+    {
+        stop_on_silence::stop_on_silence(self, threshold_db, duration)
     }
 
+    /// Converts the source to the given sample rate.
+    ///
+    /// The resampler is only inserted if the sample rate of the source is different from
+    /// `sample_rate`, otherwise the samples are passed through unchanged. This is useful to build
+    /// processing chains that assume a fixed sample rate.
     #[inline]
-    fn sample_rate(&self) -> u32 {
-        (**self).sample_rate()
+    fn at_rate(self, sample_rate: u32) -> AtRate<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        at_rate::at_rate(self, sample_rate)
     }
 
+    /// Splits the source into frequency bands.
+    ///
+    /// `frequencies` are the crossover frequencies in Hz, in increasing order. One band is
+    /// returned per frequency range, starting with the lowest one, so there is one more band than
+    /// there are frequencies. The bands are in phase with each other and their sum has the same
+    /// magnitude as the original sound, with its phase shifted around each crossover frequency.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the frequencies aren't strictly increasing or aren't between 0 and half the
+    /// sample rate.
     #[inline]
-    fn total_duration(&self) -> Option<Duration> {
-        (**self).total_duration()
+    fn crossover(self, frequencies: &[f32]) -> Vec<CrossoverBand<Self>>
+    where
+        Self: Sized,
+        Self: Source<Item = f32>,
+    {
+        crossover::crossover(self, frequencies)
+    }
+
+    /// Adds a reverb that simulates a room.
+    ///
+    /// `room_size`, `damping`, `wet` and `width` are all between 0 and 1. A bigger room has a
+    /// longer tail, `damping` reduces the high frequencies of the tail, `wet` is the level of the
+    /// reverb and `width` its stereo width. They can be changed while playing with
+    /// `RoomReverb::handle`.
+    #[inline]
+    fn room_reverb(self, room_size: f32, damping: f32, wet: f32, width: f32) -> RoomReverb<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        room_reverb::room_reverb(self, room_size, damping, wet, width)
+    }
+
+    /// Calls `callback` every time the playback position reaches one of the `markers`.
+    ///
+    /// The position is computed from the number of samples read from the source, so the callback
+    /// is called right when the first sample at that position is read. Each marker is passed to
+    /// the callback exactly once, in increasing order.
+    #[inline]
+    fn on_position<F>(self, markers: &[Duration], callback: F) -> OnPosition<Self, F>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+        F: FnMut(Duration),
+    {
+        on_position::on_position(self, markers, callback)
+    }
+
+    /// Compresses the samples with the μ-law algorithm and quantizes them to 8 bits.
+    ///
+    /// Use `mu_law_decode` to get linear samples back.
+    #[inline]
+    fn mu_law_encode(self) -> Companding<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        companding::companding(self, companding::Law::MuLaw, companding::Direction::Encode)
+    }
+
+    /// Converts μ-law companded samples back to linear samples.
+    #[inline]
+    fn mu_law_decode(self) -> Companding<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        companding::companding(self, companding::Law::MuLaw, companding::Direction::Decode)
+    }
+
+    /// Compresses the samples with the A-law algorithm and quantizes them to 8 bits.
+    ///
+    /// Use `a_law_decode` to get linear samples back.
+    #[inline]
+    fn a_law_encode(self) -> Companding<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        companding::companding(self, companding::Law::ALaw, companding::Direction::Encode)
+    }
+
+    /// Converts A-law companded samples back to linear samples.
+    #[inline]
+    fn a_law_decode(self) -> Companding<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        companding::companding(self, companding::Law::ALaw, companding::Direction::Decode)
+    }
+
+    /// Computes the spectrum of the sound while playing it unchanged.
+    ///
+    /// The latest spectrum, made of `fft_size / 2 + 1` frequency bins, is available through
+    /// `Spectrum::handle`. A Hann window is used.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `fft_size` isn't a power of two or is smaller than 2.
+    #[inline]
+    fn spectrum(self, fft_size: usize) -> Spectrum<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        spectrum::spectrum(self, fft_size, WindowFn::Hann)
+    }
+
+    /// Same as `spectrum`, but with a different window.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `fft_size` isn't a power of two or is smaller than 2.
+    #[inline]
+    fn spectrum_with_window(self, fft_size: usize, window: WindowFn) -> Spectrum<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        spectrum::spectrum(self, fft_size, window)
+    }
+
+    /// Adds this source and another one together, with a constant balance between them.
+    ///
+    /// Each sample is `self * (1 - ratio) + other * ratio`, so a ratio of `0.0` only plays this
+    /// source and a ratio of `1.0` only plays `other`. The ratio can be changed later with
+    /// `Blend::set_ratio`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `ratio` isn't between 0 and 1.
+    #[inline]
+    fn interleave_with<S>(self, other: S, ratio: f32) -> Blend<Self, S>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+        S: Source,
+        S::Item: Sample,
+    {
+        blend::blend(self, other, ratio)
+    }
+
+    /// Downmixes the source if it has more than `max` channels.
+    ///
+    /// Surround sources are downmixed with the usual coefficients, assuming that their channels
+    /// are in the front left, front right, center, LFE, back left, back right order. Sources that
+    /// have `max` channels or less are passed through untouched.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `max` is 0.
+    #[inline]
+    fn max_channels(self, max: u16) -> MaxChannels<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        max_channels::max_channels(self, max)
+    }
+
+    /// Adjusts the volume of the sound in real time so that its RMS level approaches
+    /// `target_rms`.
+    ///
+    /// The level is measured over the last `window` of sound, and the gain changes smoothly over
+    /// about the same duration. Use a window of a few seconds for music. Unlike a normalization
+    /// over the whole sound, this works with streams of unknown length.
+    #[inline]
+    fn normalize_streaming(self, target_rms: f32, window: Duration) -> NormalizeStreaming<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        normalize::normalize_streaming(self, target_rms, window)
+    }
+
+    /// Makes the source last exactly `duration`.
+    ///
+    /// The source is cut if it is longer, and silence is added at the end if it is shorter.
+    /// `total_duration` returns `duration`.
+    #[inline]
+    fn fit_duration(self, duration: Duration) -> FitDuration<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        fit_duration::fit_duration(self, duration)
+    }
+
+    /// Resamples the source to `sample_rate` with linear interpolation.
+    ///
+    /// Linear interpolation is cheap and is a good choice for sound effects in games. Unlike
+    /// `at_rate`, this is guaranteed to keep using the linear resampler if higher quality
+    /// resamplers are added later. Like `at_rate`, the samples are passed through unchanged if
+    /// the source already has that sample rate.
+    #[inline]
+    fn resample_linear(self, sample_rate: u32) -> AtRate<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        at_rate::resample_linear(self, sample_rate)
+    }
+
+    /// Reads the source on a separate thread, about `buffer` in advance.
+    ///
+    /// This moves the cost of decoding out of the audio callback, which avoids underruns with
+    /// sources that are expensive to decode. Reading the returned source never blocks: if the
+    /// decoding thread is late, silence is returned instead.
+    #[inline]
+    fn decode_in_thread(self, buffer: Duration) -> DecodeThread<Self::Item>
+    where
+        Self: Sized + Send + 'static,
+        Self::Item: Sample + Send,
+    {
+        decode_thread::decode_thread(self, buffer)
+    }
+
+    /// Multiplies blocks of `block_frames` frames by a window function.
+    ///
+    /// Windowing blocks before analysing them, for example with
+    /// [`spectrum`](Source::spectrum), reduces the leakage between frequencies. See the
+    /// [`window`] module for the available functions.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `block_frames` is less than 2.
+    #[inline]
+    fn windowed(self, window: WindowFn, block_frames: usize) -> Windowed<Self>
+    where
+        Self: Sized,
+        Self::Item: Sample,
+    {
+        windowed::windowed(self, window, block_frames)
+    }
+
+    /// Amplifies the sound by a value that can be changed while it plays.
+    ///
+    /// The returned handle can be sent to another thread; each sample is multiplied by the
+    /// latest value given to it. The volume starts at `1.0`.
+    #[inline]
+    fn amplify_shared(self) -> (AmplifyShared<Self>, VolumeHandle)
+    where
+        Self: Sized,
+    {
+        amplify_shared::amplify_shared(self, 1.0)
+    }
+
+    /// Turns a mono sound into a stereo one with a subtle, artificial stereo image.
+    ///
+    /// This is meant to make old mono recordings sound less narrow. The average of the two
+    /// channels is the original sound, so playing the result on a mono output sounds the same as
+    /// the original.
+    #[inline]
+    fn pseudo_stereo(self) -> PseudoStereo<Self>
+    where
+        Self: Sized,
+    {
+        pseudo_stereo::pseudo_stereo(self)
+    }
+
+    /// Plays this source `times` times, crossfading the end of each iteration into the beginning
+    /// of the next one during `fade`.
+    ///
+    /// This hides the seam of a loop. Like `repeat_infinite`, this stores the data in a buffer,
+    /// so the amount of memory used is proportional to the size of the sound.
+    #[inline]
+    fn repeat_crossfade(self, times: usize, fade: Duration) -> RepeatCrossfade<Self>
+    where
+        Self: Sized,
+    {
+        repeat_crossfade::repeat_crossfade(self, times, fade)
+    }
+
+    /// Stores the samples of this source in a file at `path` while it plays, which allows
+    /// seeking in it.
+    ///
+    /// This is meant for sources that are too long to be kept in memory with `buffered`. The
+    /// source is decoded on a separate thread, up to 10 seconds ahead of playback, so that it can
+    /// start right away. The file is removed when the returned source is dropped.
+    #[inline]
+    fn cache_to_disk<P>(self, path: P) -> std::io::Result<DiskCache<Self::Item>>
+    where
+        Self: Sized + Send + 'static,
+        Self::Item: Sample + Send,
+        P: AsRef<std::path::Path>,
+    {
+        disk_cache::disk_cache(self, path.as_ref())
+    }
+
+    /// Applies a gain that goes linearly from one breakpoint to the next.
+    ///
+    /// Each breakpoint is a time, relative to the start of the source, and the gain at that
+    /// time. This is useful to script the volume of a sound, for example in a cutscene.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `points` is empty.
+    #[inline]
+    fn gain_envelope(self, points: &[(Duration, f32)]) -> GainEnvelope<Self>
+    where
+        Self: Sized,
+    {
+        gain_envelope::gain_envelope(self, points)
+    }
+
+    /// Measures the loudness of the sound as it plays, as defined by ITU-R BS.1770.
+    ///
+    /// The momentary, short-term and integrated loudness are read in LUFS with the handle of the
+    /// returned source, from any thread. The sound itself is unchanged.
+    #[inline]
+    fn lufs_meter(self) -> LufsMeter<Self>
+    where
+        Self: Sized,
+    {
+        lufs::lufs_meter(self)
+    }
+
+    /// Plays the source up to `end`, then loops forever between `start` and `end`.
+    ///
+    /// This works like the sustain loop of a sampler. The source is stored in memory up to `end`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `start` isn't before `end`.
+    #[inline]
+    fn loop_region(self, start: Duration, end: Duration) -> LoopRegion<Self::Item>
+    where
+        Self: Sized,
+    {
+        loop_region::loop_region(self, start, end, Duration::ZERO)
+    }
+
+    /// Same as `loop_region`, but crossfades the end of the loop with the sound that comes just
+    /// before its start during `fade`, which hides the seam.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `start` isn't before `end`.
+    #[inline]
+    fn loop_region_with_crossfade(
+        self,
+        start: Duration,
+        end: Duration,
+        fade: Duration,
+    ) -> LoopRegion<Self::Item>
+    where
+        Self: Sized,
+    {
+        loop_region::loop_region(self, start, end, fade)
+    }
+
+    /// Renders a surround source to two channels for headphones.
+    ///
+    /// Each channel is played by a virtual speaker placed around the listener, which lets them
+    /// hear where the sounds come from without a surround setup.
+    #[inline]
+    fn surround_to_binaural(self) -> Binaural<Self>
+    where
+        Self: Sized,
+    {
+        binaural::binaural(self)
+    }
+
+    /// Measures the phase correlation between the left and right channel as the sound plays.
+    ///
+    /// The correlation is read with the handle of the returned source, from any thread. The sound
+    /// itself is unchanged. Returns an error if the source doesn't have two channels.
+    #[inline]
+    fn correlation_meter(self) -> Result<CorrelationMeter<Self>, NotStereoError>
+    where
+        Self: Sized,
+    {
+        correlation::correlation_meter(self)
+    }
+
+    /// Filters each channel with the given finite impulse response.
+    ///
+    /// `coefficients` are the taps of the filter, the first one being applied to the current
+    /// sample. This can apply any linear filter designed elsewhere.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `coefficients` is empty.
+    #[inline]
+    fn fir(self, coefficients: Vec<f32>) -> Fir<Self>
+    where
+        Self: Sized,
+    {
+        fir::fir(self, coefficients)
+    }
+
+    /// Adds silence at the end of the source so that the number of samples it returns is a
+    /// multiple of `multiple`.
+    ///
+    /// This is useful to feed processors that work on fixed-size blocks. The count is in
+    /// samples, not frames, so `multiple` should usually be a multiple of the number of
+    /// channels.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `multiple` is zero.
+    #[inline]
+    fn pad_to_multiple(self, multiple: usize) -> PadToMultiple<Self>
+    where
+        Self: Sized,
+    {
+        pad_to_multiple::pad_to_multiple(self, multiple)
+    }
+
+    /// Delays one channel by `delay`, which widens the sound through the Haas effect.
+    ///
+    /// Delays of 1 to 30 milliseconds make the sound wider without being heard as an echo. A mono
+    /// source is turned into a stereo one.
+    #[inline]
+    fn haas(self, delay: Duration, side: Channel) -> Haas<Self>
+    where
+        Self: Sized,
+    {
+        haas::haas(self, delay, side)
+    }
+
+    /// Plays the whole source into a `SamplesBuffer` with the same channels and sample rate.
+    ///
+    /// The source is read until it ends, even if its total duration is unknown. Returns `None`
+    /// without reading anything if the source is known to be infinite, see `is_infinite`. If the
+    /// format of the source changes while it plays, the rest of it is converted to the format it
+    /// had at the start.
+    fn collect_buffered(self) -> Option<SamplesBuffer<Self::Item>>
+    where
+        Self: Sized,
+    {
+        if self.is_infinite() {
+            return None;
+        }
+
+        let channels = self.channels();
+        let sample_rate = self.sample_rate();
+        let mut data = Vec::with_capacity(buffer_capacity(&self));
+        data.extend(UniformSourceIterator::<Self, Self::Item>::new(
+            self,
+            channels,
+            sample_rate,
+        ));
+        Some(SamplesBuffer::new(channels, sample_rate, data))
+    }
+
+    /// Plays the whole source and splits it into one mono `SamplesBuffer` per channel, with the
+    /// sample rate of the source.
+    ///
+    /// Each channel can then be processed on its own, and the channels put back together with
+    /// `merge_channels`. An incomplete frame at the end of the source is dropped. The source is
+    /// read until it ends, even if its total duration is unknown. Returns an empty `Vec` without
+    /// reading anything if the source is known to be infinite, see `is_infinite`. If the format
+    /// of the source changes while it plays, the rest of it is converted to the format it had at
+    /// the start.
+    fn split_channels(self) -> Vec<SamplesBuffer<Self::Item>>
+    where
+        Self: Sized,
+    {
+        if self.is_infinite() {
+            return Vec::new();
+        }
+
+        let channels = self.channels();
+        let sample_rate = self.sample_rate();
+        let frames = buffer_capacity(&self) / channels.max(1) as usize;
+        let mut data: Vec<Vec<Self::Item>> =
+            (0..channels).map(|_| Vec::with_capacity(frames)).collect();
+        let mut samples =
+            UniformSourceIterator::<Self, Self::Item>::new(self, channels, sample_rate);
+        'frames: loop {
+            for channel in data.iter_mut() {
+                match samples.next() {
+                    Some(sample) => channel.push(sample),
+                    None => break 'frames,
+                }
+            }
+        }
+
+        let frames = data.iter().map(Vec::len).min().unwrap_or(0);
+        data.into_iter()
+            .map(|mut channel| {
+                channel.truncate(frames);
+                SamplesBuffer::new(1, sample_rate, channel)
+            })
+            .collect()
+    }
+
+    /// Plays the whole source into a `Vec` of `f32` samples, and returns it with the channels
+    /// and the sample rate of the source, in this order.
+    ///
+    /// The samples are converted straight to `f32`, without losing the precision of sources
+    /// that have more than 16 bits. The source is read until it ends, even if its total duration
+    /// is unknown. Returns `None` without reading anything if the source is known to be
+    /// infinite, see `is_infinite`. If the format of the source changes while it plays, the rest
+    /// of it is converted to the format it had at the start.
+    fn to_f32_buffer(self) -> Option<(Vec<f32>, u16, u32)>
+    where
+        Self: Sized,
+    {
+        if self.is_infinite() {
+            return None;
+        }
+
+        let channels = self.channels();
+        let sample_rate = self.sample_rate();
+        let mut data = Vec::with_capacity(buffer_capacity(&self));
+        data.extend(UniformSourceIterator::<Self, f32>::new(
+            self,
+            channels,
+            sample_rate,
+        ));
+        Some((data, channels, sample_rate))
+    }
+
+    /// Delays each channel by its own duration, for example to align microphones placed at
+    /// different distances.
+    ///
+    /// # Panic
+    ///
+    /// Panics if the number of delays isn't the number of channels.
+    #[inline]
+    fn channel_delays(self, delays: &[Duration]) -> ChannelDelays<Self>
+    where
+        Self: Sized,
+    {
+        channel_delays::channel_delays(self, delays)
+    }
+
+    /// Removes the region between `start` and `end` of the source, and plays what comes before
+    /// and after it one after the other.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `start` is after `end`.
+    #[inline]
+    fn cut(self, start: Duration, end: Duration) -> Cut<Self>
+    where
+        Self: Sized,
+    {
+        cut::cut(self, start, end)
+    }
+
+    /// Converts the source to the given number of channels, with `mode` telling how extra
+    /// channels are filled.
+    ///
+    /// This makes the conversion explicit instead of leaving it to the output, which behaves like
+    /// `UpmixMode::Duplicate`. Channels beyond `channels` are dropped.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `channels` is 0.
+    #[inline]
+    fn force_channels(self, channels: u16, mode: UpmixMode) -> ForceChannels<Self>
+    where
+        Self: Sized,
+    {
+        force_channels::force_channels(self, channels, mode)
+    }
+
+    /// Skips the parts of the sound that are quieter than `threshold_db` (in dBFS), so that only
+    /// the loud parts are played, one after the other.
+    ///
+    /// The quiet part that follows a loud one is still played for `hold`, which keeps short dips
+    /// and decays. Unlike a noise gate, which silences the quiet parts, this removes them.
+    #[inline]
+    fn gate_playback(self, threshold_db: f32, hold: Duration) -> GatePlayback<Self>
+    where
+        Self: Sized,
+    {
+        gate_playback::gate_playback(self, threshold_db, hold)
+    }
+
+    /// Plays the whole source and returns the lowest and highest sample of each of `buckets`
+    /// equal parts of it, which is what is needed to draw an overview of its waveform.
+    ///
+    /// The peaks are taken over all channels. A part that doesn't contain any frame, because the
+    /// source has fewer frames than `buckets`, is `(0, 0)`. The source is read until it ends,
+    /// even if its total duration is unknown. Returns an empty `Vec` without reading anything if
+    /// the source is known to be infinite, see `is_infinite`.
+    fn waveform_peaks(self, buckets: usize) -> Vec<(i16, i16)>
+    where
+        Self: Sized,
+    {
+        waveform_peaks::waveform_peaks(self, buckets)
+    }
+
+    /// Adds the clicks of a metronome playing `bpm` beats per minute to this source.
+    ///
+    /// See `Metronome` to accent the downbeats or to play the clicks alone.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `bpm` isn't strictly positive.
+    #[inline]
+    fn with_metronome(self, bpm: f32) -> WithMetronome<Self>
+    where
+        Self: Sized,
+    {
+        metronome::with_metronome(self, bpm)
+    }
+
+    /// Holds the peak of the sound for a hardware-style peak meter with a clip indicator.
+    ///
+    /// The held peak jumps to new peaks instantly and falls by `decay_db_per_second` decibels per
+    /// second. It is read with the handle of the returned source, from any thread. The sound
+    /// itself is unchanged.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `decay_db_per_second` is negative.
+    #[inline]
+    fn clip_indicator(self, decay_db_per_second: f32) -> ClipIndicator<Self>
+    where
+        Self: Sized,
+    {
+        clip_indicator::clip_indicator(self, decay_db_per_second)
+    }
+
+    /// Makes sure that every frame has a sample for each channel, by completing the frames that
+    /// are cut short with silence.
+    ///
+    /// This repairs malformed sources that end, or change their format, in the middle of a
+    /// frame, which would otherwise shift all the channels that follow.
+    #[inline]
+    fn complete_frames(self) -> CompleteFrames<Self>
+    where
+        Self: Sized,
+    {
+        complete_frames::complete_frames(self)
+    }
+
+    /// Runs `effect` at `factor` times the sample rate of the source, then converts its output
+    /// back to the sample rate of the source with an anti-aliasing filter.
+    ///
+    /// Nonlinear effects such as distortion produce harmonics above the Nyquist frequency, which
+    /// fold back as aliasing. Oversampling keeps them out of the audible band. `effect` receives
+    /// the upsampled source and returns the effect chain to apply to it.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `factor` is zero.
+    #[inline]
+    fn oversampled<F, O>(self, factor: u32, effect: F) -> Oversampled<O>
+    where
+        Self: Sized,
+        F: FnOnce(UniformSourceIterator<Self, f32>) -> O,
+        O: Source,
+        O::Item: Sample,
+    {
+        oversample::oversampled(self, factor, effect)
+    }
+
+    /// Counts the samples returned by this source, for example to synchronize something with
+    /// the playback or to check how much of the source was played.
+    ///
+    /// Returns the source with the counter, which can be read from any thread. The sound itself
+    /// is unchanged.
+    #[inline]
+    fn counted(self) -> (Counted<Self>, Arc<AtomicU64>)
+    where
+        Self: Sized,
+    {
+        counted::counted(self)
+    }
+
+    /// Splits the source into blocks of `frames_per_block` frames of interleaved samples, as
+    /// expected by most encoders.
+    ///
+    /// The last block is shorter if the source doesn't end on a block boundary, unless
+    /// `pad_last` is true, in which case it is completed with silence.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `frames_per_block` is zero.
+    #[inline]
+    fn blocks(self, frames_per_block: usize, pad_last: bool) -> Blocks<Self>
+    where
+        Self: Sized,
+    {
+        blocks::blocks(self, frames_per_block, pad_last)
+    }
+
+    /// Equalizes the mid and the side of a stereo sound with separate bands.
+    ///
+    /// The mid is what both channels have in common and the side is their difference, so
+    /// boosting the side in a band widens the stereo image at these frequencies while leaving the
+    /// center of the mix alone. Returns an error if the source doesn't have two channels.
+    #[inline]
+    fn mid_side_eq(
+        self,
+        mid_bands: &[EqBand],
+        side_bands: &[EqBand],
+    ) -> Result<MidSideEq<Self>, NotStereoError>
+    where
+        Self: Sized,
+    {
+        mid_side_eq::mid_side_eq(self, mid_bands, side_bands)
+    }
+
+    /// Reads the source on a separate thread into a prefetch buffer that holds `buffer` of
+    /// sound, as is needed for sounds streamed from the network.
+    ///
+    /// Silence is played while the buffer fills, at the start and whenever it runs empty, and
+    /// the state of the returned source tells whether it is buffering, to show it to the user.
+    #[inline]
+    fn buffering(self, buffer: Duration) -> BufferingSource<Self::Item>
+    where
+        Self: Sized + Send + 'static,
+        Self::Item: Sample + Send,
+    {
+        buffering::buffering(self, buffer)
+    }
+
+    /// Removes what is in the center of a stereo sound, usually the vocals, to make a karaoke
+    /// mix.
+    ///
+    /// Both channels of the returned source play the difference between the left and right
+    /// channels. If `keep_bass_below` is given, the center of the sound is kept below this
+    /// frequency, in Hz, since the bass is usually centered too. Returns an error if the source
+    /// doesn't have two channels.
+    #[inline]
+    fn remove_center(
+        self,
+        keep_bass_below: Option<u32>,
+    ) -> Result<RemoveCenter<Self>, NotStereoError>
+    where
+        Self: Sized,
+    {
+        remove_center::remove_center(self, keep_bass_below)
+    }
+
+    /// Follows the amplitude envelope of this source, for example to drive visuals or other
+    /// effects from the sound.
+    ///
+    /// The envelope rises with the `attack` time and falls with the `release` time. Returns the
+    /// source with the envelope, stored as the bits of a `f32` that can be read from any thread.
+    /// The sound itself is unchanged.
+    #[inline]
+    fn envelope_follower(
+        self,
+        attack: Duration,
+        release: Duration,
+    ) -> (EnvelopeTap<Self>, Arc<AtomicU32>)
+    where
+        Self: Sized,
+    {
+        envelope_tap::envelope_tap(self, attack, release)
+    }
+}
+
+/// Number of samples to reserve to collect `source`, which is 0 if its length isn't known.
+fn buffer_capacity<S>(source: &S) -> usize
+where
+    S: Source,
+    S::Item: Sample,
+{
+    match (source.size_hint(), source.total_duration()) {
+        ((min, Some(max)), _) if min == max => max,
+        (_, Some(duration)) => {
+            let frames = duration.as_secs_f64() * source.sample_rate() as f64;
+            frames.ceil() as usize * source.channels() as usize
+        }
+        _ => 0,
+    }
+}
+
+impl<S> Source for Box<dyn Source<Item = S>>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        (**self).current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        (**self).channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        (**self).sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        (**self).total_duration()
+    }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        (**self).is_infinite()
+    }
+
+    #[inline]
+    fn next_block(&mut self, out: &mut [S]) -> usize {
+        (**self).next_block(out)
+    }
+}
+
+impl<S> Source for Box<dyn Source<Item = S> + Send>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        (**self).current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        (**self).channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        (**self).sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        (**self).total_duration()
+    }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        (**self).is_infinite()
+    }
+
+    #[inline]
+    fn next_block(&mut self, out: &mut [S]) -> usize {
+        (**self).next_block(out)
+    }
+}
+
+impl<S> Source for Box<dyn Source<Item = S> + Send + Sync>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        (**self).current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        (**self).channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        (**self).sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        (**self).total_duration()
+    }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        (**self).is_infinite()
+    }
+
+    #[inline]
+    fn next_block(&mut self, out: &mut [S]) -> usize {
+        (**self).next_block(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{from_iter, Metronome, SineWave, Source, Zero};
+
+    #[test]
+    fn known_infinite_sources() {
+        assert!(SineWave::new(440.0).is_infinite());
+        assert!(Zero::<f32>::new(1, 1000).is_infinite());
+        assert!(Metronome::new(1000, 120.0).is_infinite());
+
+        // A source of unknown length isn't taken as infinite.
+        let source = from_iter(vec![SamplesBuffer::new(1, 1000, vec![1i16; 10])]);
+        assert_eq!(source.total_duration(), None);
+        assert!(source.is_finite());
+    }
+
+    #[test]
+    fn collect_buffered() {
+        let buffer = SineWave::new(440.0)
+            .take_duration(Duration::from_millis(500))
+            .collect_buffered()
+            .unwrap();
+        assert_eq!(buffer.channels(), 1);
+        assert_eq!(buffer.sample_rate(), 48000);
+        assert_eq!(buffer.total_duration(), Some(Duration::from_millis(500)));
+        assert_eq!(buffer.count(), 24000);
+
+        // A source of unknown length is read until it ends.
+        let source = from_iter(vec![SamplesBuffer::new(1, 1000, vec![1i16; 1000])]);
+        assert_eq!(source.total_duration(), None);
+        assert_eq!(source.collect_buffered().unwrap().count(), 1000);
+
+        assert!(SineWave::new(440.0).collect_buffered().is_none());
+    }
+
+    #[test]
+    fn split_channels() {
+        let data = vec![1i16, -1, 2, -2, 3, -3, 4];
+        let channels = SamplesBuffer::new(2, 1000, data).split_channels();
+        assert_eq!(channels.len(), 2);
+        assert!(channels
+            .iter()
+            .all(|channel| channel.channels() == 1 && channel.sample_rate() == 1000));
+
+        let channels: Vec<Vec<i16>> = channels.into_iter().map(Iterator::collect).collect();
+        // The incomplete frame at the end is dropped.
+        assert_eq!(channels, [vec![1, 2, 3], vec![-1, -2, -3]]);
+        let interleaved: Vec<i16> = (0..3)
+            .flat_map(|n| [channels[0][n], channels[1][n]])
+            .collect();
+        assert_eq!(interleaved, [1, -1, 2, -2, 3, -3]);
+
+        let source = from_iter(vec![SamplesBuffer::new(2, 1000, vec![1i16, -1, 2, -2])]);
+        assert_eq!(source.split_channels().len(), 2);
+
+        assert!(SineWave::new(440.0).split_channels().is_empty());
+    }
+
+    #[test]
+    fn to_f32_buffer() {
+        let (data, channels, sample_rate) = SineWave::new(440.0)
+            .take_duration(Duration::from_millis(500))
+            .to_f32_buffer()
+            .unwrap();
+        assert_eq!((channels, sample_rate), (1, 48000));
+        assert_eq!(data.len(), 24000);
+        let expected: Vec<f32> = SineWave::new(440.0).take(24000).collect();
+        assert_eq!(data, expected);
+        let peak = data.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(peak > 0.9999 && peak <= 1.0);
+
+        let (data, _, _) = SamplesBuffer::new(2, 1000, vec![i16::MIN, i16::MAX])
+            .to_f32_buffer()
+            .unwrap();
+        assert_eq!(data, [-1.0, 1.0]);
+
+        let source = from_iter(vec![SamplesBuffer::new(1, 1000, vec![i16::MAX; 10])]);
+        assert_eq!(source.to_f32_buffer().unwrap().0, [1.0; 10]);
+
+        assert!(SineWave::new(440.0).to_f32_buffer().is_none());
     }
 }
@@ -0,0 +1,191 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::envelope::time_constant;
+use crate::{Sample, Source};
+
+/// Maximum gain applied to quiet passages, +20 dB.
+const MAX_GAIN: f32 = 10.0;
+
+/// Below this RMS the sound is considered silent and the gain is kept as it is.
+const SILENCE_RMS: f64 = 1e-4;
+
+/// Internal function that builds a `NormalizeStreaming` object.
+pub fn normalize_streaming<I>(input: I, target_rms: f32, window: Duration) -> NormalizeStreaming<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let samples_per_second = input.sample_rate() as f32 * input.channels() as f32;
+    let window_len = ((window.as_secs_f32() * samples_per_second) as usize).max(1);
+
+    NormalizeStreaming {
+        input,
+        target_rms,
+        window: VecDeque::with_capacity(window_len),
+        window_len,
+        sum_of_squares: 0.0,
+        smoothing: time_constant(window, samples_per_second),
+        gain: 1.0,
+    }
+}
+
+/// Filter that slowly adjusts the volume so that the RMS level of the sound approaches a target.
+///
+/// The level is measured over a rolling window, and the gain moves toward the one that reaches
+/// the target over about the same duration. The gain never goes above +20 dB, so that quiet
+/// passages aren't boosted too much.
+#[derive(Clone, Debug)]
+pub struct NormalizeStreaming<I> {
+    input: I,
+    target_rms: f32,
+    // Squares of the last samples.
+    window: VecDeque<f32>,
+    window_len: usize,
+    sum_of_squares: f64,
+    smoothing: f32,
+    gain: f32,
+}
+
+impl<I> NormalizeStreaming<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns the gain currently applied to the sound.
+    #[inline]
+    pub fn gain(&self) -> f32 {
+        self.gain
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for NormalizeStreaming<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+
+        let value = sample.to_f32();
+        let square = value * value;
+        self.window.push_back(square);
+        self.sum_of_squares += square as f64;
+        if self.window.len() > self.window_len {
+            if let Some(oldest) = self.window.pop_front() {
+                self.sum_of_squares -= oldest as f64;
+            }
+        }
+
+        // Rounding errors can make the sum slightly negative.
+        let rms = (self.sum_of_squares.max(0.0) / self.window.len() as f64).sqrt();
+        if rms > SILENCE_RMS {
+            let target = (self.target_rms / rms as f32).min(MAX_GAIN);
+            self.gain = target + (self.gain - target) * self.smoothing;
+        }
+
+        Some(sample.amplify(self.gain))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for NormalizeStreaming<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for NormalizeStreaming<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn rms(samples: &[f32]) -> f32 {
+        (samples.iter().map(|s| s * s).sum::<f32>() / samples.len() as f32).sqrt()
+    }
+
+    #[test]
+    fn converges_to_target() {
+        // Two seconds of a quiet tone followed by two seconds of a loud one.
+        let data: Vec<f32> = (0..16000)
+            .map(|i| {
+                let amplitude = if i < 8000 { 0.05 } else { 0.8 };
+                amplitude * (2.0 * PI * 100.0 * i as f32 / 4000.0).sin()
+            })
+            .collect();
+        let source = SamplesBuffer::new(1, 4000, data);
+
+        let output: Vec<f32> = source
+            .normalize_streaming(0.2, Duration::from_millis(200))
+            .collect();
+
+        assert!((rms(&output[7000..8000]) - 0.2).abs() < 0.02);
+        assert!((rms(&output[15000..16000]) - 0.2).abs() < 0.02);
+    }
+
+    #[test]
+    fn exposes_gain() {
+        let mut source = SamplesBuffer::new(1, 1000, vec![0.5f32; 2000])
+            .normalize_streaming(0.25, Duration::from_millis(100));
+        assert_eq!(source.gain(), 1.0);
+        source.by_ref().for_each(drop);
+        assert!((source.gain() - 0.5).abs() < 0.01);
+    }
+}
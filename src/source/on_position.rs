@@ -0,0 +1,195 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds an `OnPosition` object.
+pub fn on_position<I, F>(input: I, markers: &[Duration], callback: F) -> OnPosition<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(Duration),
+{
+    let mut markers = markers.to_vec();
+    markers.sort();
+
+    OnPosition {
+        input,
+        callback,
+        markers,
+        next_marker: 0,
+        samples_played: 0,
+        position: Duration::from_secs(0),
+    }
+}
+
+/// Calls a function every time the playback position reaches one of the markers.
+///
+/// The position is computed from the number of samples that have been read, and every marker is
+/// passed to the function exactly once, in increasing order.
+#[derive(Clone, Debug)]
+pub struct OnPosition<I, F> {
+    input: I,
+    callback: F,
+    // Sorted list of the markers.
+    markers: Vec<Duration>,
+    // Index in `markers` of the next marker to reach.
+    next_marker: usize,
+    // Number of samples read in the current frame.
+    samples_played: u64,
+    // Position at the start of the current frame.
+    position: Duration,
+}
+
+impl<I, F> OnPosition<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(Duration),
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I, F> Iterator for OnPosition<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(Duration),
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let samples_per_second = self.input.sample_rate() as u64 * self.input.channels() as u64;
+        let last_in_frame = self.input.current_frame_len() == Some(1);
+        let sample = self.input.next()?;
+
+        // Position of the start of the frame containing this sample.
+        let frame_index = self.samples_played - self.samples_played % self.input.channels() as u64;
+        let position = self.position
+            + Duration::from_nanos(frame_index * 1_000_000_000 / samples_per_second.max(1));
+        while let Some(&marker) = self.markers.get(self.next_marker) {
+            if marker > position {
+                break;
+            }
+            self.next_marker += 1;
+            (self.callback)(marker);
+        }
+
+        self.samples_played += 1;
+        if last_in_frame {
+            // The format can change after this frame, so the position is kept as a duration.
+            self.position += Duration::from_nanos(
+                self.samples_played * 1_000_000_000 / samples_per_second.max(1),
+            );
+            self.samples_played = 0;
+        }
+
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I, F> ExactSizeIterator for OnPosition<I, F>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+    F: FnMut(Duration),
+{
+}
+
+impl<I, F> Source for OnPosition<I, F>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnMut(Duration),
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn markers_fire_at_sample_counts() {
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        let fired_clone = fired.clone();
+        let markers = [Duration::from_millis(1000), Duration::from_millis(500)];
+        let mut source = SamplesBuffer::new(2, 1000, vec![0i16; 4000])
+            .on_position(&markers, move |marker| {
+                fired_clone.borrow_mut().push(marker)
+            });
+
+        let mut fired_at = Vec::new();
+        let mut samples = 0;
+        while source.next().is_some() {
+            samples += 1;
+            while fired_at.len() < fired.borrow().len() {
+                fired_at.push(samples);
+            }
+        }
+
+        assert_eq!(
+            *fired.borrow(),
+            vec![Duration::from_millis(500), Duration::from_millis(1000)]
+        );
+        // The markers fire when the first sample of the frame at that position is read.
+        assert_eq!(fired_at, vec![1001, 2001]);
+    }
+
+    #[test]
+    fn markers_past_the_end_never_fire() {
+        let fired = Rc::new(RefCell::new(0));
+        let fired_clone = fired.clone();
+        let source = SamplesBuffer::new(1, 1000, vec![0i16; 100])
+            .on_position(&[Duration::from_secs(1)], move |_| {
+                *fired_clone.borrow_mut() += 1
+            });
+
+        assert_eq!(source.count(), 100);
+        assert_eq!(*fired.borrow(), 0);
+    }
+}
@@ -0,0 +1,165 @@
+use std::time::Duration;
+
+use crate::source::UniformSourceIterator;
+use crate::{Sample, Source};
+
+/// Internal function that builds an `Oversampled` object.
+///
+/// # Panic
+///
+/// Panics if `factor` is zero.
+pub fn oversampled<I, F, O>(input: I, factor: u32, effect: F) -> Oversampled<O>
+where
+    I: Source,
+    I::Item: Sample,
+    F: FnOnce(UniformSourceIterator<I, f32>) -> O,
+    O: Source,
+    O::Item: Sample,
+{
+    assert!(factor >= 1, "the oversampling factor must be at least 1");
+
+    let channels = input.channels();
+    let sample_rate = input.sample_rate();
+    let upsampled = UniformSourceIterator::new(input, channels, sample_rate * factor);
+    Oversampled {
+        inner: UniformSourceIterator::new(effect(upsampled), channels, sample_rate),
+    }
+}
+
+/// A source that runs an effect at a multiple of the sample rate of its input.
+///
+/// The input is upsampled before the effect, and the output of the effect is downsampled back
+/// to the original sample rate with an anti-aliasing filter. This removes the harmonics that a
+/// nonlinear effect produces above the original Nyquist frequency, instead of letting them fold
+/// back as aliasing.
+#[derive(Clone)]
+pub struct Oversampled<O>
+where
+    O: Source,
+    O::Item: Sample,
+{
+    inner: UniformSourceIterator<O, f32>,
+}
+
+impl<O> Iterator for Oversampled<O>
+where
+    O: Source,
+    O::Item: Sample,
+{
+    type Item = f32;
+
+    #[inline]
+    fn next(&mut self) -> Option<f32> {
+        self.inner.next()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<O> Source for Oversampled<O>
+where
+    O: Source,
+    O::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.inner.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.inner.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.inner.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.inner.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    /// A hard-driven soft clipper, which produces strong odd harmonics.
+    struct SoftClip<I>(I);
+
+    impl<I> Iterator for SoftClip<I>
+    where
+        I: Iterator<Item = f32>,
+    {
+        type Item = f32;
+
+        fn next(&mut self) -> Option<f32> {
+            self.0.next().map(|sample| (4.0 * sample).tanh())
+        }
+    }
+
+    impl<I> Source for SoftClip<I>
+    where
+        I: Source<Item = f32>,
+    {
+        fn current_frame_len(&self) -> Option<usize> {
+            self.0.current_frame_len()
+        }
+
+        fn channels(&self) -> u16 {
+            self.0.channels()
+        }
+
+        fn sample_rate(&self) -> u32 {
+            self.0.sample_rate()
+        }
+
+        fn total_duration(&self) -> Option<Duration> {
+            self.0.total_duration()
+        }
+    }
+
+    /// Returns the amplitude of `freq` in `samples`, played at 48kHz.
+    fn amplitude(samples: &[f32], freq: f32) -> f32 {
+        let (re, im) = samples
+            .iter()
+            .enumerate()
+            .fold((0.0, 0.0), |(re, im), (n, sample)| {
+                let phase = 2.0 * PI * freq * n as f32 / 48000.0;
+                (re + sample * phase.cos(), im + sample * phase.sin())
+            });
+        2.0 * (re * re + im * im).sqrt() / samples.len() as f32
+    }
+
+    #[test]
+    fn less_aliasing() {
+        // The fifth harmonic of 7kHz, at 35kHz, folds back to 13kHz at 48kHz.
+        let tone: Vec<f32> = (0..4800)
+            .map(|n| 0.8 * (2.0 * PI * 7000.0 * n as f32 / 48000.0).sin())
+            .collect();
+
+        let plain: Vec<f32> = SoftClip(SamplesBuffer::new(1, 48000, tone.clone())).collect();
+        let oversampled = SamplesBuffer::new(1, 48000, tone).oversampled(4, SoftClip);
+        assert_eq!(oversampled.sample_rate(), 48000);
+        let oversampled: Vec<f32> = oversampled.collect();
+        assert_eq!(oversampled.len(), plain.len());
+
+        // The tone itself is kept.
+        let fundamental = amplitude(&plain[480..4320], 7000.0);
+        assert!((amplitude(&oversampled[480..4320], 7000.0) - fundamental).abs() < 0.1);
+
+        let plain_alias = amplitude(&plain[480..4320], 13000.0);
+        let oversampled_alias = amplitude(&oversampled[480..4320], 13000.0);
+        assert!(plain_alias > 0.05);
+        assert!(oversampled_alias < plain_alias / 10.0);
+    }
+}
@@ -0,0 +1,170 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `PadToMultiple` object.
+///
+/// # Panic
+///
+/// Panics if `multiple` is zero.
+pub fn pad_to_multiple<I>(input: I, multiple: usize) -> PadToMultiple<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(multiple >= 1, "the multiple must be at least 1");
+
+    PadToMultiple {
+        input,
+        multiple,
+        samples: 0,
+        ended: false,
+    }
+}
+
+/// Source that adds silence at the end of its inner source, so that the total number of samples
+/// it returns is a multiple of a given number.
+#[derive(Clone, Debug)]
+pub struct PadToMultiple<I> {
+    input: I,
+    multiple: usize,
+    // Number of samples returned so far, modulo `multiple`.
+    samples: usize,
+    // True once the inner source has ended.
+    ended: bool,
+}
+
+impl<I> PadToMultiple<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    /// Number of samples to add to `len` samples to reach a multiple.
+    #[inline]
+    fn padding(&self, len: usize) -> usize {
+        (self.multiple - (self.samples + len) % self.multiple) % self.multiple
+    }
+}
+
+impl<I> Iterator for PadToMultiple<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = if self.ended { None } else { self.input.next() };
+
+        let sample = match sample {
+            Some(sample) => sample,
+            None => {
+                self.ended = true;
+                if self.samples == 0 {
+                    return None;
+                }
+                I::Item::zero_value()
+            }
+        };
+
+        self.samples = (self.samples + 1) % self.multiple;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if self.ended {
+            let padding = self.padding(0);
+            return (padding, Some(padding));
+        }
+        let (min, max) = self.input.size_hint();
+        (
+            min.saturating_add(self.padding(min)),
+            max.and_then(|max| max.checked_add(self.padding(max))),
+        )
+    }
+}
+
+impl<I> Source for PadToMultiple<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        if self.ended {
+            return Some(self.padding(0));
+        }
+        match self.input.current_frame_len() {
+            Some(0) => Some(self.padding(0)),
+            len => len,
+        }
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let samples_per_sec = self.input.sample_rate() as f64 * self.input.channels() as f64;
+        self.input.total_duration().map(|duration| {
+            let samples = (duration.as_secs_f64() * samples_per_sec).round() as usize;
+            let samples = samples + (self.multiple - samples % self.multiple) % self.multiple;
+            Duration::from_secs_f64(samples as f64 / samples_per_sec)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn pads_to_multiple() {
+        let source = SamplesBuffer::new(1, 1000, vec![1i16; 100]).pad_to_multiple(64);
+        assert_eq!(source.size_hint(), (128, Some(128)));
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(128)));
+
+        let samples: Vec<i16> = source.collect();
+        assert_eq!(samples.len(), 128);
+        assert!(samples[..100].iter().all(|&value| value == 1));
+        assert!(samples[100..].iter().all(|&value| value == 0));
+    }
+
+    #[test]
+    fn already_aligned() {
+        let source = SamplesBuffer::new(2, 1000, vec![1i16; 128]).pad_to_multiple(64);
+        assert_eq!(source.count(), 128);
+        let source = SamplesBuffer::new(2, 1000, Vec::<i16>::new()).pad_to_multiple(64);
+        assert_eq!(source.count(), 0);
+    }
+}
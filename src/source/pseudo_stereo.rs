@@ -0,0 +1,186 @@
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Delay of the allpass filter that decorrelates the two channels.
+const ALLPASS_DELAY: Duration = Duration::from_millis(7);
+
+/// Feedback of the allpass filter.
+const ALLPASS_FEEDBACK: f32 = 0.5;
+
+/// How much of the decorrelated signal is added to one channel and removed from the other.
+const WIDTH: f32 = 0.3;
+
+/// Internal function that builds a `PseudoStereo` object.
+pub fn pseudo_stereo<I>(input: I) -> PseudoStereo<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let delay = ((ALLPASS_DELAY.as_secs_f32() * input.sample_rate() as f32) as usize).max(1);
+
+    PseudoStereo {
+        input,
+        input_history: vec![0.0; delay],
+        output_history: vec![0.0; delay],
+        position: 0,
+        right: None,
+    }
+}
+
+/// Filter that turns a mono sound into a stereo one with a subtle stereo image.
+///
+/// The sound is passed through an allpass filter, which keeps its spectrum but changes its phase.
+/// The result is added to the left channel and subtracted from the right one, so that the average
+/// of both channels is exactly the original sound. Sources with more than one channel are mixed
+/// down to mono first.
+#[derive(Clone, Debug)]
+pub struct PseudoStereo<I> {
+    input: I,
+    // Last inputs and outputs of the allpass filter.
+    input_history: Vec<f32>,
+    output_history: Vec<f32>,
+    position: usize,
+    // The right channel of the current frame, once the left one has been returned.
+    right: Option<f32>,
+}
+
+impl<I> PseudoStereo<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    /// Reads a frame of the inner source and mixes it down to mono.
+    fn next_mono(&mut self) -> Option<f32> {
+        let channels = self.input.channels().max(1);
+        let mut sum = self.input.next()?.to_f32();
+        for _ in 1..channels {
+            sum += self.input.next()?.to_f32();
+        }
+        Some(sum / channels as f32)
+    }
+}
+
+impl<I> Iterator for PseudoStereo<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if let Some(right) = self.right.take() {
+            return Some(CpalSample::from(&right));
+        }
+
+        let value = self.next_mono()?;
+
+        let delayed_input = self.input_history[self.position];
+        let delayed_output = self.output_history[self.position];
+        let decorrelated =
+            -ALLPASS_FEEDBACK * value + delayed_input + ALLPASS_FEEDBACK * delayed_output;
+        self.input_history[self.position] = value;
+        self.output_history[self.position] = decorrelated;
+        self.position = (self.position + 1) % self.input_history.len();
+
+        self.right = Some(value - WIDTH * decorrelated);
+        Some(CpalSample::from(&(value + WIDTH * decorrelated)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let channels = self.input.channels().max(1) as usize;
+        let pending = self.right.is_some() as usize;
+        let (min, max) = self.input.size_hint();
+        (
+            min / channels * 2 + pending,
+            max.map(|max| max / channels * 2 + pending),
+        )
+    }
+}
+
+impl<I> Source for PseudoStereo<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let channels = self.input.channels().max(1) as usize;
+        let pending = self.right.is_some() as usize;
+        self.input
+            .current_frame_len()
+            .map(|len| len / channels * 2 + pending)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn sums_back_to_mono() {
+        let data: Vec<f32> = (0..4410)
+            .map(|i| 0.5 * (2.0 * PI * 440.0 * i as f32 / 44100.0).sin())
+            .collect();
+        let source = SamplesBuffer::new(1, 44100, data.clone()).pseudo_stereo();
+        assert_eq!(source.channels(), 2);
+
+        let output: Vec<f32> = source.collect();
+        assert_eq!(output.len(), data.len() * 2);
+
+        let mut different = false;
+        for (frame, &original) in output.chunks(2).zip(&data) {
+            assert!(((frame[0] + frame[1]) / 2.0 - original).abs() < 1e-6);
+            different |= (frame[0] - frame[1]).abs() > 0.01;
+        }
+        assert!(different);
+    }
+
+    #[test]
+    fn stereo_input_is_mixed_down() {
+        let source = SamplesBuffer::new(2, 44100, vec![0.2f32, 0.4, 0.2, 0.4]).pseudo_stereo();
+        let output: Vec<f32> = source.collect();
+        assert_eq!(output.len(), 4);
+        assert!(((output[0] + output[1]) / 2.0 - 0.3).abs() < 1e-6);
+    }
+}
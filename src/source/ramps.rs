@@ -0,0 +1,166 @@
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Ramps` object.
+pub fn ramps<I>(input: I, fade_in: Duration, fade_out: Duration) -> Ramps<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let to_frames = |duration: Duration| duration.as_secs_f64() * input.sample_rate() as f64;
+    let total_frames = input.total_duration().map(|total| to_frames(total).round());
+
+    Ramps {
+        fade_in_frames: to_frames(fade_in),
+        fade_out_frames: to_frames(fade_out),
+        total_frames,
+        samples: 0,
+        input,
+    }
+}
+
+/// Filter that fades a sound in at its beginning and out at its end.
+///
+/// The fade out needs the total duration of the inner source. If it is unknown, only the fade in
+/// is applied.
+#[derive(Clone, Debug)]
+pub struct Ramps<I> {
+    input: I,
+    fade_in_frames: f64,
+    fade_out_frames: f64,
+    // Number of frames of the inner source, if known.
+    total_frames: Option<f64>,
+    // Number of samples returned so far.
+    samples: u64,
+}
+
+impl<I> Ramps<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Ramps<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let frame = (self.samples / self.input.channels().max(1) as u64) as f64;
+        let value = self.input.next()?;
+        self.samples += 1;
+
+        let mut factor = 1.0;
+        if frame < self.fade_in_frames {
+            factor = frame / self.fade_in_frames;
+        }
+        if let Some(total_frames) = self.total_frames {
+            // The last frame is silent.
+            let remaining = (total_frames - 1.0 - frame).max(0.0);
+            if remaining < self.fade_out_frames {
+                factor = factor.min(remaining / self.fade_out_frames);
+            }
+        }
+
+        if factor < 1.0 {
+            Some(value.amplify(factor as f32))
+        } else {
+            Some(value)
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Ramps<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Ramps<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn ramps_both_ends() {
+        let source = SamplesBuffer::new(2, 1000, vec![1.0f32; 2000]);
+        let samples: Vec<f32> = source
+            .ramps(Duration::from_millis(100), Duration::from_millis(200))
+            .collect();
+        let left: Vec<f32> = samples.iter().step_by(2).copied().collect();
+        assert_eq!(samples.len(), 2000);
+        assert_eq!(samples[0], samples[1]);
+
+        assert_eq!(left[0], 0.0);
+        assert!((left[50] - 0.5).abs() < 1e-6);
+        assert!(left[100..800].iter().all(|&value| value == 1.0));
+        assert!((left[899] - 0.5).abs() < 1e-6);
+        assert_eq!(left[999], 0.0);
+        assert!(left.windows(2).take(100).all(|w| w[1] > w[0]));
+        assert!(left.windows(2).skip(800).all(|w| w[1] < w[0]));
+    }
+
+    #[test]
+    fn unknown_duration() {
+        let source = SamplesBuffer::new(1, 1000, vec![1.0f32; 1000])
+            .repeat_infinite()
+            .ramps(Duration::from_millis(100), Duration::from_millis(200));
+        assert!(source.skip(100).take(10000).all(|value| value == 1.0));
+    }
+}
@@ -0,0 +1,215 @@
+use std::f32::consts::FRAC_1_SQRT_2;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::biquad::{Biquad, Coefficients};
+use crate::source::NotStereoError;
+use crate::{Sample, Source};
+
+/// Internal function that builds a `RemoveCenter` object.
+pub fn remove_center<I>(
+    input: I,
+    keep_bass_below: Option<u32>,
+) -> Result<RemoveCenter<I>, NotStereoError>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let channels = input.channels();
+    if channels != 2 {
+        return Err(NotStereoError { channels });
+    }
+
+    let bass = keep_bass_below.map(|freq| {
+        let sample_rate = input.sample_rate() as f32;
+        Biquad::new(Coefficients::low_pass(
+            freq as f32,
+            FRAC_1_SQRT_2,
+            sample_rate,
+        ))
+    });
+    Ok(RemoveCenter {
+        input,
+        bass,
+        right: None,
+    })
+}
+
+/// A source that removes what is in the center of a stereo sound, usually the vocals, to make a
+/// karaoke mix.
+///
+/// Both channels play the difference between the left and right channels of the inner source,
+/// so the result is mono and can be downmixed safely. The center of the low frequencies can be
+/// kept, since the bass is usually centered too.
+pub struct RemoveCenter<I> {
+    input: I,
+    // Keeps the center of the sound below the cutoff frequency.
+    bass: Option<Biquad>,
+    // The right sample of the current frame, until it is returned.
+    right: Option<f32>,
+}
+
+impl<I> RemoveCenter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for RemoveCenter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if let Some(right) = self.right.take() {
+            return Some(CpalSample::from(&right));
+        }
+
+        let left = self.input.next()?.to_f32();
+        let right = match self.input.next() {
+            Some(right) => right.to_f32(),
+            None => return Some(CpalSample::from(&left)),
+        };
+
+        let mut sample = (left - right) / 2.0;
+        if let Some(bass) = &mut self.bass {
+            sample += bass.process((left + right) / 2.0);
+        }
+        self.right = Some(sample);
+        Some(CpalSample::from(&sample))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let pending = self.right.is_some() as usize;
+        let (min, max) = self.input.size_hint();
+        (
+            min.saturating_add(pending),
+            max.and_then(|max| max.checked_add(pending)),
+        )
+    }
+}
+
+impl<I> ExactSizeIterator for RemoveCenter<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for RemoveCenter<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let pending = self.right.is_some() as usize;
+        self.input.current_frame_len().map(|len| len + pending)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        2
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{NotStereoError, Source};
+
+    fn sine(freq: f32, n: usize) -> f32 {
+        (2.0 * PI * freq * n as f32 / 48000.0).sin()
+    }
+
+    fn peak(samples: &[f32]) -> f32 {
+        samples
+            .iter()
+            .fold(0.0, |peak, sample| sample.abs().max(peak))
+    }
+
+    #[test]
+    fn cancels_center() {
+        // A voice in the center and a guitar on the left.
+        let data: Vec<f32> = (0..4800)
+            .flat_map(|n| {
+                let voice = 0.5 * sine(1000.0, n);
+                let guitar = 0.4 * sine(700.0, n);
+                [voice + guitar, voice]
+            })
+            .collect();
+        let output: Vec<f32> = SamplesBuffer::new(2, 48000, data)
+            .remove_center(None)
+            .unwrap()
+            .collect();
+
+        // Only the guitar remains, at half its level on both channels.
+        for (n, frame) in output.chunks(2).enumerate() {
+            let expected = 0.2 * sine(700.0, n);
+            assert!((frame[0] - expected).abs() < 1e-5);
+            assert_eq!(frame[0], frame[1]);
+        }
+    }
+
+    #[test]
+    fn keeps_bass() {
+        let data: Vec<f32> = (0..48000)
+            .flat_map(|n| {
+                let sample = 0.5 * sine(50.0, n) + 0.5 * sine(5000.0, n);
+                [sample, sample]
+            })
+            .collect();
+        let output: Vec<f32> = SamplesBuffer::new(2, 48000, data)
+            .remove_center(Some(500))
+            .unwrap()
+            .collect();
+
+        // The 50 Hz tone remains almost unchanged, while the 5 kHz one is mostly removed.
+        let peak = peak(&output[48000..]);
+        assert!(peak > 0.49 && peak < 0.52, "{}", peak);
+    }
+
+    #[test]
+    fn requires_stereo() {
+        let source = SamplesBuffer::new(1, 48000, vec![0.0f32; 10]);
+        assert_eq!(
+            source.remove_center(None).err(),
+            Some(NotStereoError { channels: 1 })
+        );
+    }
+}
@@ -84,6 +84,11 @@ where
     fn total_duration(&self) -> Option<Duration> {
         None
     }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        true
+    }
 }
 
 impl<I> Clone for Repeat<I>
@@ -99,3 +104,18 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn is_infinite() {
+        let source = SamplesBuffer::new(1, 44100, vec![1i16, 2, 3]);
+        assert!(source.is_finite());
+
+        let source = source.repeat_infinite();
+        assert!(source.is_infinite());
+    }
+}
@@ -0,0 +1,206 @@
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use crate::source::buffered::Buffered;
+use crate::{Sample, Source};
+
+/// Internal function that builds a `RepeatCrossfade` object.
+pub fn repeat_crossfade<I>(input: I, times: usize, fade: Duration) -> RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let channels = input.channels().max(1) as usize;
+    let frames = (fade.as_secs_f64() * input.sample_rate() as f64).round() as usize;
+    let input = input.buffered();
+
+    RepeatCrossfade {
+        current: input.clone(),
+        next: input,
+        times_left: times.saturating_sub(1),
+        infinite: false,
+        empty: times == 0,
+        fade,
+        fade_len: frames * channels,
+        channels,
+        tail: VecDeque::with_capacity(frames * channels + 1),
+        crossfade: None,
+    }
+}
+
+/// Builds a `RepeatCrossfade` object that repeats its source forever.
+pub(crate) fn repeat_crossfade_infinite<I>(input: I, fade: Duration) -> RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let mut source = repeat_crossfade(input, 1, fade);
+    source.infinite = true;
+    source
+}
+
+/// A source that plays the given source a number of times, crossfading the end of each
+/// iteration with the beginning of the next one.
+pub struct RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    current: Buffered<I>,
+    next: Buffered<I>,
+    // Number of iterations left to start after the current one.
+    times_left: usize,
+    // True if the source is repeated forever, in which case `times_left` isn't used.
+    infinite: bool,
+    // True if the source is played zero times.
+    empty: bool,
+    fade: Duration,
+    // Number of samples of the crossfade.
+    fade_len: usize,
+    channels: usize,
+    // The samples read in advance from the current iteration. Once it ends, these are the ones
+    // that are crossfaded with the next iteration.
+    tail: VecDeque<I::Item>,
+    // Number of samples of the running crossfade, and number of these returned so far.
+    crossfade: Option<(usize, usize)>,
+}
+
+impl<I> Iterator for RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.empty {
+            return None;
+        }
+
+        if let Some((len, position)) = self.crossfade {
+            // `current` is the new iteration and `tail` is the end of the previous one.
+            let fading_out = self.tail.pop_front()?;
+            let fading_in = self.current.next().unwrap_or_else(I::Item::zero_value);
+            let frames = (len / self.channels).max(1);
+            let factor = (position / self.channels) as f32 / frames as f32;
+
+            self.crossfade = if position + 1 < len {
+                Some((len, position + 1))
+            } else {
+                None
+            };
+            return Some(
+                fading_out
+                    .amplify(1.0 - factor)
+                    .saturating_add(fading_in.amplify(factor)),
+            );
+        }
+
+        while self.tail.len() <= self.fade_len {
+            match self.current.next() {
+                Some(sample) => self.tail.push_back(sample),
+                None => break,
+            }
+        }
+
+        if self.tail.len() > self.fade_len || (self.times_left == 0 && !self.infinite) {
+            return self.tail.pop_front();
+        }
+
+        // The iteration has ended: crossfade what is left of it with the next one.
+        if !self.infinite {
+            self.times_left -= 1;
+        }
+        self.current = self.next.clone();
+        if self.tail.is_empty() {
+            return self.current.next();
+        }
+        self.crossfade = Some((self.tail.len(), 0));
+        self.next()
+    }
+}
+
+impl<I> Source for RepeatCrossfade<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.current.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.current.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        if self.empty {
+            return Some(Duration::ZERO);
+        }
+        if self.infinite {
+            return None;
+        }
+        let times = self.times_left as u32 + 1;
+        let duration = self.next.total_duration()?;
+        let overlap = self.fade.min(duration) * (times - 1);
+        Some((duration * times).saturating_sub(overlap))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    fn ramp() -> SamplesBuffer<f32> {
+        let data: Vec<f32> = (0..1000).map(|i| i as f32 / 1000.0).collect();
+        SamplesBuffer::new(1, 1000, data)
+    }
+
+    #[test]
+    fn length_accounts_for_overlap() {
+        let source = ramp().repeat_crossfade(3, Duration::from_millis(100));
+        assert_eq!(source.total_duration(), Some(Duration::from_millis(2800)));
+        assert_eq!(source.count(), 3 * 1000 - 2 * 100);
+    }
+
+    #[test]
+    fn no_discontinuity_at_joins() {
+        let output: Vec<f32> = ramp()
+            .repeat_crossfade(3, Duration::from_millis(100))
+            .collect();
+        let max_step = output
+            .windows(2)
+            .map(|w| (w[1] - w[0]).abs())
+            .fold(0.0, f32::max);
+        // Without the crossfade, the ramp would jump from 1 to 0.
+        assert!(max_step < 0.02, "{}", max_step);
+        assert_eq!(output[0], 0.0);
+        assert_eq!(output[899], 0.899);
+    }
+
+    #[test]
+    fn zero_and_one_times() {
+        assert_eq!(
+            ramp()
+                .repeat_crossfade(0, Duration::from_millis(100))
+                .count(),
+            0
+        );
+        let output: Vec<f32> = ramp()
+            .repeat_crossfade(1, Duration::from_millis(100))
+            .collect();
+        assert_eq!(output, ramp().collect::<Vec<_>>());
+    }
+}
@@ -0,0 +1,155 @@
+use std::f32::consts::PI;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Loudness that ReplayGain 2.0 brings every track to, in LUFS.
+const REFERENCE_LOUDNESS: f32 = -18.0;
+
+/// Factor by which the sound is oversampled to find its true peak.
+const OVERSAMPLING: usize = 4;
+
+/// Number of samples on each side of the interpolated position used by the oversampling filter.
+const HALF_TAPS: usize = 12;
+
+/// ReplayGain values of a whole source, returned by [`scan_replaygain`].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ReplayGainInfo {
+    /// Gain to apply to bring the source to the ReplayGain reference loudness, in dB.
+    pub gain: f32,
+    /// Highest absolute value of the sound between its samples, as a linear amplitude where
+    /// `1.0` is full scale.
+    pub peak: f32,
+}
+
+/// Reads a whole source and computes its ReplayGain 2.0 track gain and peak.
+///
+/// The loudness is measured as defined by ITU-R BS.1770 and compared to the reference of -18
+/// LUFS. A silent source gets a gain of 0 dB. The peak is measured on the sound oversampled four
+/// times, which finds the peaks that fall between samples.
+///
+/// This consumes the source, so use `buffered` first if it must be played afterwards. The source
+/// must not be infinite.
+pub fn scan_replaygain<I>(source: I) -> ReplayGainInfo
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let filter = oversampling_filter();
+    let mut meter = source.lufs_meter();
+    let handle = meter.handle();
+
+    let mut peak = 0f32;
+    // The last samples of each channel, oldest first.
+    let mut history: Vec<Vec<f32>> = Vec::new();
+    let mut channel = 0;
+
+    loop {
+        if channel == 0 && history.len() != meter.channels() as usize {
+            history = vec![vec![0.0; 2 * HALF_TAPS]; meter.channels() as usize];
+        }
+        let sample = match meter.next() {
+            Some(sample) => sample.to_f32(),
+            None => break,
+        };
+
+        let samples = &mut history[channel];
+        samples.rotate_left(1);
+        samples[2 * HALF_TAPS - 1] = sample;
+        peak = peak.max(sample.abs());
+        for phase in &filter {
+            let value: f32 = samples.iter().zip(phase).map(|(s, c)| s * c).sum();
+            peak = peak.max(value.abs());
+        }
+
+        channel = (channel + 1) % history.len();
+    }
+
+    // The samples still in the filter once the source has ended.
+    for samples in history.iter_mut() {
+        for _ in 0..HALF_TAPS {
+            samples.rotate_left(1);
+            samples[2 * HALF_TAPS - 1] = 0.0;
+            for phase in &filter {
+                let value: f32 = samples.iter().zip(phase).map(|(s, c)| s * c).sum();
+                peak = peak.max(value.abs());
+            }
+        }
+    }
+
+    let loudness = handle.integrated();
+    let gain = if loudness.is_finite() {
+        REFERENCE_LOUDNESS - loudness
+    } else {
+        0.0
+    };
+
+    ReplayGainInfo { gain, peak }
+}
+
+/// Returns the coefficients that interpolate the sound between the two samples in the middle of
+/// `2 * HALF_TAPS` samples, for each of the positions added by the oversampling.
+fn oversampling_filter() -> Vec<Vec<f32>> {
+    (1..OVERSAMPLING)
+        .map(|phase| {
+            let position = (HALF_TAPS - 1) as f32 + phase as f32 / OVERSAMPLING as f32;
+            (0..2 * HALF_TAPS)
+                .map(|tap| {
+                    // A sinc with a Hann window.
+                    let x = position - tap as f32;
+                    let sinc = (PI * x).sin() / (PI * x);
+                    let window = 0.5 * (1.0 + (PI * x / HALF_TAPS as f32).cos());
+                    sinc * window
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use super::scan_replaygain;
+    use crate::buffer::SamplesBuffer;
+
+    fn sine(amplitude: f32, frequency: f32, phase: f32, seconds: usize) -> SamplesBuffer<f32> {
+        let data: Vec<f32> = (0..48000 * seconds)
+            .flat_map(|i| {
+                let value = amplitude * (2.0 * PI * frequency * i as f32 / 48000.0 + phase).sin();
+                [value, value]
+            })
+            .collect();
+        SamplesBuffer::new(2, 48000, data)
+    }
+
+    #[test]
+    fn reference_gain() {
+        // A stereo 1 kHz sine at -23 dBFS measures -23 LUFS, 5 dB below the reference.
+        let amplitude = 10f32.powf(-23.0 / 20.0);
+        let info = scan_replaygain(sine(amplitude, 1000.0, 0.0, 10));
+        assert!((info.gain - 5.0).abs() < 0.1, "{}", info.gain);
+        assert!((info.peak - amplitude).abs() < 1e-3);
+    }
+
+    #[test]
+    fn true_peak_between_samples() {
+        // At a quarter of the sample rate and shifted by 45 degrees, every sample is at 71% of
+        // the amplitude of the sine.
+        let info = scan_replaygain(sine(0.5, 12000.0, PI / 4.0, 1));
+        assert!((info.peak - 0.5).abs() < 0.01, "{}", info.peak);
+    }
+
+    #[test]
+    fn silence() {
+        let info = scan_replaygain(SamplesBuffer::new(1, 48000, vec![0i16; 48000]));
+        assert_eq!(
+            info,
+            super::ReplayGainInfo {
+                gain: 0.0,
+                peak: 0.0
+            }
+        );
+    }
+}
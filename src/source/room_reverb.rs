@@ -0,0 +1,389 @@
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+// Implemented following the public domain Freeverb by Jezar at Dreampoint.
+
+/// Delays of the comb filters, in samples at 44.1 kHz.
+const COMB_TUNINGS: [usize; 8] = [1116, 1188, 1277, 1356, 1422, 1491, 1557, 1617];
+/// Delays of the allpass filters, in samples at 44.1 kHz.
+const ALLPASS_TUNINGS: [usize; 4] = [556, 441, 341, 225];
+/// Extra delay of the right side, in samples at 44.1 kHz.
+const STEREO_SPREAD: usize = 23;
+const FIXED_GAIN: f32 = 0.015;
+const ALLPASS_FEEDBACK: f32 = 0.5;
+
+/// Internal function that builds a `RoomReverb` object.
+pub fn room_reverb<I>(input: I, room_size: f32, damping: f32, wet: f32, width: f32) -> RoomReverb<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let scale = input.sample_rate() as f32 / 44100.0;
+    let scaled = |samples: usize| ((samples as f32 * scale) as usize).max(1);
+    let build_side = |spread: usize| Tank {
+        combs: COMB_TUNINGS
+            .iter()
+            .map(|&delay| Comb::new(scaled(delay + spread)))
+            .collect(),
+        allpasses: ALLPASS_TUNINGS
+            .iter()
+            .map(|&delay| Allpass::new(scaled(delay + spread)))
+            .collect(),
+    };
+
+    let handle = RoomReverbHandle {
+        params: Arc::new([
+            AtomicU32::new(0),
+            AtomicU32::new(0),
+            AtomicU32::new(0),
+            AtomicU32::new(0),
+        ]),
+    };
+    handle.set_room_size(room_size);
+    handle.set_damping(damping);
+    handle.set_wet(wet);
+    handle.set_width(width);
+
+    let channels = input.channels() as usize;
+    RoomReverb {
+        input,
+        handle,
+        left: build_side(0),
+        right: build_side(STEREO_SPREAD),
+        frame: Vec::with_capacity(channels),
+        position: 0,
+    }
+}
+
+/// A Freeverb-like reverb, built from comb and allpass filters.
+///
+/// All the channels of a frame are mixed together and fed to two reverb tanks, one for even
+/// channels and one for odd channels.
+#[derive(Clone, Debug)]
+pub struct RoomReverb<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    handle: RoomReverbHandle,
+    left: Tank,
+    right: Tank,
+    // The current frame, with the reverb already applied.
+    frame: Vec<I::Item>,
+    // Position of the next output sample inside `frame`.
+    position: usize,
+}
+
+/// Changes the parameters of a `RoomReverb` while it is playing.
+///
+/// This can be cloned and sent to another thread.
+#[derive(Clone, Debug)]
+pub struct RoomReverbHandle {
+    // The room size, damping, wet level and width, stored as the bits of an `f32`.
+    params: Arc<[AtomicU32; 4]>,
+}
+
+impl RoomReverbHandle {
+    #[inline]
+    fn get(&self, index: usize) -> f32 {
+        f32::from_bits(self.params[index].load(Ordering::Relaxed))
+    }
+
+    #[inline]
+    fn set(&self, index: usize, value: f32) {
+        self.params[index].store(value.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    /// Returns the size of the room, between 0 and 1.
+    #[inline]
+    pub fn room_size(&self) -> f32 {
+        self.get(0)
+    }
+
+    /// Sets the size of the room, between 0 and 1. Bigger rooms have longer tails.
+    #[inline]
+    pub fn set_room_size(&self, room_size: f32) {
+        self.set(0, room_size);
+    }
+
+    /// Returns how much the high frequencies are damped, between 0 and 1.
+    #[inline]
+    pub fn damping(&self) -> f32 {
+        self.get(1)
+    }
+
+    /// Sets how much the high frequencies are damped, between 0 and 1.
+    #[inline]
+    pub fn set_damping(&self, damping: f32) {
+        self.set(1, damping);
+    }
+
+    /// Returns the level of the reverb, between 0 and 1.
+    #[inline]
+    pub fn wet(&self) -> f32 {
+        self.get(2)
+    }
+
+    /// Sets the level of the reverb, between 0 and 1. The original sound is played at `1 - wet`.
+    #[inline]
+    pub fn set_wet(&self, wet: f32) {
+        self.set(2, wet);
+    }
+
+    /// Returns the stereo width of the reverb, between 0 and 1.
+    #[inline]
+    pub fn width(&self) -> f32 {
+        self.get(3)
+    }
+
+    /// Sets the stereo width of the reverb, between 0 and 1.
+    #[inline]
+    pub fn set_width(&self, width: f32) {
+        self.set(3, width);
+    }
+}
+
+impl<I> RoomReverb<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a handle that changes the parameters of the reverb.
+    #[inline]
+    pub fn handle(&self) -> RoomReverbHandle {
+        self.handle.clone()
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for RoomReverb<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.position >= self.frame.len() {
+            let channels = self.input.channels() as usize;
+            self.frame.clear();
+            self.frame.extend(self.input.by_ref().take(channels));
+            if self.frame.is_empty() {
+                return None;
+            }
+            self.position = 0;
+
+            let feedback = self.handle.room_size() * 0.28 + 0.7;
+            let damping = self.handle.damping() * 0.4;
+            let wet = self.handle.wet();
+            let width = self.handle.width();
+            let wet1 = wet * (width / 2.0 + 0.5);
+            let wet2 = wet * ((1.0 - width) / 2.0);
+            let dry = 1.0 - wet;
+
+            let mono: f32 = self.frame.iter().map(|s| s.to_f32()).sum::<f32>() * FIXED_GAIN;
+            let out_left = self.left.process(mono, feedback, damping);
+            let out_right = self.right.process(mono, feedback, damping);
+            let left = out_left * wet1 + out_right * wet2;
+            let right = out_right * wet1 + out_left * wet2;
+
+            let single_channel = self.frame.len() == 1;
+            for (channel, sample) in self.frame.iter_mut().enumerate() {
+                let reverb = if single_channel {
+                    (left + right) / 2.0
+                } else if channel % 2 == 0 {
+                    left
+                } else {
+                    right
+                };
+                *sample = CpalSample::from(&(sample.to_f32() * dry + reverb));
+            }
+        }
+
+        let sample = self.frame[self.position];
+        self.position += 1;
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let buffered = self.frame.len() - self.position;
+        let (min, max) = self.input.size_hint();
+        (min + buffered, max.map(|max| max + buffered))
+    }
+}
+
+impl<I> Source for RoomReverb<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        // The rest of `frame` has already been read from the inner source.
+        let buffered = self.frame.len() - self.position;
+        self.input.current_frame_len().map(|len| len + buffered)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+/// Comb filters in parallel followed by allpass filters in series.
+#[derive(Clone, Debug)]
+struct Tank {
+    combs: Vec<Comb>,
+    allpasses: Vec<Allpass>,
+}
+
+impl Tank {
+    #[inline]
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let mut output: f32 = self
+            .combs
+            .iter_mut()
+            .map(|comb| comb.process(input, feedback, damping))
+            .sum();
+        for allpass in &mut self.allpasses {
+            output = allpass.process(output);
+        }
+        output
+    }
+}
+
+/// Feedback comb filter with a low-pass filter in the feedback loop.
+#[derive(Clone, Debug)]
+struct Comb {
+    buffer: Vec<f32>,
+    index: usize,
+    filter_store: f32,
+}
+
+impl Comb {
+    fn new(delay: usize) -> Comb {
+        Comb {
+            buffer: vec![0.0; delay],
+            index: 0,
+            filter_store: 0.0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, input: f32, feedback: f32, damping: f32) -> f32 {
+        let output = self.buffer[self.index];
+        self.filter_store = output * (1.0 - damping) + self.filter_store * damping;
+        self.buffer[self.index] = input + self.filter_store * feedback;
+        self.index = (self.index + 1) % self.buffer.len();
+        output
+    }
+}
+
+/// Schroeder allpass filter.
+#[derive(Clone, Debug)]
+struct Allpass {
+    buffer: Vec<f32>,
+    index: usize,
+}
+
+impl Allpass {
+    fn new(delay: usize) -> Allpass {
+        Allpass {
+            buffer: vec![0.0; delay],
+            index: 0,
+        }
+    }
+
+    #[inline]
+    fn process(&mut self, input: f32) -> f32 {
+        let delayed = self.buffer[self.index];
+        self.buffer[self.index] = input + delayed * ALLPASS_FEEDBACK;
+        self.index = (self.index + 1) % self.buffer.len();
+        delayed - input
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    /// Index of the last sample of the impulse response that is still audible.
+    fn tail_length(room_size: f32) -> usize {
+        let mut data = vec![0.0f32; 3 * 44100];
+        data[0] = 1.0;
+        let output: Vec<f32> = SamplesBuffer::new(1, 44100, data)
+            .room_reverb(room_size, 0.5, 1.0, 1.0)
+            .collect();
+        output.iter().rposition(|s| s.abs() > 1e-4).unwrap()
+    }
+
+    #[test]
+    fn frame_len_includes_buffered_samples() {
+        let input =
+            SamplesBuffer::new(2, 1000, vec![0.0f32; 100]).take_duration(Duration::from_millis(10));
+        let mut source = input.room_reverb(0.5, 0.5, 1.0, 1.0);
+        for _ in 0..10 {
+            let len = source.current_frame_len().unwrap();
+            source.next();
+            assert_eq!(source.current_frame_len(), Some(len - 1));
+        }
+    }
+
+    #[test]
+    fn bigger_room_has_longer_tail() {
+        assert!(tail_length(0.9) > tail_length(0.3));
+    }
+
+    #[test]
+    fn handle_changes_parameters() {
+        let source = SamplesBuffer::new(2, 44100, vec![0.0f32; 4]).room_reverb(0.5, 0.5, 0.3, 1.0);
+        let handle = source.handle();
+        handle.set_wet(0.0);
+        handle.set_room_size(2.0);
+        assert_eq!(handle.room_size(), 1.0);
+
+        // Without any reverb the sound is unchanged.
+        let input = SamplesBuffer::new(2, 44100, vec![0.5f32, -0.5, 0.25, 0.0]);
+        let source = input.room_reverb(0.5, 0.5, 0.3, 1.0);
+        source.handle().set_wet(0.0);
+        assert_eq!(source.collect::<Vec<_>>(), vec![0.5, -0.5, 0.25, 0.0]);
+    }
+}
@@ -0,0 +1,115 @@
+use std::marker::PhantomData;
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// A source that produces silence for a given duration.
+///
+/// The duration is rounded to the nearest frame.
+#[derive(Clone, Debug)]
+pub struct Silence<S> {
+    channels: u16,
+    sample_rate: u32,
+    // Number of samples left to produce.
+    remaining: usize,
+    marker: PhantomData<S>,
+}
+
+impl<S> Silence<S> {
+    /// Builds a new `Silence` that lasts for `duration`.
+    ///
+    /// # Panic
+    ///
+    /// Panics if `channels` or `sample_rate` is zero.
+    #[inline]
+    pub fn new(channels: u16, sample_rate: u32, duration: Duration) -> Silence<S> {
+        assert!(channels >= 1);
+        assert!(sample_rate >= 1);
+
+        let frames = (duration.as_secs_f64() * sample_rate as f64).round() as usize;
+        Silence {
+            channels,
+            sample_rate,
+            remaining: frames * channels as usize,
+            marker: PhantomData,
+        }
+    }
+}
+
+impl<S> Iterator for Silence<S>
+where
+    S: Sample,
+{
+    type Item = S;
+
+    #[inline]
+    fn next(&mut self) -> Option<S> {
+        if self.remaining == 0 {
+            return None;
+        }
+        self.remaining -= 1;
+        Some(S::zero_value())
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.remaining, Some(self.remaining))
+    }
+}
+
+impl<S> ExactSizeIterator for Silence<S> where S: Sample {}
+
+impl<S> Source for Silence<S>
+where
+    S: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        Some(self.remaining)
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.channels
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.sample_rate
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        let frames = (self.remaining / self.channels as usize) as u64;
+        let secs = frames / self.sample_rate as u64;
+        let nanos = (frames % self.sample_rate as u64) * 1_000_000_000 / self.sample_rate as u64;
+        Some(Duration::new(secs, nanos as u32))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::source::{Silence, Source};
+
+    #[test]
+    fn exact_length() {
+        let mut silence = Silence::<i16>::new(2, 44100, Duration::from_secs(3));
+        assert_eq!(silence.total_duration(), Some(Duration::from_secs(3)));
+        assert_eq!(silence.current_frame_len(), Some(44100 * 2 * 3));
+
+        let samples: Vec<i16> = silence.by_ref().collect();
+        assert_eq!(samples.len(), 44100 * 2 * 3);
+        assert!(samples.iter().all(|&s| s == 0));
+        assert_eq!(silence.next(), None);
+        assert_eq!(silence.total_duration(), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn rounds_to_frames() {
+        let silence = Silence::<f32>::new(1, 1000, Duration::from_micros(2600));
+        assert_eq!(silence.len(), 3);
+        assert_eq!(silence.total_duration(), Some(Duration::from_millis(3)));
+    }
+}
@@ -55,4 +55,9 @@ impl Source for SineWave {
     fn total_duration(&self) -> Option<Duration> {
         None
     }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        true
+    }
 }
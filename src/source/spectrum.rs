@@ -0,0 +1,317 @@
+use std::f32::consts::PI;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::WindowFn;
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Spectrum` object.
+///
+/// # Panic
+///
+/// Panics if `fft_size` isn't a power of two or is smaller than 2.
+pub fn spectrum<I>(input: I, fft_size: usize, window: WindowFn) -> Spectrum<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(
+        fft_size >= 2 && fft_size.is_power_of_two(),
+        "the FFT size must be a power of two"
+    );
+
+    let window: Vec<f32> = (0..fft_size)
+        .map(|n| window.coefficient(n, fft_size))
+        .collect();
+    let handle = SpectrumHandle {
+        magnitudes: Arc::new(Mutex::new(vec![0.0; fft_size / 2 + 1])),
+        sample_rate: input.sample_rate(),
+        fft_size,
+    };
+
+    Spectrum {
+        input,
+        handle,
+        window,
+        samples: Vec::with_capacity(fft_size),
+        re: vec![0.0; fft_size],
+        im: vec![0.0; fft_size],
+        magnitudes: vec![0.0; fft_size / 2 + 1],
+        frame_sum: 0.0,
+        current_channel: 0,
+    }
+}
+
+/// Gives access to the latest spectrum computed by a `Spectrum` source.
+///
+/// This can be cloned and sent to another thread.
+#[derive(Clone, Debug)]
+pub struct SpectrumHandle {
+    magnitudes: Arc<Mutex<Vec<f32>>>,
+    sample_rate: u32,
+    fft_size: usize,
+}
+
+impl SpectrumHandle {
+    /// Returns the magnitude of each frequency bin, from 0 Hz up to half the sample rate.
+    ///
+    /// There are `fft_size / 2 + 1` bins. A full scale sine wave has a magnitude of about 1.
+    #[inline]
+    pub fn magnitudes(&self) -> Vec<f32> {
+        self.magnitudes.lock().unwrap().clone()
+    }
+
+    /// Returns the frequency in Hz at the center of a bin.
+    #[inline]
+    pub fn bin_frequency(&self, bin: usize) -> f32 {
+        bin as f32 * self.sample_rate as f32 / self.fft_size as f32
+    }
+}
+
+/// A source that computes the spectrum of the sound it plays.
+///
+/// The channels are mixed together, and a new spectrum is computed every `fft_size / 2`
+/// frames over the last `fft_size` frames. The samples themselves are passed through unchanged.
+#[derive(Clone, Debug)]
+pub struct Spectrum<I> {
+    input: I,
+    handle: SpectrumHandle,
+    window: Vec<f32>,
+    // The last frames, mixed to mono.
+    samples: Vec<f32>,
+    // Scratch buffers for the FFT and its result, so that nothing is allocated while playing.
+    re: Vec<f32>,
+    im: Vec<f32>,
+    magnitudes: Vec<f32>,
+    // Sum of the samples of the current frame.
+    frame_sum: f32,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+}
+
+impl<I> Spectrum<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a handle that gives access to the latest spectrum.
+    #[inline]
+    pub fn handle(&self) -> SpectrumHandle {
+        self.handle.clone()
+    }
+
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+
+    fn update_spectrum(&mut self) {
+        let size = self.window.len();
+        for ((re, sample), window) in self.re.iter_mut().zip(&self.samples).zip(&self.window) {
+            *re = sample * window;
+        }
+        self.im.iter_mut().for_each(|im| *im = 0.0);
+        fft(&mut self.re, &mut self.im);
+
+        // Scale so that a full scale sine wave has a magnitude of 1.
+        let scale = 2.0 / self.window.iter().sum::<f32>();
+        for (bin, magnitude) in self.magnitudes.iter_mut().enumerate() {
+            let (re, im) = (self.re[bin], self.im[bin]);
+            *magnitude = (re * re + im * im).sqrt() * scale;
+        }
+
+        // The audio thread doesn't wait for the handle. If it is being read, this spectrum is
+        // skipped and the next one is published instead.
+        if let Ok(mut magnitudes) = self.handle.magnitudes.try_lock() {
+            magnitudes.copy_from_slice(&self.magnitudes);
+        }
+
+        // Keep the second half for the next spectrum.
+        self.samples.drain(..size / 2);
+    }
+}
+
+/// In-place iterative radix-2 FFT. The length must be a power of two.
+pub(crate) fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let a = start + k;
+                let b = a + len / 2;
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
+
+impl<I> Iterator for Spectrum<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+
+        let channels = self.input.channels().max(1);
+        self.frame_sum += sample.to_f32();
+        self.current_channel += 1;
+        if self.current_channel >= channels {
+            self.samples.push(self.frame_sum / channels as f32);
+            self.frame_sum = 0.0;
+            self.current_channel = 0;
+            if self.samples.len() == self.window.len() {
+                self.update_spectrum();
+            }
+        }
+
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Spectrum<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Spectrum<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+    use crate::source::WindowFn;
+
+    #[test]
+    fn tone_has_dominant_bin() {
+        let data: Vec<f32> = (0..8000)
+            .map(|i| (2.0 * PI * 1000.0 * i as f32 / 8000.0).sin())
+            .collect();
+        let source = SamplesBuffer::new(1, 8000, data.clone()).spectrum(256);
+        let handle = source.handle();
+
+        // The audio is passed through unchanged.
+        assert_eq!(source.collect::<Vec<_>>(), data);
+
+        let magnitudes = handle.magnitudes();
+        assert_eq!(magnitudes.len(), 129);
+        let (peak, magnitude) =
+            magnitudes.iter().enumerate().fold(
+                (0, 0.0),
+                |best, (bin, &m)| if m > best.1 { (bin, m) } else { best },
+            );
+        assert_eq!(handle.bin_frequency(peak), 1000.0);
+        assert!((magnitude - 1.0).abs() < 0.05);
+    }
+
+    #[test]
+    fn other_window() {
+        let data: Vec<f32> = (0..1024)
+            .map(|i| (2.0 * PI * 500.0 * i as f32 / 8000.0).sin())
+            .collect();
+        let source = SamplesBuffer::new(1, 8000, data).spectrum_with_window(64, WindowFn::Blackman);
+        let handle = source.handle();
+        source.for_each(drop);
+
+        let magnitudes = handle.magnitudes();
+        assert!(magnitudes[4] > 0.9);
+        assert!(magnitudes[20] < 0.01);
+    }
+
+    #[test]
+    fn rectangular_window() {
+        let data: Vec<f32> = (0..1024)
+            .map(|i| (2.0 * PI * 500.0 * i as f32 / 8000.0).sin())
+            .collect();
+        let source =
+            SamplesBuffer::new(1, 8000, data).spectrum_with_window(64, WindowFn::Rectangular);
+        let handle = source.handle();
+        source.for_each(drop);
+
+        // The tone is exactly on a bin, so nothing leaks to the other ones.
+        let magnitudes = handle.magnitudes();
+        assert!((magnitudes[4] - 1.0).abs() < 0.01);
+        assert!(magnitudes[5] < 0.01);
+    }
+
+    #[test]
+    #[should_panic]
+    fn size_not_power_of_two() {
+        SamplesBuffer::new(1, 8000, vec![0.0f32]).spectrum(100);
+    }
+}
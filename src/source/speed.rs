@@ -91,16 +91,34 @@ where
 
     #[inline]
     fn total_duration(&self) -> Option<Duration> {
-        // TODO: the crappy API of duration makes this code difficult to write
-        if let Some(duration) = self.input.total_duration() {
-            let as_ns = duration.as_secs() * 1000000000 + duration.subsec_nanos() as u64;
-            let new_val = (as_ns as f32 / self.factor) as u64;
-            Some(Duration::new(
-                new_val / 1000000000,
-                (new_val % 1000000000) as u32,
-            ))
-        } else {
-            None
-        }
+        // The number of samples doesn't change, so `current_frame_len` is left untouched; only
+        // the time they take to play does.
+        self.input
+            .total_duration()
+            .map(|duration| duration.div_f64(self.factor as f64))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn duration_is_scaled() {
+        let input = SamplesBuffer::new(2, 44100, vec![0i16; 44100 * 2 * 10]);
+        let source = input.speed(2.0);
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(5)));
+        assert_eq!(source.sample_rate(), 88200);
+        assert_eq!(source.count(), 44100 * 2 * 10);
+    }
+
+    #[test]
+    fn slower_duration() {
+        let input = SamplesBuffer::new(1, 1000, vec![0i16; 3000]);
+        let source = input.speed(0.5);
+        assert_eq!(source.total_duration(), Some(Duration::from_secs(6)));
     }
 }
@@ -0,0 +1,196 @@
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Internal function that builds a `StopOnSilence` object.
+pub fn stop_on_silence<I>(input: I, threshold_db: f32, duration: Duration) -> StopOnSilence<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let frames = duration.as_secs_f64() * input.sample_rate() as f64;
+
+    StopOnSilence {
+        input,
+        threshold: 10f32.powf(threshold_db / 20.0),
+        max_silent_frames: frames.round() as u64,
+        silent_frames: 0,
+        frame_channels: 0,
+        current_channel: 0,
+        frame_silent: true,
+    }
+}
+
+/// A source that ends once the given source has been silent for a certain duration.
+///
+/// A frame is silent when all of its channels are. The source always ends on a frame boundary.
+#[derive(Clone, Debug)]
+pub struct StopOnSilence<I> {
+    input: I,
+    // Linear amplitude below which a sample is considered silent.
+    threshold: f32,
+    max_silent_frames: u64,
+    // Number of consecutive silent frames returned so far.
+    silent_frames: u64,
+    // Number of channels of the frame being returned.
+    frame_channels: u16,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+    // Whether all the samples of the current frame so far are silent.
+    frame_silent: bool,
+}
+
+impl<I> StopOnSilence<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for StopOnSilence<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        if self.current_channel == 0 {
+            if self.silent_frames >= self.max_silent_frames {
+                return None;
+            }
+            self.frame_channels = self.input.channels().max(1);
+            self.frame_silent = true;
+        }
+
+        let sample = self.input.next()?;
+        if sample.to_f32().abs() >= self.threshold {
+            self.frame_silent = false;
+        }
+
+        self.current_channel += 1;
+        if self.current_channel >= self.frame_channels {
+            self.current_channel = 0;
+            if self.frame_silent {
+                self.silent_frames += 1;
+            } else {
+                self.silent_frames = 0;
+            }
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.input.size_hint().1)
+    }
+}
+
+impl<I> Source for StopOnSilence<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        self.input.is_infinite()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn ends_after_silence() {
+        let mut data = vec![0.5f32; 100];
+        // A dip shorter than the duration must not stop the source.
+        data.extend(vec![0.0f32; 30]);
+        data.extend(vec![0.5f32; 100]);
+        data.extend(vec![0.0f32; 200]);
+        let input = SamplesBuffer::new(1, 1000, data);
+
+        let output: Vec<f32> = input
+            .stop_on_silence(-40.0, Duration::from_millis(50))
+            .collect();
+
+        assert_eq!(output.len(), 100 + 30 + 100 + 50);
+    }
+
+    #[test]
+    fn plays_loud_source_until_end() {
+        let input = SamplesBuffer::new(2, 1000, vec![0.5f32; 100]);
+        let output: Vec<f32> = input
+            .stop_on_silence(-40.0, Duration::from_millis(10))
+            .collect();
+        assert_eq!(output.len(), 100);
+    }
+
+    #[test]
+    fn ends_on_frame_boundary() {
+        let mut data = vec![0.5f32; 20];
+        data.extend(vec![0.0f32; 100]);
+        let input = SamplesBuffer::new(2, 1000, data);
+
+        // 2.5 ms of stereo is 5 samples, which is rounded to 3 whole frames.
+        let output: Vec<f32> = input
+            .stop_on_silence(-40.0, Duration::from_micros(2500))
+            .collect();
+        assert_eq!(output.len(), 20 + 6);
+    }
+
+    #[test]
+    fn frame_with_one_loud_channel_is_not_silent() {
+        let mut data: Vec<f32> = (0..50).flat_map(|_| [0.0, 0.5]).collect();
+        data.extend(vec![0.0f32; 20]);
+        let input = SamplesBuffer::new(2, 1000, data);
+
+        let output: Vec<f32> = input
+            .stop_on_silence(-40.0, Duration::from_millis(5))
+            .collect();
+        assert_eq!(output.len(), 100 + 10);
+    }
+}
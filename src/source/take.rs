@@ -176,4 +176,10 @@ where
             None
         }
     }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        // Ends after the requested duration at the latest.
+        false
+    }
 }
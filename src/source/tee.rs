@@ -0,0 +1,212 @@
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::{Sample, Source};
+
+/// Maximum number of frames kept for the half that lags behind.
+const MAX_BUFFERED_FRAMES: usize = 1 << 16;
+
+/// Internal function that builds a pair of `Tee` objects.
+pub fn tee<I>(input: I) -> (Tee<I>, Tee<I>)
+where
+    I: Source,
+    I::Item: Sample,
+{
+    tee_with_capacity(input, MAX_BUFFERED_FRAMES)
+}
+
+fn tee_with_capacity<I>(input: I, capacity: usize) -> (Tee<I>, Tee<I>)
+where
+    I: Source,
+    I::Item: Sample,
+{
+    let shared = Arc::new(Mutex::new(Shared {
+        input,
+        buffer: VecDeque::new(),
+        buffer_side: 0,
+        capacity,
+        alive: [true, true],
+    }));
+
+    let first = Tee {
+        shared: shared.clone(),
+        side: 0,
+    };
+    let second = Tee { shared, side: 1 };
+    (first, second)
+}
+
+struct Shared<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    input: I,
+    // Samples already returned to one half but not yet to the other one.
+    buffer: VecDeque<I::Item>,
+    // The half that `buffer` is waiting for.
+    buffer_side: usize,
+    // Maximum number of frames in `buffer`.
+    capacity: usize,
+    // Whether each half still exists.
+    alive: [bool; 2],
+}
+
+/// One of the two halves of a split source.
+///
+/// Both halves produce the same samples. Samples read by one half are kept until the other half
+/// reads them as well. If one half lags behind by more than 65536 frames, which is about 1.4
+/// seconds at 48 kHz, its oldest frames are dropped, so that the buffer doesn't grow forever.
+pub struct Tee<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    shared: Arc<Mutex<Shared<I>>>,
+    side: usize,
+}
+
+impl<I> Iterator for Tee<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let mut shared = self.shared.lock().unwrap();
+
+        if shared.buffer_side == self.side {
+            if let Some(sample) = shared.buffer.pop_front() {
+                return Some(sample);
+            }
+        }
+
+        let sample = shared.input.next()?;
+        let other = 1 - self.side;
+        if shared.alive[other] {
+            shared.buffer_side = other;
+            shared.buffer.push_back(sample);
+            // Whole frames are dropped so that the channels stay in place.
+            let channels = shared.input.channels().max(1) as usize;
+            if shared.buffer.len() > shared.capacity * channels {
+                shared.buffer.drain(..channels);
+            }
+        }
+        Some(sample)
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let shared = self.shared.lock().unwrap();
+        let buffered = if shared.buffer_side == self.side {
+            shared.buffer.len()
+        } else {
+            0
+        };
+        let (min, max) = shared.input.size_hint();
+        (min + buffered, max.map(|max| max + buffered))
+    }
+}
+
+impl<I> Source for Tee<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        let shared = self.shared.lock().unwrap();
+        if shared.buffer_side == self.side && !shared.buffer.is_empty() {
+            return Some(shared.buffer.len());
+        }
+        shared.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.shared.lock().unwrap().input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.shared.lock().unwrap().input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.shared.lock().unwrap().input.total_duration()
+    }
+}
+
+impl<I> Drop for Tee<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    fn drop(&mut self) {
+        if let Ok(mut shared) = self.shared.lock() {
+            shared.alive[self.side] = false;
+            if shared.buffer_side == self.side {
+                shared.buffer.clear();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::tee_with_capacity;
+    use crate::buffer::SamplesBuffer;
+    use crate::source::Source;
+
+    #[test]
+    fn both_halves_are_identical() {
+        let data = vec![1i16, 2, 3, 4, 5, 6, 7, 8];
+        let (mut first, mut second) = SamplesBuffer::new(2, 44100, data.clone()).split();
+
+        // Interleave the reads a bit before reading the rest.
+        assert_eq!(first.next(), Some(1));
+        assert_eq!(first.next(), Some(2));
+        assert_eq!(second.next(), Some(1));
+
+        let mut first_output = vec![1, 2];
+        first_output.extend(first.by_ref());
+        let mut second_output = vec![1];
+        second_output.extend(second.by_ref());
+
+        assert_eq!(first_output, data);
+        assert_eq!(second_output, data);
+        assert_eq!(first.channels(), 2);
+    }
+
+    #[test]
+    fn lagging_half_is_bounded() {
+        let input = SamplesBuffer::new(1, 44100, vec![1i16, 2, 3, 4, 5, 6]);
+        let (first, second) = tee_with_capacity(input, 2);
+
+        assert_eq!(first.collect::<Vec<_>>(), vec![1, 2, 3, 4, 5, 6]);
+        assert_eq!(second.collect::<Vec<_>>(), vec![5, 6]);
+    }
+
+    #[test]
+    fn lagging_half_drops_frames() {
+        let input = SamplesBuffer::new(3, 44100, vec![1i16, 2, 3, 4, 5, 6, 7, 8, 9]);
+        let (first, second) = tee_with_capacity(input, 2);
+
+        assert_eq!(first.count(), 9);
+        assert_eq!(second.collect::<Vec<_>>(), vec![4, 5, 6, 7, 8, 9]);
+    }
+
+    #[test]
+    fn dropped_half_stops_buffering() {
+        let input = SamplesBuffer::new(1, 44100, vec![1i16, 2, 3]);
+        let (mut first, second) = input.split();
+        drop(second);
+
+        assert_eq!(first.by_ref().collect::<Vec<_>>(), vec![1, 2, 3]);
+        assert!(first.shared.lock().unwrap().buffer.is_empty());
+    }
+}
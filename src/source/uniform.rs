@@ -20,6 +20,10 @@ where
     target_channels: u16,
     target_sample_rate: u32,
     total_duration: Option<Duration>,
+    linear: bool,
+    // Channels and sample rate of the frame being converted.
+    from_channels: u16,
+    from_sample_rate: u32,
 }
 
 impl<I, D> UniformSourceIterator<I, D>
@@ -33,15 +37,41 @@ where
         input: I,
         target_channels: u16,
         target_sample_rate: u32,
+    ) -> UniformSourceIterator<I, D> {
+        UniformSourceIterator::build(input, target_channels, target_sample_rate, false)
+    }
+
+    /// Same as `new`, but the sample rate is always converted with linear interpolation.
+    #[inline]
+    pub(crate) fn new_linear(
+        input: I,
+        target_channels: u16,
+        target_sample_rate: u32,
+    ) -> UniformSourceIterator<I, D> {
+        UniformSourceIterator::build(input, target_channels, target_sample_rate, true)
+    }
+
+    #[inline]
+    fn build(
+        input: I,
+        target_channels: u16,
+        target_sample_rate: u32,
+        linear: bool,
     ) -> UniformSourceIterator<I, D> {
         let total_duration = input.total_duration();
-        let input = UniformSourceIterator::bootstrap(input, target_channels, target_sample_rate);
+        let from_channels = input.channels();
+        let from_sample_rate = input.sample_rate();
+        let input =
+            UniformSourceIterator::bootstrap(input, target_channels, target_sample_rate, linear);
 
         UniformSourceIterator {
             inner: Some(input),
             target_channels,
             target_sample_rate,
             total_duration,
+            linear,
+            from_channels,
+            from_sample_rate,
         }
     }
 
@@ -50,9 +80,9 @@ where
         input: I,
         target_channels: u16,
         target_sample_rate: u32,
+        linear: bool,
     ) -> DataConverter<ChannelCountConverter<SampleRateConverter<Take<I>>>, D> {
-        // Limit the frame length to something reasonable
-        let frame_len = input.current_frame_len().map(|x| x.min(32768));
+        let frame_len = frame_len(&input);
 
         let from_channels = input.channels();
         let from_sample_rate = input.sample_rate();
@@ -61,18 +91,36 @@ where
             iter: input,
             n: frame_len,
         };
-        let input = SampleRateConverter::new(
-            input,
-            cpal::SampleRate(from_sample_rate),
-            cpal::SampleRate(target_sample_rate),
-            from_channels,
-        );
+        let from_sample_rate = cpal::SampleRate(from_sample_rate);
+        let target_sample_rate = cpal::SampleRate(target_sample_rate);
+        let mut input = if linear {
+            SampleRateConverter::new_linear(
+                input,
+                from_sample_rate,
+                target_sample_rate,
+                from_channels,
+            )
+        } else {
+            SampleRateConverter::new(input, from_sample_rate, target_sample_rate, from_channels)
+        };
+        input.set_continued();
         let input = ChannelCountConverter::new(input, from_channels, target_channels);
 
         DataConverter::new(input)
     }
 }
 
+/// Returns the number of samples to convert before looking at the format of the input again.
+#[inline]
+fn frame_len<I>(input: &I) -> Option<usize>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    // Limit the frame length to something reasonable
+    input.current_frame_len().map(|x| x.min(32768))
+}
+
 impl<I, D> Iterator for UniformSourceIterator<I, D>
 where
     I: Source,
@@ -83,25 +131,45 @@ where
 
     #[inline]
     fn next(&mut self) -> Option<D> {
-        if let Some(value) = self.inner.as_mut().unwrap().next() {
-            return Some(value);
-        }
+        loop {
+            if let Some(value) = self.inner.as_mut().unwrap().next() {
+                return Some(value);
+            }
 
-        let input = self
-            .inner
-            .take()
-            .unwrap()
-            .into_inner()
-            .into_inner()
-            .into_inner()
-            .iter;
-
-        let mut input =
-            UniformSourceIterator::bootstrap(input, self.target_channels, self.target_sample_rate);
-
-        let value = input.next();
-        self.inner = Some(input);
-        value
+            let mut converter = self.inner.take().unwrap().into_inner().into_inner();
+
+            if converter.is_waiting() {
+                // The anti-aliasing filter carries on with the next frame if its format is the
+                // same, so that the frame boundary isn't heard.
+                let input = converter.inner_mut();
+                if input.iter.channels() == self.from_channels
+                    && input.iter.sample_rate() == self.from_sample_rate
+                {
+                    input.n = frame_len(&input.iter);
+                    converter.resume();
+                } else {
+                    converter.finish();
+                }
+                let input =
+                    ChannelCountConverter::new(converter, self.from_channels, self.target_channels);
+                self.inner = Some(DataConverter::new(input));
+                continue;
+            }
+
+            let input = converter.into_inner().iter;
+            self.from_channels = input.channels();
+            self.from_sample_rate = input.sample_rate();
+            let mut input = UniformSourceIterator::bootstrap(
+                input,
+                self.target_channels,
+                self.target_sample_rate,
+                self.linear,
+            );
+
+            let value = input.next();
+            self.inner = Some(input);
+            return value;
+        }
     }
 
     #[inline]
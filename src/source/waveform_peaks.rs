@@ -0,0 +1,102 @@
+use cpal::Sample as CpalSample;
+
+use crate::{Sample, Source};
+
+/// Internal function that computes the waveform peaks of a source.
+pub fn waveform_peaks<I>(input: I, buckets: usize) -> Vec<(i16, i16)>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    if buckets == 0 || input.is_infinite() {
+        return Vec::new();
+    }
+
+    // Frames are never split between two buckets, even if the format of the source changes.
+    let mut frames = Vec::new();
+    let mut samples = Vec::new();
+    let mut input = input;
+    loop {
+        let channels = input.channels().max(1) as usize;
+        let frame_len = input.current_frame_len();
+        let mut read = 0;
+        for sample in input.by_ref() {
+            samples.push(sample.to_i16());
+            if samples.len() == channels {
+                frames.push(frame_peak(&samples));
+                samples.clear();
+            }
+            read += 1;
+            if frame_len == Some(read) {
+                break;
+            }
+        }
+        if !samples.is_empty() {
+            frames.push(frame_peak(&samples));
+            samples.clear();
+        }
+        if frame_len != Some(read) || read == 0 {
+            break;
+        }
+    }
+
+    (0..buckets)
+        .map(|bucket| {
+            let start = bucket * frames.len() / buckets;
+            let end = (bucket + 1) * frames.len() / buckets;
+            frames[start..end]
+                .iter()
+                .copied()
+                .reduce(|(min1, max1), (min2, max2)| (min1.min(min2), max1.max(max2)))
+                .unwrap_or((0, 0))
+        })
+        .collect()
+}
+
+/// Returns the lowest and highest sample of a frame.
+fn frame_peak(samples: &[i16]) -> (i16, i16) {
+    let min = samples.iter().copied().min().unwrap_or(0);
+    let max = samples.iter().copied().max().unwrap_or(0);
+    (min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::{from_iter, SineWave, Source};
+
+    #[test]
+    fn peaks_per_bucket() {
+        let data = vec![1i16, -2, 5, 3, -7, 0, 4, 4, 9, -9, 2, 2];
+        let peaks = SamplesBuffer::new(2, 1000, data).waveform_peaks(3);
+        assert_eq!(peaks, [(-2, 5), (-7, 4), (-9, 9)]);
+    }
+
+    #[test]
+    fn bucket_count() {
+        let source = SamplesBuffer::new(1, 1000, (0..1000).collect::<Vec<i16>>());
+        let peaks = source.waveform_peaks(7);
+        assert_eq!(peaks.len(), 7);
+        assert_eq!(peaks[0], (0, 141));
+        assert_eq!(peaks[6], (857, 999));
+
+        // More buckets than frames leaves some buckets empty.
+        let peaks = SamplesBuffer::new(1, 1000, vec![3i16, -3]).waveform_peaks(4);
+        assert_eq!(peaks, [(0, 0), (3, 3), (0, 0), (-3, -3)]);
+    }
+
+    #[test]
+    fn unknown_length() {
+        let source = from_iter(vec![
+            SamplesBuffer::new(1, 1000, vec![1i16, -4, 2]),
+            SamplesBuffer::new(1, 1000, vec![6i16, 0, -1]),
+        ]);
+        assert_eq!(source.total_duration(), None);
+        assert_eq!(source.waveform_peaks(2), [(-4, 2), (-1, 6)]);
+    }
+
+    #[test]
+    fn infinite_source() {
+        assert!(SineWave::new(440.0).waveform_peaks(10).is_empty());
+    }
+}
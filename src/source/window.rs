@@ -0,0 +1,61 @@
+//! Window functions, used to taper blocks of samples before analysing them.
+//!
+//! Each function returns the coefficient of the `n`th sample of a block of `size` samples. The
+//! coefficients are symmetric: the first and the last ones are equal.
+
+use std::f32::consts::PI;
+
+/// A window function that can be applied with [`Source::windowed`](crate::Source::windowed),
+/// or used by [`Source::spectrum_with_window`](crate::Source::spectrum_with_window).
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowFn {
+    /// No window. Gives the sharpest peaks but leaks a lot between frequencies.
+    Rectangular,
+    /// The Hann window, which goes down to zero at both ends.
+    Hann,
+    /// The Hamming window, which goes down to 0.08 at both ends.
+    Hamming,
+    /// The Blackman window, which leaks less than Hann but has wider peaks.
+    Blackman,
+}
+
+impl WindowFn {
+    /// Returns the coefficient of the `n`th sample of a block of `size` samples.
+    #[inline]
+    pub fn coefficient(self, n: usize, size: usize) -> f32 {
+        match self {
+            WindowFn::Rectangular => 1.0,
+            WindowFn::Hann => hann(n, size),
+            WindowFn::Hamming => hamming(n, size),
+            WindowFn::Blackman => blackman(n, size),
+        }
+    }
+}
+
+/// Phase of the `n`th sample of a block of `size` samples, from 0 to 2π.
+#[inline]
+fn phase(n: usize, size: usize) -> f32 {
+    if size < 2 {
+        return PI;
+    }
+    2.0 * PI * n as f32 / (size - 1) as f32
+}
+
+/// Coefficient of the Hann window.
+#[inline]
+pub fn hann(n: usize, size: usize) -> f32 {
+    0.5 - 0.5 * phase(n, size).cos()
+}
+
+/// Coefficient of the Hamming window.
+#[inline]
+pub fn hamming(n: usize, size: usize) -> f32 {
+    0.54 - 0.46 * phase(n, size).cos()
+}
+
+/// Coefficient of the Blackman window.
+#[inline]
+pub fn blackman(n: usize, size: usize) -> f32 {
+    let x = phase(n, size);
+    0.42 - 0.5 * x.cos() + 0.08 * (2.0 * x).cos()
+}
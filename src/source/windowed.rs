@@ -0,0 +1,158 @@
+use std::time::Duration;
+
+use cpal::Sample as CpalSample;
+
+use crate::source::window::WindowFn;
+use crate::{Sample, Source};
+
+/// Internal function that builds a `Windowed` object.
+///
+/// # Panic
+///
+/// Panics if `block_frames` is less than 2.
+pub fn windowed<I>(input: I, window: WindowFn, block_frames: usize) -> Windowed<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    assert!(block_frames >= 2, "blocks must be at least two frames long");
+
+    Windowed {
+        input,
+        coefficients: (0..block_frames)
+            .map(|n| window.coefficient(n, block_frames))
+            .collect(),
+        frame: 0,
+        current_channel: 0,
+    }
+}
+
+/// Filter that multiplies each block of frames by a window function.
+///
+/// The blocks follow each other without overlapping, starting with the first sample of the
+/// source. All the channels of a frame are multiplied by the same coefficient.
+#[derive(Clone, Debug)]
+pub struct Windowed<I> {
+    input: I,
+    // Coefficient of each frame of a block.
+    coefficients: Vec<f32>,
+    // Position of the current frame in its block.
+    frame: usize,
+    // Channel of the next sample returned by the iterator.
+    current_channel: u16,
+}
+
+impl<I> Windowed<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    /// Returns a reference to the inner source.
+    #[inline]
+    pub fn inner(&self) -> &I {
+        &self.input
+    }
+
+    /// Returns a mutable reference to the inner source.
+    #[inline]
+    pub fn inner_mut(&mut self) -> &mut I {
+        &mut self.input
+    }
+
+    /// Returns the inner source.
+    #[inline]
+    pub fn into_inner(self) -> I {
+        self.input
+    }
+}
+
+impl<I> Iterator for Windowed<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    type Item = I::Item;
+
+    #[inline]
+    fn next(&mut self) -> Option<I::Item> {
+        let sample = self.input.next()?;
+        let coefficient = self.coefficients[self.frame];
+
+        self.current_channel += 1;
+        if self.current_channel >= self.input.channels() {
+            self.current_channel = 0;
+            self.frame = (self.frame + 1) % self.coefficients.len();
+        }
+
+        Some(CpalSample::from(&(sample.to_f32() * coefficient)))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.input.size_hint()
+    }
+}
+
+impl<I> ExactSizeIterator for Windowed<I>
+where
+    I: Source + ExactSizeIterator,
+    I::Item: Sample,
+{
+}
+
+impl<I> Source for Windowed<I>
+where
+    I: Source,
+    I::Item: Sample,
+{
+    #[inline]
+    fn current_frame_len(&self) -> Option<usize> {
+        self.input.current_frame_len()
+    }
+
+    #[inline]
+    fn channels(&self) -> u16 {
+        self.input.channels()
+    }
+
+    #[inline]
+    fn sample_rate(&self) -> u32 {
+        self.input.sample_rate()
+    }
+
+    #[inline]
+    fn total_duration(&self) -> Option<Duration> {
+        self.input.total_duration()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::buffer::SamplesBuffer;
+    use crate::source::window::WindowFn;
+    use crate::source::Source;
+
+    #[test]
+    fn hann_edges_are_zero() {
+        let input = SamplesBuffer::new(2, 1000, vec![1.0f32; 2 * 16 * 3]);
+        let output: Vec<f32> = input.windowed(WindowFn::Hann, 16).collect();
+        assert_eq!(output.len(), 2 * 16 * 3);
+
+        for block in output.chunks(2 * 16) {
+            // Both channels of the first and last frames.
+            for &sample in &[block[0], block[1], block[30], block[31]] {
+                assert!(sample.abs() < 1e-6);
+            }
+            // The middle of the block is left almost untouched.
+            assert!(block[16] > 0.98);
+        }
+    }
+
+    #[test]
+    fn hamming_edges() {
+        let input = SamplesBuffer::new(1, 1000, vec![1.0f32; 8]);
+        let output: Vec<f32> = input.windowed(WindowFn::Hamming, 8).collect();
+        assert!((output[0] - 0.08).abs() < 1e-6);
+        assert!((output[7] - 0.08).abs() < 1e-6);
+    }
+}
@@ -57,4 +57,9 @@ where
     fn total_duration(&self) -> Option<Duration> {
         None
     }
+
+    #[inline]
+    fn is_infinite(&self) -> bool {
+        true
+    }
 }
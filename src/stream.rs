@@ -1,9 +1,12 @@
 use std::io::{Read, Seek};
 use std::marker::Sync;
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
 use std::sync::{Arc, Weak};
+use std::time::Duration;
 use std::{error, fmt};
 
 use crate::decoder;
+use crate::dither::{DitherType, Ditherer};
 use crate::dynamic_mixer::{self, DynamicMixerController};
 use crate::sink::Sink;
 use crate::source::Source;
@@ -15,6 +18,10 @@ use cpal::{Sample, SupportedStreamConfig};
 /// If this is dropped playback will end & attached `OutputStreamHandle`s will no longer work.
 pub struct OutputStream {
     mixer: Arc<DynamicMixerController<f32>>,
+    // Latest latency measured by the data callback, in nanoseconds.
+    latency: Arc<AtomicU64>,
+    // The `DitherType` used by the data callback.
+    dither: Arc<AtomicU8>,
     _stream: cpal::Stream,
 }
 
@@ -42,15 +49,107 @@ impl OutputStream {
         device: &cpal::Device,
         config: SupportedStreamConfig,
     ) -> Result<(Self, OutputStreamHandle), StreamError> {
-        let (mixer, _stream) = device.try_new_output_stream_config(config)?;
+        let parts = device.try_new_output_stream_config(config)?;
+        OutputStream::from_parts(parts)
+    }
+
+    /// Returns a new stream & handle using the given device with the given number of channels.
+    ///
+    /// This allows opening a multichannel output, for example a 5.1 output with `6` channels.
+    /// The channels of the sources played on the stream are mapped one to one to the channels
+    /// of the device, so a source with the same number of channels keeps each channel on the
+    /// same speaker. Both are expected to use the usual order of the channels, which is front
+    /// left, front right, center, LFE, back left and back right for 5.1.
+    ///
+    /// There is no routing based on the layout of the sources. A source with fewer channels is
+    /// converted like with `UpmixMode::Duplicate`, so its last channel is copied to the extra
+    /// speakers. Use `Source::force_channels` to choose another conversion before playing it.
+    ///
+    /// The default sample rate of the device is used if it supports it with that number of
+    /// channels. Unlike `try_from_device_config`, this function doesn't fall back to other
+    /// channel counts and returns `StreamError::UnsupportedChannelCount` if the device doesn't
+    /// support the requested number of channels.
+    pub fn try_from_device_with_channels(
+        device: &cpal::Device,
+        channels: u16,
+    ) -> Result<(Self, OutputStreamHandle), StreamError> {
+        let default_rate = device.default_output_config()?.sample_rate();
+        let config = config_with_channels(sorted_output_configs(device)?, channels, default_rate)
+            .ok_or(StreamError::UnsupportedChannelCount(channels))?;
+
+        let parts = device.new_output_stream_with_format(config)?;
+        OutputStream::from_parts(parts)
+    }
+
+    /// Starts playing a stream and returns it with its handle.
+    fn from_parts(parts: StreamParts) -> Result<(Self, OutputStreamHandle), StreamError> {
+        let (mixer, latency, dither, _stream) = parts;
         _stream.play()?;
-        let out = Self { mixer, _stream };
+        let out = Self {
+            mixer,
+            latency,
+            dither,
+            _stream,
+        };
         let handle = OutputStreamHandle {
             mixer: Arc::downgrade(&out.mixer),
         };
         Ok((out, handle))
     }
 
+    /// Returns a new stream & handle using the default output device with the given number of
+    /// channels.
+    ///
+    /// See `try_from_device_with_channels`.
+    pub fn try_default_with_channels(
+        channels: u16,
+    ) -> Result<(Self, OutputStreamHandle), StreamError> {
+        let default_device = cpal::default_host()
+            .default_output_device()
+            .ok_or(StreamError::NoDevice)?;
+
+        Self::try_from_device_with_channels(&default_device, channels)
+    }
+
+    /// Returns true if all the sources played on this stream so far were sent to the device
+    /// untouched.
+    ///
+    /// This is the case when the sources have the channels and sample rate of the device, so that
+    /// they don't have to be resampled. Samples are always converted to `f32` and then to the
+    /// sample format of the device, which doesn't change the value of `f32` and `i16` samples.
+    /// Sources that are played at the same time are still mixed together.
+    #[inline]
+    pub fn is_bit_perfect(&self) -> bool {
+        self.mixer.is_bit_perfect()
+    }
+
+    /// Returns the time it takes for a sample to be played once it was read from the sources.
+    ///
+    /// This is the duration of the buffer requested by the device plus the delay that the device
+    /// reports before playing it. It is measured each time the device asks for samples, and is
+    /// zero until the first time it does. The accuracy depends on the platform: some backends
+    /// don't report the delay of the device itself, or only report an estimate, and the latency of
+    /// the hardware beyond the driver (such as Bluetooth headphones) is usually not included.
+    #[inline]
+    pub fn latency(&self) -> Duration {
+        Duration::from_nanos(self.latency.load(Ordering::Relaxed))
+    }
+
+    /// Sets how the samples are dithered when the device takes integer samples.
+    ///
+    /// The default is `DitherType::None`. Dithering changes the samples, so sources aren't played
+    /// bit-perfect anymore on such devices, whatever `is_bit_perfect` returns.
+    #[inline]
+    pub fn set_dither(&self, dither: DitherType) {
+        self.dither.store(dither.to_u8(), Ordering::Relaxed);
+    }
+
+    /// Returns how the samples are dithered when the device takes integer samples.
+    #[inline]
+    pub fn dither(&self) -> DitherType {
+        DitherType::from_u8(self.dither.load(Ordering::Relaxed))
+    }
+
     /// Return a new stream & handle using the default output device.
     ///
     /// On failure will fallback to trying any non-default output devices.
@@ -138,6 +237,8 @@ pub enum StreamError {
     BuildStreamError(cpal::BuildStreamError),
     SupportedStreamConfigsError(cpal::SupportedStreamConfigsError),
     NoDevice,
+    /// The device doesn't support the requested number of channels.
+    UnsupportedChannelCount(u16),
 }
 
 impl From<cpal::DefaultStreamConfigError> for StreamError {
@@ -172,6 +273,9 @@ impl fmt::Display for StreamError {
             Self::DefaultStreamConfigError(e) => e.fmt(f),
             Self::SupportedStreamConfigsError(e) => e.fmt(f),
             Self::NoDevice => write!(f, "NoDevice"),
+            Self::UnsupportedChannelCount(channels) => {
+                write!(f, "Unsupported channel count: {}", channels)
+            }
         }
     }
 }
@@ -184,6 +288,7 @@ impl error::Error for StreamError {
             Self::DefaultStreamConfigError(e) => Some(e),
             Self::SupportedStreamConfigsError(e) => Some(e),
             Self::NoDevice => None,
+            Self::UnsupportedChannelCount(_) => None,
         }
     }
 }
@@ -193,28 +298,64 @@ pub(crate) trait CpalDeviceExt {
     fn new_output_stream_with_format(
         &self,
         format: cpal::SupportedStreamConfig,
-    ) -> Result<(Arc<DynamicMixerController<f32>>, cpal::Stream), cpal::BuildStreamError>;
+    ) -> Result<StreamParts, cpal::BuildStreamError>;
 
     fn try_new_output_stream_config(
         &self,
         config: cpal::SupportedStreamConfig,
-    ) -> Result<(Arc<DynamicMixerController<f32>>, cpal::Stream), StreamError>;
+    ) -> Result<StreamParts, StreamError>;
+}
+
+/// The mixer feeding a stream, the latency measured by its callback, its dithering and the
+/// stream itself.
+pub(crate) type StreamParts = (
+    Arc<DynamicMixerController<f32>>,
+    Arc<AtomicU64>,
+    Arc<AtomicU8>,
+    cpal::Stream,
+);
+
+/// Returns the latency of a callback that was asked for `samples` samples.
+fn output_latency(
+    samples: usize,
+    channels: u16,
+    sample_rate: u32,
+    device_delay: Option<Duration>,
+) -> Duration {
+    let frames = samples / channels.max(1) as usize;
+    let buffer = Duration::from_secs_f64(frames as f64 / sample_rate.max(1) as f64);
+    buffer + device_delay.unwrap_or_default()
 }
 
 impl CpalDeviceExt for cpal::Device {
     fn new_output_stream_with_format(
         &self,
         format: cpal::SupportedStreamConfig,
-    ) -> Result<(Arc<DynamicMixerController<f32>>, cpal::Stream), cpal::BuildStreamError> {
+    ) -> Result<StreamParts, cpal::BuildStreamError> {
         let (mixer_tx, mut mixer_rx) =
             dynamic_mixer::mixer::<f32>(format.channels(), format.sample_rate().0);
 
+        let latency = Arc::new(AtomicU64::new(0));
+        let callback_latency = latency.clone();
+        let (channels, sample_rate) = (format.channels(), format.sample_rate().0);
+        let measure_latency = move |samples: usize, info: &cpal::OutputCallbackInfo| {
+            let timestamp = info.timestamp();
+            let delay = timestamp.playback.duration_since(&timestamp.callback);
+            let latency = output_latency(samples, channels, sample_rate, delay);
+            callback_latency.store(latency.as_nanos() as u64, Ordering::Relaxed);
+        };
+
+        let dither = Arc::new(AtomicU8::new(DitherType::None.to_u8()));
+        let callback_dither = dither.clone();
+        let mut ditherer = Ditherer::new(channels);
+
         let error_callback = |err| eprintln!("an error occurred on output stream: {}", err);
 
         match format.sample_format() {
             cpal::SampleFormat::F32 => self.build_output_stream::<f32, _, _>(
                 &format.config(),
-                move |data, _| {
+                move |data, info| {
+                    measure_latency(data.len(), info);
                     data.iter_mut()
                         .for_each(|d| *d = mixer_rx.next().unwrap_or(0f32))
                 },
@@ -222,32 +363,38 @@ impl CpalDeviceExt for cpal::Device {
             ),
             cpal::SampleFormat::I16 => self.build_output_stream::<i16, _, _>(
                 &format.config(),
-                move |data, _| {
-                    data.iter_mut()
-                        .for_each(|d| *d = mixer_rx.next().map(|s| s.to_i16()).unwrap_or(0i16))
+                move |data, info| {
+                    measure_latency(data.len(), info);
+                    let dither = DitherType::from_u8(callback_dither.load(Ordering::Relaxed));
+                    data.iter_mut().for_each(|d| {
+                        *d = ditherer.convert(dither, mixer_rx.next().unwrap_or(0f32))
+                    })
                 },
                 error_callback,
             ),
             cpal::SampleFormat::U16 => self.build_output_stream::<u16, _, _>(
                 &format.config(),
-                move |data, _| {
+                move |data, info| {
+                    measure_latency(data.len(), info);
+                    let dither = DitherType::from_u8(callback_dither.load(Ordering::Relaxed));
                     data.iter_mut().for_each(|d| {
-                        *d = mixer_rx
-                            .next()
-                            .map(|s| s.to_u16())
-                            .unwrap_or(u16::max_value() / 2)
+                        *d = match (dither, mixer_rx.next()) {
+                            (_, None) => u16::max_value() / 2,
+                            (DitherType::None, Some(s)) => s.to_u16(),
+                            (dither, Some(s)) => ditherer.convert(dither, s).to_u16(),
+                        }
                     })
                 },
                 error_callback,
             ),
         }
-        .map(|stream| (mixer_tx, stream))
+        .map(|stream| (mixer_tx, latency, dither, stream))
     }
 
     fn try_new_output_stream_config(
         &self,
         config: SupportedStreamConfig,
-    ) -> Result<(Arc<DynamicMixerController<f32>>, cpal::Stream), StreamError> {
+    ) -> Result<StreamParts, StreamError> {
         self.new_output_stream_with_format(config).or_else(|err| {
             // look through all supported formats to see if another works
             supported_output_formats(self)?
@@ -264,9 +411,7 @@ fn supported_output_formats(
 ) -> Result<impl Iterator<Item = cpal::SupportedStreamConfig>, StreamError> {
     const HZ_44100: cpal::SampleRate = cpal::SampleRate(44_100);
 
-    let mut supported: Vec<_> = device.supported_output_configs()?.collect();
-    supported.sort_by(|a, b| b.cmp_default_heuristics(a));
-
+    let supported = sorted_output_configs(device)?;
     Ok(supported.into_iter().flat_map(|sf| {
         let max_rate = sf.max_sample_rate();
         let min_rate = sf.min_sample_rate();
@@ -278,3 +423,83 @@ fn supported_output_formats(
         formats
     }))
 }
+
+/// The output configurations supported by the device, the best ones first.
+fn sorted_output_configs(
+    device: &cpal::Device,
+) -> Result<Vec<cpal::SupportedStreamConfigRange>, StreamError> {
+    let mut supported: Vec<_> = device.supported_output_configs()?.collect();
+    supported.sort_by(|a, b| b.cmp_default_heuristics(a));
+    Ok(supported)
+}
+
+/// Picks the first configuration of `supported` with `channels` channels, at `default_rate` if
+/// one of them supports it and at its highest sample rate otherwise.
+fn config_with_channels(
+    supported: Vec<cpal::SupportedStreamConfigRange>,
+    channels: u16,
+    default_rate: cpal::SampleRate,
+) -> Option<cpal::SupportedStreamConfig> {
+    let mut supported = supported
+        .into_iter()
+        .filter(|range| range.channels() == channels)
+        .peekable();
+    let first = supported.peek().cloned();
+
+    supported
+        .find(|range| {
+            range.min_sample_rate() <= default_rate && default_rate <= range.max_sample_rate()
+        })
+        .map(|range| range.with_sample_rate(default_rate))
+        .or_else(|| first.map(|range| range.with_max_sample_rate()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use cpal::{SampleFormat, SampleRate, SupportedBufferSize, SupportedStreamConfigRange};
+
+    use super::{config_with_channels, output_latency};
+
+    #[test]
+    fn latency_of_buffer() {
+        // 512 frames of stereo at 48 kHz, without any delay reported by the device.
+        let latency = output_latency(1024, 2, 48000, None);
+        assert!((latency.as_secs_f64() - 512.0 / 48000.0).abs() < 1e-9);
+
+        let latency = output_latency(1024, 2, 48000, Some(Duration::from_millis(20)));
+        assert!(latency > Duration::from_millis(30) && latency < Duration::from_millis(31));
+    }
+
+    #[test]
+    fn pick_config_with_channels() {
+        let range = |channels, min, max| {
+            SupportedStreamConfigRange::new(
+                channels,
+                SampleRate(min),
+                SampleRate(max),
+                SupportedBufferSize::Unknown,
+                SampleFormat::F32,
+            )
+        };
+        let supported = vec![
+            range(2, 44100, 48000),
+            range(6, 96000, 96000),
+            range(6, 44100, 48000),
+        ];
+
+        // The default rate is used when a configuration with that many channels supports it.
+        let config = config_with_channels(supported.clone(), 6, SampleRate(48000)).unwrap();
+        assert_eq!(config.channels(), 6);
+        assert_eq!(config.sample_rate(), SampleRate(48000));
+
+        // Otherwise the first one is used at its highest rate.
+        let config = config_with_channels(supported.clone(), 6, SampleRate(22050)).unwrap();
+        assert_eq!(config.channels(), 6);
+        assert_eq!(config.sample_rate(), SampleRate(96000));
+
+        // There is no fallback to other channel counts.
+        assert!(config_with_channels(supported, 8, SampleRate(48000)).is_none());
+    }
+}
@@ -0,0 +1,18 @@
+#![cfg(feature = "mp3")]
+
+use std::io::BufReader;
+
+use rodio::Source;
+
+#[test]
+fn test_mp3_gapless() {
+    let file = std::fs::File::open("assets/music.mp3").unwrap();
+    let decoder = rodio::Decoder::new_mp3(BufReader::new(file)).unwrap();
+    assert_eq!(decoder.channels(), 2);
+
+    // The Xing header announces 390 MPEG frames of 1152 samples. The LAME tag gives an encoder
+    // delay of 576 samples and a padding of 984 samples, from which the decoder delay of the
+    // specification (529 samples) is moved from the end to the start.
+    let expected_frames = 390 * 1152 - (576 + 529) - (984 - 529);
+    assert_eq!(decoder.count(), expected_frames * 2);
+}